@@ -0,0 +1,24 @@
+-- Repeated full imports could store the same Help Scout article more than once.
+-- Keep the most recently updated copy of each article before enforcing uniqueness.
+CREATE TEMP TABLE duplicate_articles AS
+SELECT id
+FROM (
+    SELECT id,
+           ROW_NUMBER() OVER (
+               PARTITION BY helpscout_article_id
+               ORDER BY updated_at DESC, created_at DESC
+           ) AS row_number
+    FROM articles
+    WHERE helpscout_article_id IS NOT NULL
+) ranked
+WHERE row_number > 1;
+
+DELETE FROM embeddings WHERE article_id IN (SELECT id FROM duplicate_articles);
+DELETE FROM content_versions WHERE article_id IN (SELECT id FROM duplicate_articles);
+-- article_chunks rows are removed by ON DELETE CASCADE
+DELETE FROM articles WHERE id IN (SELECT id FROM duplicate_articles);
+
+DROP TABLE duplicate_articles;
+
+-- Allow upserting articles by their Help Scout ID
+CREATE UNIQUE INDEX idx_articles_helpscout_article_id ON articles(helpscout_article_id);
@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS idx_article_removals_removed_at;
+DROP TABLE IF EXISTS article_removals;
+
+DROP INDEX IF EXISTS idx_articles_archived_at;
+ALTER TABLE articles
+DROP COLUMN IF EXISTS archived_at;
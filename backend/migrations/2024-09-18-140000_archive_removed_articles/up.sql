@@ -0,0 +1,18 @@
+-- Soft delete for articles that were deleted or unpublished in Help Scout
+ALTER TABLE articles
+ADD COLUMN archived_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX idx_articles_archived_at ON articles(archived_at);
+
+-- Audit trail of articles removed from search by the sync reconciliation pass
+CREATE TABLE article_removals (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    article_id UUID NOT NULL,
+    helpscout_article_id VARCHAR,
+    title VARCHAR(255) NOT NULL,
+    reason VARCHAR(50) NOT NULL,
+    removed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (article_id) REFERENCES articles(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_article_removals_removed_at ON article_removals(removed_at);
@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS idx_job_status_history_job_id;
+DROP TABLE IF EXISTS job_status_history;
+
+DROP INDEX IF EXISTS idx_jobs_status_run_at;
+DROP TABLE IF EXISTS jobs;
@@ -0,0 +1,4 @@
+-- Jobs fanned out by another job (e.g. SyncArticle jobs of a sync run)
+ALTER TABLE jobs ADD COLUMN parent_id UUID REFERENCES jobs(id) ON DELETE CASCADE;
+
+CREATE INDEX idx_jobs_parent_id ON jobs(parent_id);
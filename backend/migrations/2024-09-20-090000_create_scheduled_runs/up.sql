@@ -0,0 +1,13 @@
+-- One row per time the scheduler fired, whether it enqueued a job or not
+CREATE TABLE scheduled_runs (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    schedule_name VARCHAR(100) NOT NULL,
+    job_id UUID,
+    status VARCHAR(50) NOT NULL,
+    message TEXT,
+    scheduled_for TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (job_id) REFERENCES jobs(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_scheduled_runs_schedule_name ON scheduled_runs(schedule_name, created_at);
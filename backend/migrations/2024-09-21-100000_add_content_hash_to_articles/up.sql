@@ -0,0 +1,4 @@
+-- SHA-256 of the source file for articles imported from a Markdown directory,
+-- so re-imports only rewrite files that changed
+ALTER TABLE articles
+ADD COLUMN content_hash VARCHAR(64);
@@ -0,0 +1,64 @@
+-- Drops the per-dimension indexes, including those added for other models
+DO $$
+DECLARE
+    index_name TEXT;
+BEGIN
+    FOR index_name IN
+        SELECT indexname FROM pg_indexes
+        WHERE schemaname = current_schema()
+        AND tablename IN ('embeddings', 'articles', 'collections')
+        AND indexname ~ '_[0-9]+$'
+        AND indexdef LIKE '%vector_dims(%'
+    LOOP
+        EXECUTE format('DROP INDEX %I', index_name);
+    END LOOP;
+END $$;
+
+ALTER TABLE collections DROP COLUMN metadata_embedding_model;
+ALTER TABLE articles DROP COLUMN metadata_embedding_model;
+
+-- Vectors that don't fit vector(384) can't be kept
+UPDATE collections SET paragraph_description_embedding = NULL
+WHERE vector_dims(paragraph_description_embedding) <> 384;
+UPDATE collections SET bullet_points_embedding = NULL
+WHERE vector_dims(bullet_points_embedding) <> 384;
+UPDATE collections SET keywords_embedding = NULL
+WHERE vector_dims(keywords_embedding) <> 384;
+
+ALTER TABLE collections
+ALTER COLUMN paragraph_description_embedding TYPE vector(384),
+ALTER COLUMN bullet_points_embedding TYPE vector(384),
+ALTER COLUMN keywords_embedding TYPE vector(384);
+
+CREATE INDEX idx_collections_paragraph_description_embedding ON collections USING ivfflat (paragraph_description_embedding vector_cosine_ops);
+CREATE INDEX idx_collections_bullet_points_embedding ON collections USING ivfflat (bullet_points_embedding vector_cosine_ops);
+CREATE INDEX idx_collections_keywords_embedding ON collections USING ivfflat (keywords_embedding vector_cosine_ops);
+
+UPDATE articles SET paragraph_description_embedding = NULL
+WHERE vector_dims(paragraph_description_embedding) <> 384;
+UPDATE articles SET bullet_points_embedding = NULL
+WHERE vector_dims(bullet_points_embedding) <> 384;
+UPDATE articles SET keywords_embedding = NULL
+WHERE vector_dims(keywords_embedding) <> 384;
+
+ALTER TABLE articles
+ALTER COLUMN paragraph_description_embedding TYPE vector(384),
+ALTER COLUMN bullet_points_embedding TYPE vector(384),
+ALTER COLUMN keywords_embedding TYPE vector(384);
+
+CREATE INDEX idx_articles_paragraph_description_embedding ON articles USING ivfflat (paragraph_description_embedding vector_cosine_ops);
+CREATE INDEX idx_articles_bullet_points_embedding ON articles USING ivfflat (bullet_points_embedding vector_cosine_ops);
+CREATE INDEX idx_articles_keywords_embedding ON articles USING ivfflat (keywords_embedding vector_cosine_ops);
+
+DROP INDEX IF EXISTS idx_embeddings_model;
+
+UPDATE article_chunks SET embedding_id = NULL
+WHERE embedding_id IN (SELECT id FROM embeddings WHERE dimensions <> 384);
+DELETE FROM embeddings WHERE dimensions <> 384;
+
+ALTER TABLE embeddings
+DROP COLUMN dimensions,
+DROP COLUMN model,
+ALTER COLUMN embedding_vector TYPE vector(384);
+
+CREATE INDEX ON embeddings USING ivfflat (embedding_vector vector_cosine_ops) WITH (lists = 100);
@@ -0,0 +1,73 @@
+-- Let models of any dimension live side by side. Every chunk embedding records
+-- the model and dimension it was generated with, and search only compares a
+-- query with vectors from the same model. Existing rows came from the Python
+-- service's all-MiniLM-L6-v2.
+DROP INDEX IF EXISTS embeddings_embedding_vector_idx;
+
+ALTER TABLE embeddings
+ALTER COLUMN embedding_vector TYPE vector,
+ADD COLUMN model VARCHAR(255) NOT NULL DEFAULT 'all-MiniLM-L6-v2',
+ADD COLUMN dimensions INTEGER NOT NULL DEFAULT 384;
+
+ALTER TABLE embeddings
+ALTER COLUMN model DROP DEFAULT,
+ALTER COLUMN dimensions DROP DEFAULT;
+
+CREATE INDEX idx_embeddings_model ON embeddings(model);
+
+-- Metadata vectors are regenerated in place and tagged with their model, so
+-- searches skip those from another model than the query's
+DROP INDEX IF EXISTS idx_articles_paragraph_description_embedding;
+DROP INDEX IF EXISTS idx_articles_bullet_points_embedding;
+DROP INDEX IF EXISTS idx_articles_keywords_embedding;
+DROP INDEX IF EXISTS idx_collections_paragraph_description_embedding;
+DROP INDEX IF EXISTS idx_collections_bullet_points_embedding;
+DROP INDEX IF EXISTS idx_collections_keywords_embedding;
+
+ALTER TABLE articles
+ALTER COLUMN paragraph_description_embedding TYPE vector,
+ALTER COLUMN bullet_points_embedding TYPE vector,
+ALTER COLUMN keywords_embedding TYPE vector,
+ADD COLUMN metadata_embedding_model VARCHAR(255);
+
+ALTER TABLE collections
+ALTER COLUMN paragraph_description_embedding TYPE vector,
+ALTER COLUMN bullet_points_embedding TYPE vector,
+ALTER COLUMN keywords_embedding TYPE vector,
+ADD COLUMN metadata_embedding_model VARCHAR(255);
+
+UPDATE articles SET metadata_embedding_model = 'all-MiniLM-L6-v2'
+WHERE paragraph_description_embedding IS NOT NULL
+OR bullet_points_embedding IS NOT NULL
+OR keywords_embedding IS NOT NULL;
+
+UPDATE collections SET metadata_embedding_model = 'all-MiniLM-L6-v2'
+WHERE paragraph_description_embedding IS NOT NULL
+OR bullet_points_embedding IS NOT NULL
+OR keywords_embedding IS NOT NULL;
+
+-- Indexes need a fixed dimension, so each vector column gets one partial
+-- index per dimension, over the vectors cast to it. These cover the existing
+-- 384-dimensional vectors; the backend adds the ones for other models'
+-- dimensions when it starts, see models::embedding::ensure_vector_indexes.
+CREATE INDEX idx_embeddings_embedding_vector_384 ON embeddings
+USING hnsw ((embedding_vector::vector(384)) vector_cosine_ops)
+WHERE vector_dims(embedding_vector) = 384;
+CREATE INDEX idx_articles_paragraph_description_embedding_384 ON articles
+USING hnsw ((paragraph_description_embedding::vector(384)) vector_cosine_ops)
+WHERE vector_dims(paragraph_description_embedding) = 384;
+CREATE INDEX idx_articles_bullet_points_embedding_384 ON articles
+USING hnsw ((bullet_points_embedding::vector(384)) vector_cosine_ops)
+WHERE vector_dims(bullet_points_embedding) = 384;
+CREATE INDEX idx_articles_keywords_embedding_384 ON articles
+USING hnsw ((keywords_embedding::vector(384)) vector_cosine_ops)
+WHERE vector_dims(keywords_embedding) = 384;
+CREATE INDEX idx_collections_paragraph_description_embedding_384 ON collections
+USING hnsw ((paragraph_description_embedding::vector(384)) vector_cosine_ops)
+WHERE vector_dims(paragraph_description_embedding) = 384;
+CREATE INDEX idx_collections_bullet_points_embedding_384 ON collections
+USING hnsw ((bullet_points_embedding::vector(384)) vector_cosine_ops)
+WHERE vector_dims(bullet_points_embedding) = 384;
+CREATE INDEX idx_collections_keywords_embedding_384 ON collections
+USING hnsw ((keywords_embedding::vector(384)) vector_cosine_ops)
+WHERE vector_dims(keywords_embedding) = 384;
@@ -0,0 +1,14 @@
+-- Keep only the active generation's chunks and embeddings
+DELETE FROM embeddings
+WHERE id IN (
+    SELECT embedding_id FROM article_chunks
+    WHERE generation_id NOT IN (SELECT id FROM embedding_generations WHERE status = 'active')
+);
+
+DELETE FROM article_chunks
+WHERE generation_id NOT IN (SELECT id FROM embedding_generations WHERE status = 'active');
+
+DROP INDEX IF EXISTS idx_article_chunks_generation_id;
+ALTER TABLE article_chunks DROP COLUMN generation_id;
+
+DROP TABLE embedding_generations;
@@ -0,0 +1,37 @@
+-- Chunks belong to a generation of the search index. Re-embedding builds a
+-- new generation next to the active one and switches to it once complete, so
+-- search keeps reading the old chunks meanwhile. Search only reads chunks of
+-- the active generation.
+CREATE TABLE embedding_generations (
+    id SERIAL PRIMARY KEY,
+    model VARCHAR(255) NOT NULL,
+    status VARCHAR(50) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    activated_at TIMESTAMP WITH TIME ZONE
+);
+
+-- At most one generation is active at a time
+CREATE UNIQUE INDEX idx_embedding_generations_active
+ON embedding_generations(status) WHERE status = 'active';
+
+-- Existing chunks become the first, active generation
+INSERT INTO embedding_generations (model, status, activated_at)
+VALUES (
+    COALESCE(
+        (SELECT model FROM embeddings GROUP BY model ORDER BY COUNT(*) DESC LIMIT 1),
+        'all-MiniLM-L6-v2'
+    ),
+    'active',
+    CURRENT_TIMESTAMP
+);
+
+ALTER TABLE article_chunks
+ADD COLUMN generation_id INTEGER REFERENCES embedding_generations(id) ON DELETE CASCADE;
+
+UPDATE article_chunks
+SET generation_id = (SELECT id FROM embedding_generations WHERE status = 'active');
+
+ALTER TABLE article_chunks
+ALTER COLUMN generation_id SET NOT NULL;
+
+CREATE INDEX idx_article_chunks_generation_id ON article_chunks(generation_id);
@@ -0,0 +1,14 @@
+-- Vectors by model and SHA-256 of the embedded text, so text that was
+-- embedded before is not sent to the embedding backend again, e.g. unchanged
+-- chunks when re-embedding. The least recently used entries are evicted once
+-- there are more than embedding.cache_size.
+CREATE TABLE embedding_cache (
+    content_hash VARCHAR(64) NOT NULL,
+    model VARCHAR(255) NOT NULL,
+    embedding_vector vector NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_used_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (content_hash, model)
+);
+
+CREATE INDEX idx_embedding_cache_last_used_at ON embedding_cache(last_used_at);
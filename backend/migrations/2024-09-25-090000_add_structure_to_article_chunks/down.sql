@@ -0,0 +1,4 @@
+ALTER TABLE article_chunks
+DROP COLUMN heading_path,
+DROP COLUMN start_offset,
+DROP COLUMN end_offset;
@@ -0,0 +1,7 @@
+-- Where each chunk sits in its article: the headings above it and its
+-- character range in markdown_content. Title chunks and chunks from before
+-- structure-aware chunking have no range.
+ALTER TABLE article_chunks
+ADD COLUMN heading_path TEXT[] NOT NULL DEFAULT '{}',
+ADD COLUMN start_offset INTEGER,
+ADD COLUMN end_offset INTEGER;
@@ -0,0 +1,6 @@
+DROP INDEX idx_articles_search_vector;
+
+ALTER TABLE articles
+DROP COLUMN search_vector;
+
+DROP FUNCTION immutable_array_to_string(TEXT[], TEXT);
@@ -0,0 +1,16 @@
+-- Full-text search over each article, weighting the title above the
+-- generated keywords, then the bullet points, then the body.
+-- array_to_string is only STABLE, which generated columns don't accept.
+CREATE FUNCTION immutable_array_to_string(arr TEXT[], sep TEXT) RETURNS TEXT
+    LANGUAGE sql IMMUTABLE PARALLEL SAFE
+    AS $$ SELECT array_to_string(arr, sep) $$;
+
+ALTER TABLE articles
+ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', coalesce(title, '')), 'A') ||
+    setweight(to_tsvector('english', coalesce(immutable_array_to_string(keywords, ' '), '')), 'B') ||
+    setweight(to_tsvector('english', coalesce(immutable_array_to_string(bullet_points, ' '), '')), 'C') ||
+    setweight(to_tsvector('english', coalesce(markdown_content, '')), 'D')
+) STORED;
+
+CREATE INDEX idx_articles_search_vector ON articles USING GIN (search_vector);
@@ -0,0 +1,3 @@
+-- Refreshed by the worker running a job. A running job whose heartbeat is
+-- older than the lease belongs to a worker that died and may be reclaimed.
+ALTER TABLE jobs ADD COLUMN heartbeat_at TIMESTAMP WITH TIME ZONE;
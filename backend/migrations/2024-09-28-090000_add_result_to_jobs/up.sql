@@ -0,0 +1,3 @@
+-- What a completed job produced, e.g. the outcome of a SyncArticle job, so a
+-- parent job can report what its children did.
+ALTER TABLE jobs ADD COLUMN result JSONB;
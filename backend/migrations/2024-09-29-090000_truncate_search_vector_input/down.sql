@@ -0,0 +1,14 @@
+DROP INDEX idx_articles_search_vector;
+
+ALTER TABLE articles
+DROP COLUMN search_vector;
+
+ALTER TABLE articles
+ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', coalesce(title, '')), 'A') ||
+    setweight(to_tsvector('english', coalesce(immutable_array_to_string(keywords, ' '), '')), 'B') ||
+    setweight(to_tsvector('english', coalesce(immutable_array_to_string(bullet_points, ' '), '')), 'C') ||
+    setweight(to_tsvector('english', coalesce(markdown_content, '')), 'D')
+) STORED;
+
+CREATE INDEX idx_articles_search_vector ON articles USING GIN (search_vector);
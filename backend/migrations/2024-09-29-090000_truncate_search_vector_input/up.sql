@@ -0,0 +1,17 @@
+-- to_tsvector fails on text whose vector would exceed 1MB, which made saving
+-- a very long article fail. Only the start of the body is indexed; articles
+-- rarely say anything in their last pages that their first ones don't.
+DROP INDEX idx_articles_search_vector;
+
+ALTER TABLE articles
+DROP COLUMN search_vector;
+
+ALTER TABLE articles
+ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', coalesce(title, '')), 'A') ||
+    setweight(to_tsvector('english', coalesce(immutable_array_to_string(keywords, ' '), '')), 'B') ||
+    setweight(to_tsvector('english', coalesce(immutable_array_to_string(bullet_points, ' '), '')), 'C') ||
+    setweight(to_tsvector('english', left(coalesce(markdown_content, ''), 100000)), 'D')
+) STORED;
+
+CREATE INDEX idx_articles_search_vector ON articles USING GIN (search_vector);
@@ -0,0 +1,4 @@
+-- Set when an operator cancels a running job. The worker holding the job may
+-- run in another process, so it polls this flag with its heartbeat and stops
+-- at its next checkpoint.
+ALTER TABLE jobs ADD COLUMN cancel_requested BOOLEAN NOT NULL DEFAULT FALSE;
@@ -52,6 +52,26 @@ def embed_text():
         logger.error(f"Error generating embedding: {str(e)}")
         return jsonify({'error': f'Internal server error: {str(e)}'}), 500
 
+@app.route('/embed_batch', methods=['POST'])
+def embed_batch():
+    try:
+        data = request.json
+        texts = data.get('texts')
+        if not isinstance(texts, list) or not all(isinstance(text, str) for text in texts):
+            logger.warning("Batch request without a list of texts")
+            return jsonify({'error': 'texts must be a list of strings'}), 400
+        if not texts:
+            return jsonify({'embeddings': []})
+
+        # Encode the whole request at once; the model batches internally
+        embeddings = model.encode(texts)
+        logger.info(f"Successfully generated {len(texts)} embeddings")
+
+        return jsonify({'embeddings': embeddings.tolist()})
+    except Exception as e:
+        logger.error(f"Error generating batch embeddings: {str(e)}")
+        return jsonify({'error': f'Internal server error: {str(e)}'}), 500
+
 @app.route('/test-embed')
 def test_embed():
     try:
@@ -0,0 +1,382 @@
+//! Maintenance commands for cron jobs and scripts. Each command runs in the
+//! foreground, prints per-item progress to stderr and exits non-zero when
+//! anything failed. `sync` goes through the job queue like `/parse`, so it
+//! shows up in job status and never runs alongside another sync; it only
+//! runs its own jobs, leaving the rest of the queue to the server.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use diesel::dsl::count_star;
+use diesel::prelude::*;
+use dotenv::dotenv;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+use uuid::Uuid;
+
+use backend::config::{self, Config};
+use backend::db::{self, DbPool};
+use backend::job::{Job, JobControl, JobEvent, JobEventKind, JobProgress, JobQueue};
+use backend::models::{Article, ChildJobCounts, EmbeddingGeneration, JobInfo, JobStatus};
+use backend::schema::{article_chunks, articles, collections, embeddings};
+use backend::services::archive::{export_archive, import_archive};
+use backend::services::data_processor::SyncMode;
+use backend::services::{DataProcessor, EmbeddingService, MetadataGenerator};
+
+#[derive(Parser)]
+#[command(name = "helpdoc-admin", about = "HelpDocApp maintenance commands")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Sync collections and articles from the configured document source
+    Sync {
+        /// Fetch every article instead of only the ones changed upstream
+        #[arg(long)]
+        full: bool,
+    },
+    /// Regenerate article chunks and embeddings into a new generation and
+    /// switch search to it once complete
+    Reembed {
+        /// Only embed active articles that have no chunks yet, into the
+        /// active generation
+        #[arg(long)]
+        missing: bool,
+    },
+    /// Switch search back to the embedding generation active before the last
+    /// re-embedding
+    Rollback,
+    /// Generate AI metadata for active articles
+    GenerateMetadata {
+        /// Defaults to `metadata.batch_limit`
+        #[arg(long)]
+        limit: Option<usize>,
+        /// Defaults to `metadata.concurrency`
+        #[arg(long)]
+        concurrency: Option<usize>,
+    },
+    /// Retry metadata generation for articles that failed before
+    RetryFailed {
+        /// Defaults to `metadata.concurrency`
+        #[arg(long)]
+        concurrency: Option<usize>,
+    },
+    /// Delete all collections, articles, chunks and embeddings
+    Clear {
+        /// Required, as this cannot be undone
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Print row counts and embedding/metadata coverage
+    Stats,
+    /// Write the knowledge base to a portable archive
+    Export {
+        file: PathBuf,
+        /// Include metadata vectors, chunks and chunk embeddings
+        #[arg(long)]
+        embeddings: bool,
+    },
+    /// Load an archive written by `export`
+    Import { file: PathBuf },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    dotenv().ok();
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    match run(Cli::parse().command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(command: Command) -> Result<()> {
+    let config = Config::load()?;
+    config.validate()?;
+    config::init(config.clone())?;
+    let pool = Arc::new(db::init_pool_with(&config.database));
+    let control = JobControl::new();
+    let cancel = control.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("Cancelling after the current item...");
+            cancel.cancel();
+        }
+    });
+    let progress = progress_printer();
+
+    match command {
+        Command::Sync { full } => {
+            let mode = if full {
+                SyncMode::Full
+            } else {
+                SyncMode::Incremental
+            };
+            let data_processor = Arc::new(DataProcessor::new(pool.clone()).await?);
+            let metadata_generator = Arc::new(MetadataGenerator::with_processor(
+                data_processor.clone(),
+                pool.clone(),
+                config.metadata.concurrency,
+            ));
+            let job_queue = JobQueue::without_workers(pool.clone(), &config.jobs);
+            let job_id = job_queue.enqueue_exclusive(Job::SyncAll(mode)).await?;
+            eprintln!("Enqueued sync as job {}", job_id);
+            // Jobs this process holds when it exits are only reclaimed once
+            // their lease expires, so it must not claim the server's jobs.
+            job_queue.spawn_job_tree_workers(job_id, data_processor, metadata_generator);
+
+            let (job, counts) = wait_for_job(&job_queue, job_id, &control).await?;
+            let outcomes: Vec<String> = counts
+                .outcomes
+                .iter()
+                .map(|(outcome, count)| format!("{}: {}", outcome, count))
+                .collect();
+            println!(
+                "Sync finished ({}, failed: {})",
+                outcomes.join(", "),
+                counts.failed
+            );
+            anyhow::ensure!(
+                job.status()? == JobStatus::Completed,
+                "Sync job {} ended as {}: {}",
+                job.id,
+                job.status,
+                job.last_error.as_deref().unwrap_or("no error recorded")
+            );
+            anyhow::ensure!(
+                counts.failed == 0,
+                "{} articles failed to sync",
+                counts.failed
+            );
+        }
+        Command::Reembed { missing } => {
+            let service = EmbeddingService::new()?;
+            if missing {
+                service
+                    .backfill_embeddings(&pool, &control, &progress)
+                    .await?;
+            } else {
+                let mut conn = pool.get().context("Failed to get DB connection")?;
+                service
+                    .reembed_all_articles(&mut conn, &control, &progress)
+                    .await
+                    .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+            }
+            finish("Embedding", &progress)?;
+        }
+        Command::Rollback => {
+            let mut conn = pool.get().context("Failed to get DB connection")?;
+            let generation = EmbeddingGeneration::rollback(&mut conn)?
+                .context("No previous embedding generation to roll back to")?;
+            println!(
+                "Search now uses embedding generation {} ({})",
+                generation.id, generation.model
+            );
+        }
+        Command::GenerateMetadata { limit, concurrency } => {
+            let concurrency = concurrency.unwrap_or(config.metadata.concurrency);
+            let limit = limit.unwrap_or(config.metadata.batch_limit);
+            let (successful, failed) = MetadataGenerator::new(concurrency)
+                .await?
+                .generate_article_metadata(limit, &control, &progress)
+                .await
+                .map_err(|e| anyhow::anyhow!(e))?;
+            println!(
+                "Metadata generated for {} articles, {} failed",
+                successful.len(),
+                failed.len()
+            );
+            anyhow::ensure!(failed.is_empty(), "{} articles failed", failed.len());
+        }
+        Command::RetryFailed { concurrency } => {
+            let concurrency = concurrency.unwrap_or(config.metadata.concurrency);
+            let (successful, failed) = MetadataGenerator::new(concurrency)
+                .await?
+                .generate_failed_article_metadata(&control, &progress)
+                .await
+                .map_err(|e| anyhow::anyhow!(e))?;
+            println!(
+                "Metadata retried: {} successful, {} failed",
+                successful.len(),
+                failed.len()
+            );
+            anyhow::ensure!(failed.is_empty(), "{} articles failed again", failed.len());
+        }
+        Command::Clear { yes } => {
+            anyhow::ensure!(yes, "Refusing to delete everything without --yes");
+            let mut conn = pool.get().context("Failed to get DB connection")?;
+            db::clear_all_tables(&mut conn)?;
+            println!("Cleared all collections, articles and embeddings");
+        }
+        Command::Stats => print_stats(&pool, config.embedding.active_model())?,
+        Command::Export { file, embeddings } => {
+            let mut conn = pool.get().context("Failed to get DB connection")?;
+            let writer = BufWriter::new(
+                File::create(&file).with_context(|| format!("Failed to create {:?}", file))?,
+            );
+            let summary = export_archive(&mut conn, writer, embeddings)?;
+            println!("Exported {} to {:?}", summary, file);
+        }
+        Command::Import { file } => {
+            let mut conn = pool.get().context("Failed to get DB connection")?;
+            let reader = File::open(&file).with_context(|| format!("Failed to open {:?}", file))?;
+            let summary = import_archive(&mut conn, reader)?;
+            println!("Imported {} from {:?}", summary, file);
+        }
+    }
+    Ok(())
+}
+
+/// A `JobProgress` whose events are printed to stderr as they happen.
+fn progress_printer() -> JobProgress {
+    let (events, mut receiver) = broadcast::channel::<JobEvent>(1024);
+    tokio::spawn(async move {
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            match event.kind {
+                JobEventKind::ItemSucceeded { item, progress } => eprintln!(
+                    "[{}/{}] ok {}",
+                    progress.succeeded + progress.failed,
+                    progress.total,
+                    item
+                ),
+                JobEventKind::ItemFailed {
+                    item,
+                    reason,
+                    progress,
+                } => eprintln!(
+                    "[{}/{}] FAILED {}: {}",
+                    progress.succeeded + progress.failed,
+                    progress.total,
+                    item,
+                    reason
+                ),
+                _ => {}
+            }
+        }
+    });
+    JobProgress::new(Uuid::nil(), None, events)
+}
+
+/// Polls the job until it and its children are finished, printing the
+/// children's progress as it changes. Interrupting the command cancels the
+/// job.
+async fn wait_for_job(
+    job_queue: &JobQueue,
+    job_id: Uuid,
+    control: &JobControl,
+) -> Result<(JobInfo, ChildJobCounts)> {
+    let mut cancel_requested = false;
+    let mut reported = (0, 0);
+    loop {
+        let mut conn = job_queue
+            .db_pool
+            .get()
+            .context("Failed to get DB connection")?;
+        let job = JobInfo::find(&mut conn, job_id)?
+            .with_context(|| format!("Job {} no longer exists", job_id))?;
+        let counts = JobInfo::child_counts(&mut conn, job_id)?;
+        if job.is_finished(&mut conn)? {
+            return Ok((job, counts));
+        }
+
+        if (counts.total, counts.done + counts.failed) != reported {
+            reported = (counts.total, counts.done + counts.failed);
+            eprintln!("[{}/{}] {} failed", reported.1, reported.0, counts.failed);
+        }
+        if control.is_cancelled() && !cancel_requested {
+            job_queue.cancel_job(job_id)?;
+            cancel_requested = true;
+        }
+        drop(conn);
+        sleep(job_queue.poll_interval).await;
+    }
+}
+
+fn finish(task: &str, progress: &JobProgress) -> Result<()> {
+    let snapshot = progress.snapshot();
+    println!(
+        "{} finished: {} succeeded, {} failed",
+        task, snapshot.succeeded, snapshot.failed
+    );
+    anyhow::ensure!(snapshot.failed == 0, "{} items failed", snapshot.failed);
+    Ok(())
+}
+
+/// Row counts; articles without embeddings are counted for `active_model` in
+/// the active generation, the chunks search currently uses.
+fn print_stats(pool: &DbPool, active_model: &str) -> Result<()> {
+    let mut conn = pool.get().context("Failed to get DB connection")?;
+    let collection_count: i64 = collections::table.select(count_star()).first(&mut conn)?;
+    let active: i64 = articles::table
+        .filter(articles::archived_at.is_null())
+        .select(count_star())
+        .first(&mut conn)?;
+    let archived: i64 = articles::table
+        .filter(articles::archived_at.is_not_null())
+        .select(count_star())
+        .first(&mut conn)?;
+    let with_metadata: i64 = articles::table
+        .filter(articles::archived_at.is_null())
+        .filter(articles::paragraph_description.is_not_null())
+        .select(count_star())
+        .first(&mut conn)?;
+    let generation = EmbeddingGeneration::active(&mut conn)?;
+    let without_embeddings =
+        Article::load_active_without_embeddings(&mut conn, generation.id, active_model)?.len();
+    let chunk_count: i64 = article_chunks::table
+        .select(count_star())
+        .first(&mut conn)?;
+    let embedding_count: i64 = embeddings::table.select(count_star()).first(&mut conn)?;
+
+    for (label, value) in [
+        ("collections", collection_count),
+        ("active articles", active),
+        ("archived articles", archived),
+        ("with metadata", with_metadata),
+        ("without embeddings", without_embeddings as i64),
+        ("chunks", chunk_count),
+        ("embeddings", embedding_count),
+    ] {
+        println!("{:<20}{}", format!("{}:", label), value);
+    }
+
+    let per_model: Vec<(String, i32, i64)> = embeddings::table
+        .group_by((embeddings::model, embeddings::dimensions))
+        .select((embeddings::model, embeddings::dimensions, count_star()))
+        .order(embeddings::model)
+        .load(&mut conn)?;
+    for (model, dimensions, count) in per_model {
+        let active = if model == active_model {
+            ", active"
+        } else {
+            ""
+        };
+        println!("  {} ({} dims{}): {}", model, dimensions, active, count);
+    }
+
+    println!("embedding generations:");
+    for generation in EmbeddingGeneration::load_all(&mut conn)? {
+        println!(
+            "  {} {} ({}, created {})",
+            generation.id, generation.status, generation.model, generation.created_at
+        );
+    }
+    Ok(())
+}
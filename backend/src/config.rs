@@ -0,0 +1,737 @@
+//! Typed application configuration.
+//!
+//! `Config::load` starts from the defaults below, merges the TOML file named
+//! by `HELPDOC_CONFIG` (else `config.toml` in the working directory, if it
+//! exists) and finally applies the environment variables listed in
+//! `ENV_OVERRIDES`, so existing `.env` files keep working. `main` validates the
+//! result at startup and hands each service its section; code constructed
+//! without one (tests, `Default` constructors) reads `config::get()`.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use toml::Value;
+
+use crate::errors::ConfigError;
+use crate::models::embedding::LEGACY_EMBEDDING_MODEL;
+
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub source: SourceConfig,
+    pub helpscout: HelpScoutConfig,
+    pub embedding: EmbeddingConfig,
+    pub ai: AiConfig,
+    pub metadata: MetadataConfig,
+    pub jobs: JobsConfig,
+    pub schedule: ScheduleConfig,
+    pub import: ImportConfig,
+    pub search: SearchConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    /// Bearer token required by routes that dump, replace or drop data, such
+    /// as `/export` and `/import`. Those routes are refused while it is unset.
+    pub admin_token: Option<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 3000,
+            admin_token: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: Option<String>,
+    pub max_connections: u32,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            max_connections: 10,
+        }
+    }
+}
+
+/// Which `DocumentSource` syncs read from, see `data_processor::source`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SourceConfig {
+    /// `helpscout`, `local` or `sitemap`.
+    pub kind: String,
+    /// Root of the `local` source.
+    pub docs_dir: Option<String>,
+    pub sitemap_urls: Vec<String>,
+    pub sitemap_requests_per_minute: u32,
+}
+
+impl Default for SourceConfig {
+    fn default() -> Self {
+        Self {
+            kind: "helpscout".to_string(),
+            docs_dir: None,
+            sitemap_urls: Vec::new(),
+            sitemap_requests_per_minute: 60,
+        }
+    }
+}
+
+/// The Help Scout Docs API client and webhook receiver.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HelpScoutConfig {
+    pub base_url: Option<String>,
+    pub api_key: Option<String>,
+    pub webhook_secret: Option<String>,
+    pub timeout_ms: u64,
+    pub connect_timeout_ms: u64,
+    pub max_retries: u32,
+    pub retry_base_ms: u64,
+    pub retry_max_ms: u64,
+    /// Longest `Retry-After` the client waits for; a longer one fails the
+    /// request.
+    pub max_retry_after_ms: u64,
+    pub requests_per_minute: u32,
+    pub burst: u32,
+}
+
+impl Default for HelpScoutConfig {
+    fn default() -> Self {
+        Self {
+            base_url: None,
+            api_key: None,
+            webhook_secret: None,
+            timeout_ms: 30_000,
+            connect_timeout_ms: 5_000,
+            max_retries: 5,
+            retry_base_ms: 500,
+            retry_max_ms: 60_000,
+            max_retry_after_ms: 120_000,
+            requests_per_minute: 200,
+            burst: 10,
+        }
+    }
+}
+
+/// How article chunks and queries are embedded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmbeddingConfig {
+    /// `http`, `ollama` or `candle`, see `embedding::embedder::from_config`.
+    pub backend: String,
+    /// Base URL of the Python service; `/embed`, `/health` and `/test-embed`
+    /// are appended.
+    pub url: String,
+    /// Identifier stored with embeddings from the `http` and `candle`
+    /// backends; change it whenever the model behind them changes.
+    pub model: String,
+    /// Model directory of the `candle` backend.
+    pub model_path: Option<String>,
+    /// Ollama servers of the `ollama` backend, one per port.
+    pub ollama_host: String,
+    pub ollama_ports: Vec<u16>,
+    pub ollama_model: String,
+    /// Size of the vectors the `http` and `ollama` models produce.
+    pub dimensions: usize,
+    /// Estimated tokens per article chunk, see `chunker::estimate_tokens`.
+    /// all-MiniLM-L6-v2 ignores anything past 256 tokens and the estimate
+    /// runs low, hence the margin.
+    pub chunk_size: usize,
+    /// Estimated tokens consecutive chunks of a section share.
+    pub chunk_overlap: usize,
+    /// Chunks sent to the backend per request.
+    pub batch_size: usize,
+    /// Search queries whose vectors are kept in memory; 0 disables the cache.
+    pub query_cache_size: usize,
+    /// Chunk vectors kept in the `embedding_cache` table; the least recently
+    /// used ones are evicted after each embedding run.
+    pub cache_size: usize,
+    /// Backend of the model the active generation was built with, while a
+    /// re-embed into a new model is pending: search keeps embedding queries
+    /// with it until the new generation is activated. Only its backend
+    /// settings are used.
+    pub previous: Option<Box<EmbeddingConfig>>,
+}
+
+impl Default for EmbeddingConfig {
+    fn default() -> Self {
+        Self {
+            backend: "http".to_string(),
+            url: "http://localhost:8080".to_string(),
+            model: LEGACY_EMBEDDING_MODEL.to_string(),
+            model_path: None,
+            ollama_host: "http://localhost".to_string(),
+            ollama_ports: vec![11434],
+            ollama_model: "all-minilm".to_string(),
+            dimensions: 384,
+            chunk_size: 200,
+            chunk_overlap: 32,
+            batch_size: 32,
+            query_cache_size: 1000,
+            cache_size: 100_000,
+            previous: None,
+        }
+    }
+}
+
+impl EmbeddingConfig {
+    pub fn endpoint(&self, path: &str) -> String {
+        format!("{}/{}", self.url.trim_end_matches('/'), path)
+    }
+
+    /// The model new embeddings are tagged with and searched by.
+    pub fn active_model(&self) -> &str {
+        match self.backend.as_str() {
+            "ollama" => &self.ollama_model,
+            _ => &self.model,
+        }
+    }
+}
+
+/// The Ollama server used for chat, query expansion and metadata.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AiConfig {
+    pub ollama_host: String,
+    pub ollama_port: u16,
+    pub model: String,
+    /// Chat messages kept as history per conversation.
+    pub history_size: u16,
+}
+
+impl Default for AiConfig {
+    fn default() -> Self {
+        Self {
+            ollama_host: "http://localhost".to_string(),
+            ollama_port: 11434,
+            model: "llama3.1:latest".to_string(),
+            history_size: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetadataConfig {
+    /// Articles processed at once by the metadata generator.
+    pub concurrency: usize,
+    /// Articles handled by one `/metadata-generation` run.
+    pub batch_limit: usize,
+}
+
+impl Default for MetadataConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: 6,
+            batch_limit: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobsConfig {
+    pub workers: usize,
+    pub rate_limit_ms: u64,
+    pub poll_interval_ms: u64,
+    pub max_attempts: i32,
+    pub retry_base_ms: u64,
+    pub retry_max_ms: u64,
+    /// How long a running job may go without a heartbeat before another
+    /// worker assumes its worker died and reclaims it.
+    pub lease_ms: u64,
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            workers: 4,
+            rate_limit_ms: 500,
+            poll_interval_ms: 1000,
+            max_attempts: 5,
+            retry_base_ms: 1000,
+            retry_max_ms: 300_000,
+            lease_ms: 300_000,
+        }
+    }
+}
+
+/// Cron expressions (with seconds) of the scheduled jobs; unset disables one.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScheduleConfig {
+    pub sync: Option<String>,
+    pub retry_failed_metadata: Option<String>,
+    pub backfill_embeddings: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImportConfig {
+    /// Directory imported by `POST /import/markdown`.
+    pub markdown_dir: Option<String>,
+    /// Largest archive accepted by `POST /import`.
+    pub archive_max_bytes: usize,
+}
+
+impl Default for ImportConfig {
+    fn default() -> Self {
+        Self {
+            markdown_dir: None,
+            archive_max_bytes: 512 * 1024 * 1024,
+        }
+    }
+}
+
+/// Result counts at each search stage, and how semantic and keyword results
+/// are merged.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchConfig {
+    /// Collections matched per metadata embedding.
+    pub collection_matches: i64,
+    /// Articles matched per metadata embedding.
+    pub metadata_matches: i64,
+    /// Nearest chunks considered by semantic search.
+    pub chunk_candidates: i64,
+    /// Articles returned by keyword search.
+    pub keyword_results: i64,
+    /// Articles returned by semantic and two-stage search.
+    pub article_results: usize,
+    /// Articles returned by combined search.
+    pub combined_results: usize,
+    /// Best-matching chunks returned per article as passages.
+    pub passages_per_article: usize,
+    pub fusion: FusionMethod,
+    /// The `k` of reciprocal rank fusion; larger values flatten the
+    /// advantage of top ranks.
+    pub rrf_k: f64,
+    /// Weights of the semantic and keyword result lists in fusion.
+    pub semantic_weight: f64,
+    pub keyword_weight: f64,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            collection_matches: 3,
+            metadata_matches: 3,
+            chunk_candidates: 20,
+            keyword_results: 10,
+            article_results: 5,
+            combined_results: 10,
+            passages_per_article: 3,
+            fusion: FusionMethod::Rrf,
+            rrf_k: 60.0,
+            semantic_weight: 1.0,
+            keyword_weight: 1.0,
+        }
+    }
+}
+
+/// How search merges its semantic and keyword result lists, see
+/// `search::fusion::Fusion`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FusionMethod {
+    /// Reciprocal rank fusion with `search.rrf_k`.
+    Rrf,
+    /// Normalized scores times `search.semantic_weight` and
+    /// `search.keyword_weight`.
+    Weighted,
+}
+
+/// Environment variables that override a config key, as `(variable, key)`.
+/// Lists are comma-separated.
+pub const ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("SERVER_HOST", "server.host"),
+    ("SERVER_PORT", "server.port"),
+    ("ADMIN_TOKEN", "server.admin_token"),
+    ("DATABASE_URL", "database.url"),
+    ("DATABASE_MAX_CONNECTIONS", "database.max_connections"),
+    ("DOCUMENT_SOURCE", "source.kind"),
+    ("DOCS_DIR", "source.docs_dir"),
+    ("SITEMAP_URLS", "source.sitemap_urls"),
+    (
+        "SITEMAP_RATE_LIMIT_PER_MINUTE",
+        "source.sitemap_requests_per_minute",
+    ),
+    ("API_BASE_URL", "helpscout.base_url"),
+    ("API_KEY", "helpscout.api_key"),
+    ("HELPSCOUT_WEBHOOK_SECRET", "helpscout.webhook_secret"),
+    ("API_TIMEOUT_MS", "helpscout.timeout_ms"),
+    ("API_CONNECT_TIMEOUT_MS", "helpscout.connect_timeout_ms"),
+    ("API_MAX_RETRIES", "helpscout.max_retries"),
+    ("API_RETRY_BASE_MS", "helpscout.retry_base_ms"),
+    ("API_RETRY_MAX_MS", "helpscout.retry_max_ms"),
+    ("API_MAX_RETRY_AFTER_MS", "helpscout.max_retry_after_ms"),
+    ("API_RATE_LIMIT_PER_MINUTE", "helpscout.requests_per_minute"),
+    ("API_RATE_LIMIT_BURST", "helpscout.burst"),
+    ("EMBEDDING_BACKEND", "embedding.backend"),
+    ("EMBEDDING_SERVICE_URL", "embedding.url"),
+    ("EMBEDDING_MODEL", "embedding.model"),
+    ("EMBEDDING_MODEL_PATH", "embedding.model_path"),
+    ("EMBEDDING_OLLAMA_HOST", "embedding.ollama_host"),
+    ("EMBEDDING_OLLAMA_PORTS", "embedding.ollama_ports"),
+    ("EMBEDDING_OLLAMA_MODEL", "embedding.ollama_model"),
+    ("EMBEDDING_DIMENSIONS", "embedding.dimensions"),
+    ("EMBEDDING_CHUNK_SIZE", "embedding.chunk_size"),
+    ("EMBEDDING_CHUNK_OVERLAP", "embedding.chunk_overlap"),
+    ("EMBEDDING_BATCH_SIZE", "embedding.batch_size"),
+    ("EMBEDDING_QUERY_CACHE_SIZE", "embedding.query_cache_size"),
+    ("EMBEDDING_CACHE_SIZE", "embedding.cache_size"),
+    ("OLLAMA_HOST", "ai.ollama_host"),
+    ("OLLAMA_PORT", "ai.ollama_port"),
+    ("OLLAMA_MODEL", "ai.model"),
+    ("METADATA_CONCURRENCY", "metadata.concurrency"),
+    ("METADATA_BATCH_LIMIT", "metadata.batch_limit"),
+    ("JOB_QUEUE_WORKERS", "jobs.workers"),
+    ("JOB_QUEUE_RATE_LIMIT_MS", "jobs.rate_limit_ms"),
+    ("JOB_QUEUE_POLL_INTERVAL_MS", "jobs.poll_interval_ms"),
+    ("JOB_QUEUE_MAX_ATTEMPTS", "jobs.max_attempts"),
+    ("JOB_QUEUE_RETRY_BASE_MS", "jobs.retry_base_ms"),
+    ("JOB_QUEUE_RETRY_MAX_MS", "jobs.retry_max_ms"),
+    ("JOB_QUEUE_LEASE_MS", "jobs.lease_ms"),
+    ("SCHEDULE_SYNC", "schedule.sync"),
+    (
+        "SCHEDULE_RETRY_FAILED_METADATA",
+        "schedule.retry_failed_metadata",
+    ),
+    (
+        "SCHEDULE_BACKFILL_EMBEDDINGS",
+        "schedule.backfill_embeddings",
+    ),
+    ("MARKDOWN_IMPORT_DIR", "import.markdown_dir"),
+    ("ARCHIVE_IMPORT_MAX_BYTES", "import.archive_max_bytes"),
+];
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Installs the configuration returned by `get`. Fails if it was already set,
+/// including implicitly by an earlier `get`.
+pub fn init(config: Config) -> Result<(), ConfigError> {
+    CONFIG
+        .set(config)
+        .map_err(|_| ConfigError::AlreadyInitialized)
+}
+
+/// The configuration passed to `init`, else the one `Config::load` finds.
+/// Panics if that fails rather than running on defaults; the binaries call
+/// `init` first so a broken config stops them at startup.
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(|| {
+        Config::load().unwrap_or_else(|e| panic!("Failed to load configuration: {}", e))
+    })
+}
+
+impl Config {
+    /// Defaults, overlaid with the config file and then the environment.
+    pub fn load() -> Result<Self, ConfigError> {
+        let path = match env::var("HELPDOC_CONFIG") {
+            Ok(path) if !path.is_empty() => Some(PathBuf::from(path)),
+            _ => Some(PathBuf::from(DEFAULT_CONFIG_FILE)).filter(|path| path.exists()),
+        };
+        let contents = match &path {
+            Some(path) => fs::read_to_string(path).map_err(|source| ConfigError::Read {
+                path: path.clone(),
+                source,
+            })?,
+            None => String::new(),
+        };
+        Self::from_toml(&contents, |key| env::var(key).ok()).map_err(|e| e.in_file(path.as_deref()))
+    }
+
+    /// Parses `contents` and applies the overrides that `lookup` returns for
+    /// the variables in `ENV_OVERRIDES`.
+    pub fn from_toml(
+        contents: &str,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let parsed: Config = toml::from_str(contents).map_err(ConfigError::Parse)?;
+        let mut value = Value::try_from(&parsed).map_err(ConfigError::Serialize)?;
+        for (variable, key) in ENV_OVERRIDES {
+            if let Some(raw) = lookup(variable) {
+                apply_override(&mut value, key, &raw).map_err(|reason| ConfigError::Override {
+                    variable: variable.to_string(),
+                    reason,
+                })?;
+            }
+        }
+        value.try_into().map_err(ConfigError::Parse)
+    }
+
+    /// Checks everything that would otherwise only fail once used.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+        let mut require = |ok: bool, problem: &str| {
+            if !ok {
+                problems.push(problem.to_string());
+            }
+        };
+
+        require(is_set(&self.database.url), "database.url must be set");
+        require(
+            self.database.max_connections > 0,
+            "database.max_connections must be positive",
+        );
+        require(self.server.port > 0, "server.port must be positive");
+        match self.source.kind.as_str() {
+            "helpscout" => {
+                require(
+                    is_set(&self.helpscout.base_url),
+                    "helpscout.base_url must be set",
+                );
+                require(
+                    is_set(&self.helpscout.api_key),
+                    "helpscout.api_key must be set",
+                );
+            }
+            "local" => require(is_set(&self.source.docs_dir), "source.docs_dir must be set"),
+            "sitemap" => require(
+                !self.source.sitemap_urls.is_empty(),
+                "source.sitemap_urls must not be empty",
+            ),
+            _ => require(false, "source.kind must be helpscout, local or sitemap"),
+        }
+        require(
+            self.helpscout.retry_base_ms <= self.helpscout.retry_max_ms,
+            "helpscout.retry_base_ms must not exceed helpscout.retry_max_ms",
+        );
+        require(
+            self.helpscout.requests_per_minute > 0 && self.helpscout.burst > 0,
+            "helpscout.requests_per_minute and helpscout.burst must be positive",
+        );
+        require(
+            self.source.sitemap_requests_per_minute > 0,
+            "source.sitemap_requests_per_minute must be positive",
+        );
+        match self.embedding.backend.as_str() {
+            "http" => {}
+            "ollama" => {
+                require(
+                    url::Url::parse(&self.embedding.ollama_host).is_ok(),
+                    "embedding.ollama_host must be a URL",
+                );
+                require(
+                    !self.embedding.ollama_ports.is_empty(),
+                    "embedding.ollama_ports must not be empty",
+                );
+                require(
+                    !self.embedding.ollama_model.is_empty(),
+                    "embedding.ollama_model must be set",
+                );
+            }
+            "candle" => {
+                require(
+                    cfg!(feature = "candle"),
+                    "embedding.backend = \"candle\" requires building with the candle feature",
+                );
+                require(
+                    is_set(&self.embedding.model_path),
+                    "embedding.model_path must be set",
+                );
+            }
+            _ => require(false, "embedding.backend must be http, ollama or candle"),
+        }
+        require(
+            !self.embedding.model.is_empty(),
+            "embedding.model must be set",
+        );
+        require(
+            self.embedding.dimensions > 0,
+            "embedding.dimensions must be positive",
+        );
+        require(
+            url::Url::parse(&self.embedding.url).is_ok(),
+            "embedding.url must be a URL",
+        );
+        require(
+            self.embedding.chunk_size > 0,
+            "embedding.chunk_size must be positive",
+        );
+        require(
+            self.embedding.chunk_overlap < self.embedding.chunk_size,
+            "embedding.chunk_overlap must be smaller than embedding.chunk_size",
+        );
+        require(
+            self.embedding.batch_size > 0,
+            "embedding.batch_size must be positive",
+        );
+        require(
+            url::Url::parse(&self.ai.ollama_host).is_ok(),
+            "ai.ollama_host must be a URL",
+        );
+        require(!self.ai.model.is_empty(), "ai.model must be set");
+        require(
+            self.metadata.concurrency > 0,
+            "metadata.concurrency must be positive",
+        );
+        require(self.jobs.workers > 0, "jobs.workers must be positive");
+        require(
+            self.jobs.max_attempts > 0,
+            "jobs.max_attempts must be positive",
+        );
+        require(
+            self.jobs.retry_base_ms <= self.jobs.retry_max_ms,
+            "jobs.retry_base_ms must not exceed jobs.retry_max_ms",
+        );
+        require(self.jobs.lease_ms > 0, "jobs.lease_ms must be positive");
+        for (name, expression) in [
+            ("sync", &self.schedule.sync),
+            (
+                "retry_failed_metadata",
+                &self.schedule.retry_failed_metadata,
+            ),
+            ("backfill_embeddings", &self.schedule.backfill_embeddings),
+        ] {
+            let expression = expression.as_deref().map(str::trim).unwrap_or_default();
+            require(
+                expression.is_empty() || cron::Schedule::from_str(expression).is_ok(),
+                &format!("schedule.{} is not a valid cron expression", name),
+            );
+        }
+        let search = &self.search;
+        require(
+            search.collection_matches > 0
+                && search.metadata_matches > 0
+                && search.chunk_candidates > 0
+                && search.keyword_results > 0
+                && search.article_results > 0
+                && search.combined_results > 0
+                && search.passages_per_article > 0,
+            "search limits must be positive",
+        );
+        require(search.rrf_k >= 0.0, "search.rrf_k must not be negative");
+        require(
+            search.semantic_weight >= 0.0
+                && search.keyword_weight >= 0.0
+                && search.semantic_weight + search.keyword_weight > 0.0,
+            "search.semantic_weight and search.keyword_weight must not be negative or both zero",
+        );
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+
+    pub fn bind_address(&self) -> (String, u16) {
+        (self.server.host.clone(), self.server.port)
+    }
+}
+
+impl JobsConfig {
+    pub fn rate_limit(&self) -> Duration {
+        Duration::from_millis(self.rate_limit_ms)
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval_ms)
+    }
+
+    pub fn retry_base_delay(&self) -> Duration {
+        Duration::from_millis(self.retry_base_ms)
+    }
+
+    pub fn retry_max_delay(&self) -> Duration {
+        Duration::from_millis(self.retry_max_ms)
+    }
+
+    pub fn lease(&self) -> Duration {
+        Duration::from_millis(self.lease_ms)
+    }
+}
+
+impl ConfigError {
+    fn in_file(self, path: Option<&Path>) -> Self {
+        match (self, path) {
+            (ConfigError::Parse(source), Some(path)) => ConfigError::ParseFile {
+                path: path.to_path_buf(),
+                source,
+            },
+            (error, _) => error,
+        }
+    }
+}
+
+fn is_set(value: &Option<String>) -> bool {
+    value
+        .as_deref()
+        .is_some_and(|value| !value.trim().is_empty())
+}
+
+/// Sets the dotted `key` of `config` to `raw`, converted to the type of the
+/// value it replaces. Keys without a value yet are optional strings.
+fn apply_override(config: &mut Value, key: &str, raw: &str) -> Result<(), String> {
+    let (section, field) = key
+        .split_once('.')
+        .ok_or_else(|| format!("invalid key {}", key))?;
+    let table = config
+        .get_mut(section)
+        .and_then(Value::as_table_mut)
+        .ok_or_else(|| format!("unknown section {}", section))?;
+
+    let raw = raw.trim();
+    // An empty variable unsets an optional key rather than setting ""
+    if raw.is_empty() && !matches!(table.get(field), Some(Value::String(_))) {
+        table.remove(field);
+        return Ok(());
+    }
+    let value = match table.get(field) {
+        Some(Value::Integer(_)) => Value::Integer(
+            raw.parse()
+                .map_err(|_| format!("{:?} is not an integer", raw))?,
+        ),
+        Some(Value::Float(_)) => Value::Float(
+            raw.parse()
+                .map_err(|_| format!("{:?} is not a number", raw))?,
+        ),
+        Some(Value::Boolean(_)) => Value::Boolean(
+            raw.parse()
+                .map_err(|_| format!("{:?} is not true or false", raw))?,
+        ),
+        Some(Value::Array(items)) => {
+            let integers = matches!(items.first(), Some(Value::Integer(_)));
+            Value::Array(
+                raw.split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(|item| {
+                        if integers {
+                            item.parse()
+                                .map(Value::Integer)
+                                .map_err(|_| format!("{:?} is not an integer", item))
+                        } else {
+                            Ok(Value::String(item.to_string()))
+                        }
+                    })
+                    .collect::<Result<_, _>>()?,
+            )
+        }
+        _ => Value::String(raw.to_string()),
+    };
+    table.insert(field.to_string(), value);
+    Ok(())
+}
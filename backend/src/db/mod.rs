@@ -1,24 +1,31 @@
 // File: src/db/mod.rs
 
-use std::env;
-
 use diesel::r2d2::{self, ConnectionManager, Pool};
 use diesel::RunQueryDsl;
 use diesel::{sql_query, PgConnection};
 
+use crate::config::{self, DatabaseConfig};
+
 
 pub type DbPool = Pool<ConnectionManager<PgConnection>>;
 
 pub fn init_pool() -> DbPool {
-    let database_url: String = get_database_url();
-    let manager = ConnectionManager::<PgConnection>::new(database_url);
+    init_pool_with(&config::get().database)
+}
+
+pub fn init_pool_with(config: &DatabaseConfig) -> DbPool {
+    let manager = ConnectionManager::<PgConnection>::new(get_database_url(config));
     r2d2::Pool::builder()
+        .max_size(config.max_connections)
         .build(manager)
         .expect("Failed to create pool.")
 }
 
-pub fn get_database_url() -> String {
-    env::var("DATABASE_URL").expect("DATABASE_URL must be set")
+pub fn get_database_url(config: &DatabaseConfig) -> String {
+    config
+        .url
+        .clone()
+        .expect("database.url (DATABASE_URL) must be set")
 }
 
 pub fn clear_all_tables(conn: &mut PgConnection) -> Result<(), diesel::result::Error> {
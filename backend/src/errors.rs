@@ -28,6 +28,84 @@ pub enum SyncError {
     Other(#[from] anyhow::Error),
 }
 
+#[derive(Error, Debug)]
+pub enum JobControlError {
+    #[error("Job {0} not found")]
+    NotFound(uuid::Uuid),
+    #[error("Job {0} already finished with status {1}")]
+    AlreadyFinished(uuid::Uuid, crate::models::JobStatus),
+    #[error("Database error: {0}")]
+    Database(#[from] diesel::result::Error),
+    #[error("Other error: {0}")]
+    Other(#[from] anyhow::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum EnqueueError {
+    #[error("A {job_type} job is already active: {job_id}")]
+    AlreadyActive {
+        job_type: String,
+        job_id: uuid::Uuid,
+    },
+    #[error("Failed to enqueue job: {0}")]
+    Other(#[from] anyhow::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum WebhookError {
+    #[error("Missing signature header")]
+    MissingSignature,
+    #[error("Signature does not match the payload")]
+    InvalidSignature,
+    #[error("Unsupported event type: {0}")]
+    UnsupportedEvent(String),
+    #[error("Invalid payload: {0}")]
+    InvalidPayload(#[from] serde_json::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read config file {path:?}: {source}")]
+    Read {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Invalid config: {0}")]
+    Parse(toml::de::Error),
+    #[error("Invalid config file {path:?}: {source}")]
+    ParseFile {
+        path: std::path::PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("Failed to serialize config: {0}")]
+    Serialize(toml::ser::Error),
+    #[error("Invalid value in {variable}: {reason}")]
+    Override { variable: String, reason: String },
+    #[error("Invalid configuration: {}", .0.join("; "))]
+    Invalid(Vec<String>),
+    #[error("Configuration was already initialized")]
+    AlreadyInitialized,
+}
+
+#[derive(Error, Debug)]
+pub enum ArchiveError {
+    #[error("Archive does not start with a header record")]
+    MissingHeader,
+    #[error("Unsupported archive format {format:?} version {version}")]
+    UnsupportedVersion { format: String, version: u32 },
+    #[error("Invalid record on line {line}: {source}")]
+    InvalidRecord {
+        line: usize,
+        source: serde_json::Error,
+    },
+    #[error("Chunk on line {line} belongs to article {article_id}, which is not in the archive")]
+    OrphanChunk { line: usize, article_id: uuid::Uuid },
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Database error: {0}")]
+    Database(#[from] diesel::result::Error),
+}
+
 #[derive(Debug)]
 pub struct MetadataGenerationError(pub Box<dyn std::error::Error + Send + Sync>);
 
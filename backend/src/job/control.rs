@@ -0,0 +1,268 @@
+use std::sync::Arc;
+
+use diesel::PgConnection;
+use log::info;
+use thiserror::Error;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+use crate::errors::JobControlError;
+use crate::models::{JobInfo, JobStatus};
+
+use super::{JobEventKind, JobQueue};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlState {
+    Running,
+    Paused,
+    Cancelled,
+}
+
+#[derive(Debug, Error)]
+#[error("Job was cancelled")]
+pub struct JobCancelled;
+
+/// Shared between a running job and the endpoints that control it. Long jobs
+/// call `checkpoint` between units of work; it waits while the job is paused
+/// and fails once the job is cancelled.
+#[derive(Clone)]
+pub struct JobControl {
+    state: Arc<watch::Sender<ControlState>>,
+}
+
+impl Default for JobControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JobControl {
+    pub fn new() -> Self {
+        let (state, _) = watch::channel(ControlState::Running);
+        Self {
+            state: Arc::new(state),
+        }
+    }
+
+    pub fn state(&self) -> ControlState {
+        *self.state.borrow()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state() == ControlState::Cancelled
+    }
+
+    pub fn pause(&self) {
+        self.transition(ControlState::Running, ControlState::Paused);
+    }
+
+    pub fn resume(&self) {
+        self.transition(ControlState::Paused, ControlState::Running);
+    }
+
+    pub fn cancel(&self) {
+        self.state.send_replace(ControlState::Cancelled);
+    }
+
+    /// Applies the operator requests stored in the job's row, which another
+    /// process may have made.
+    pub fn follow(&self, status: JobStatus, cancel_requested: bool) {
+        match status {
+            _ if cancel_requested => self.cancel(),
+            JobStatus::Cancelled => self.cancel(),
+            JobStatus::Paused => self.pause(),
+            JobStatus::Running => self.resume(),
+            _ => {}
+        }
+    }
+
+    pub async fn checkpoint(&self) -> Result<(), JobCancelled> {
+        let mut state = self.state.subscribe();
+        loop {
+            let current = *state.borrow_and_update();
+            match current {
+                ControlState::Running => return Ok(()),
+                ControlState::Cancelled => return Err(JobCancelled),
+                ControlState::Paused => {
+                    if state.changed().await.is_err() {
+                        return Err(JobCancelled);
+                    }
+                }
+            }
+        }
+    }
+
+    fn transition(&self, from: ControlState, to: ControlState) {
+        self.state.send_if_modified(|state| {
+            if *state == from {
+                *state = to;
+                true
+            } else {
+                false
+            }
+        });
+    }
+}
+
+impl JobQueue {
+    /// Registers the control of a job a worker is about to run.
+    pub(crate) fn register_control(&self, job_id: Uuid) -> JobControl {
+        let control = JobControl::new();
+        self.controls
+            .lock()
+            .expect("job control registry poisoned")
+            .insert(job_id, control.clone());
+        control
+    }
+
+    pub(crate) fn unregister_control(&self, job_id: Uuid) {
+        self.controls
+            .lock()
+            .expect("job control registry poisoned")
+            .remove(&job_id);
+    }
+
+    fn running_control(&self, job_id: Uuid) -> Option<JobControl> {
+        self.controls
+            .lock()
+            .expect("job control registry poisoned")
+            .get(&job_id)
+            .cloned()
+    }
+
+    /// Cancels the job and every unfinished child. Queued and paused jobs are
+    /// cancelled immediately; running jobs stop at their next checkpoint, which
+    /// workers in other processes reach after their next heartbeat.
+    pub fn cancel_job(&self, job_id: Uuid) -> Result<JobStatus, JobControlError> {
+        let mut conn = self.db_pool.get().map_err(anyhow::Error::from)?;
+        let job = self.controllable_job(&mut conn, job_id)?;
+
+        for (id, parent_id) in self.job_and_children(&mut conn, &job)? {
+            let cancelled = JobInfo::transition(
+                &mut conn,
+                id,
+                &[JobStatus::Queued, JobStatus::Retrying, JobStatus::Paused],
+                JobStatus::Cancelled,
+                "Cancelled by operator",
+            )?;
+            if cancelled {
+                self.emit_control_status(id, parent_id, JobStatus::Cancelled);
+            } else {
+                JobInfo::request_cancel(&mut conn, id)?;
+            }
+            if let Some(control) = self.running_control(id) {
+                control.cancel();
+            }
+        }
+
+        info!("Cancelled job: {}", job_id);
+        Ok(JobStatus::Cancelled)
+    }
+
+    /// Pauses the job and every unfinished child. A running job holds its
+    /// worker while paused; workers in other processes pause after their next
+    /// heartbeat.
+    pub fn pause_job(&self, job_id: Uuid) -> Result<JobStatus, JobControlError> {
+        let mut conn = self.db_pool.get().map_err(anyhow::Error::from)?;
+        let job = self.controllable_job(&mut conn, job_id)?;
+
+        for (id, parent_id) in self.job_and_children(&mut conn, &job)? {
+            let paused = JobInfo::transition(
+                &mut conn,
+                id,
+                &[JobStatus::Queued, JobStatus::Retrying, JobStatus::Running],
+                JobStatus::Paused,
+                "Paused by operator",
+            )?;
+            if paused {
+                self.emit_control_status(id, parent_id, JobStatus::Paused);
+            }
+            if let Some(control) = self.running_control(id) {
+                control.pause();
+            }
+        }
+
+        info!("Paused job: {}", job_id);
+        Ok(JobStatus::Paused)
+    }
+
+    /// Resumes a paused job and its paused children. Jobs that were paused
+    /// before a worker picked them up, or whose worker stopped sending
+    /// heartbeats, go back in the queue.
+    pub fn resume_job(&self, job_id: Uuid) -> Result<JobStatus, JobControlError> {
+        let mut conn = self.db_pool.get().map_err(anyhow::Error::from)?;
+        let job = self.controllable_job(&mut conn, job_id)?;
+
+        let mut resumed_status = job.status().map_err(JobControlError::Other)?;
+        for (id, parent_id) in self.job_and_children(&mut conn, &job)? {
+            let control = self.running_control(id);
+            let held = control.is_some()
+                || JobInfo::find(&mut conn, id)?.is_some_and(|job| job.has_live_worker(self.lease));
+            let status = if held {
+                JobStatus::Running
+            } else {
+                JobStatus::Queued
+            };
+            let resumed = JobInfo::transition(
+                &mut conn,
+                id,
+                &[JobStatus::Paused],
+                status,
+                "Resumed by operator",
+            )?;
+            if resumed {
+                self.emit_control_status(id, parent_id, status);
+                if id == job_id {
+                    resumed_status = status;
+                }
+            }
+            if let Some(control) = control {
+                control.resume();
+            }
+        }
+
+        info!("Resumed job: {}", job_id);
+        Ok(resumed_status)
+    }
+
+    fn controllable_job(
+        &self,
+        conn: &mut PgConnection,
+        job_id: Uuid,
+    ) -> Result<JobInfo, JobControlError> {
+        let job = JobInfo::find(conn, job_id)?.ok_or(JobControlError::NotFound(job_id))?;
+
+        let status = job.status().map_err(JobControlError::Other)?;
+        // A finished parent may still have unfinished children to control
+        if status.is_terminal() && job.unfinished_children(conn)?.is_empty() {
+            return Err(JobControlError::AlreadyFinished(job_id, status));
+        }
+        Ok(job)
+    }
+
+    /// The job and its unfinished children, each with its parent ID.
+    fn job_and_children(
+        &self,
+        conn: &mut PgConnection,
+        job: &JobInfo,
+    ) -> Result<Vec<(Uuid, Option<Uuid>)>, JobControlError> {
+        let mut ids = vec![(job.id, job.parent_id)];
+        ids.extend(
+            job.unfinished_children(conn)?
+                .into_iter()
+                .map(|child_id| (child_id, Some(job.id))),
+        );
+        Ok(ids)
+    }
+
+    fn emit_control_status(&self, job_id: Uuid, parent_id: Option<Uuid>, status: JobStatus) {
+        self.emit(
+            job_id,
+            parent_id,
+            JobEventKind::Status {
+                status,
+                message: Some("Changed by operator".to_string()),
+            },
+        );
+    }
+}
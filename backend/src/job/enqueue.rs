@@ -1,33 +1,70 @@
-use chrono::Utc;
 use uuid::Uuid;
 
-use crate::models::{JobInfo, JobStatus};
+use crate::errors::EnqueueError;
+use crate::models::JobInfo;
 
 use super::{Job, JobQueue};
 
 impl JobQueue {
     pub async fn enqueue_job(&self, job: Job) -> Result<Uuid, anyhow::Error> {
-        let id = Uuid::new_v4();
-        let job_info = JobInfo {
-            id,
-            status: JobStatus::Queued,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        };
+        let job_info = self.job_info(&job)?;
 
+        let mut conn = self.db_pool.get()?;
+        let job_info = job_info
+            .store(&mut conn)
+            .map_err(|e| anyhow::anyhow!("Failed to enqueue job: {}", e))?;
+
+        log::info!("Enqueued {} job: {}", job_info.job_type, job_info.id);
+        Ok(job_info.id)
+    }
+
+    /// Enqueues the job unless a job of the same type is still active,
+    /// counting the children it fanned out.
+    pub async fn enqueue_exclusive(&self, job: Job) -> Result<Uuid, EnqueueError> {
+        let job_info = self.job_info(&job)?;
+
+        let mut conn = self.db_pool.get().map_err(anyhow::Error::from)?;
+        match job_info
+            .store_unless_active(&mut conn)
+            .map_err(anyhow::Error::from)?
         {
-            let mut statuses = self
-                .job_statuses
-                .lock()
-                .expect("Failed to lock job statuses");
-            statuses.push(job_info);
+            Ok(job_info) => {
+                log::info!("Enqueued {} job: {}", job_info.job_type, job_info.id);
+                Ok(job_info.id)
+            }
+            Err(job_id) => Err(EnqueueError::AlreadyActive {
+                job_type: job_info.job_type,
+                job_id,
+            }),
         }
+    }
 
-        self.sender
-            .send(job)
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to enqueue job: {}", e))?;
+    /// Enqueues `jobs` as children of `parent_id`, all or nothing.
+    pub async fn enqueue_child_jobs(
+        &self,
+        parent_id: Uuid,
+        jobs: Vec<Job>,
+    ) -> Result<Vec<Uuid>, anyhow::Error> {
+        let job_infos = jobs
+            .iter()
+            .map(|job| Ok(self.job_info(job)?.with_parent(parent_id)))
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+        let mut conn = self.db_pool.get()?;
+        JobInfo::store_all(&mut conn, &job_infos)
+            .map_err(|e| anyhow::anyhow!("Failed to enqueue child jobs: {}", e))?;
+
+        log::info!(
+            "Enqueued {} child jobs of job: {}",
+            job_infos.len(),
+            parent_id
+        );
+        Ok(job_infos.iter().map(|job_info| job_info.id).collect())
+    }
 
-        Ok(id)
+    fn job_info(&self, job: &Job) -> Result<JobInfo, anyhow::Error> {
+        let payload = serde_json::to_value(job)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize job: {}", e))?;
+        Ok(JobInfo::new(job.name(), payload, self.max_attempts))
     }
 }
@@ -1,76 +1,122 @@
-use crate::models::{JobInfo, JobStatus};
-use crate::services::DataProcessor;
+use crate::config::{self, JobsConfig};
+use crate::db::DbPool;
+use crate::models::{JobInfo, JobStatusChange};
+use crate::services::{DataProcessor, MetadataGenerator};
+use crate::utils::backoff_delay;
 
-use chrono::Utc;
-use std::env;
+use super::{JobControl, JobEvent, JobEventKind};
+
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use tokio::sync::mpsc;
+use tokio::sync::broadcast;
 use tokio::time::Duration;
 use uuid::Uuid;
 
-use log::info;
-
-use super::Job;
+use log::{error, info};
 
 #[derive(Clone)]
 pub struct JobQueue {
-    pub sender: mpsc::Sender<Job>,
-    pub job_statuses: Arc<Mutex<Vec<JobInfo>>>,
+    pub db_pool: Arc<DbPool>,
     pub num_workers: usize,
     pub rate_limit: Duration,
+    pub poll_interval: Duration,
+    pub max_attempts: i32,
+    pub retry_base_delay: Duration,
+    pub retry_max_delay: Duration,
+    /// Running jobs without a heartbeat for this long are reclaimed.
+    pub lease: Duration,
+    /// Controls of the jobs currently held by this process's workers.
+    pub(crate) controls: Arc<Mutex<HashMap<Uuid, JobControl>>>,
+    /// Progress and status events of every job run by this process.
+    pub(crate) events: broadcast::Sender<JobEvent>,
 }
 
 impl JobQueue {
-    pub fn new(sync_processor: Arc<DataProcessor>) -> Self {
-        let num_workers = env::var("JOB_QUEUE_WORKERS")
-            .ok()
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(4);
-
-        let rate_limit = env::var("JOB_QUEUE_RATE_LIMIT_MS")
-            .ok()
-            .and_then(|v| v.parse().ok())
-            .map(Duration::from_millis)
-            .unwrap_or(Duration::from_millis(500));
-
-        let (sender, receiver) = mpsc::channel(32);
-        let job_statuses = Arc::new(Mutex::new(Vec::new()));
-        let job_queue = Self {
-            sender,
-            job_statuses,
-            num_workers,
-            rate_limit,
-        };
-
-        job_queue.spawn_workers(receiver, sync_processor);
-
-        info!("Job queue initialized with {} workers", num_workers);
+    pub fn new(
+        db_pool: Arc<DbPool>,
+        sync_processor: Arc<DataProcessor>,
+        metadata_generator: Arc<MetadataGenerator>,
+    ) -> Self {
+        Self::with_config(
+            db_pool,
+            sync_processor,
+            metadata_generator,
+            &config::get().jobs,
+        )
+    }
+
+    pub fn with_config(
+        db_pool: Arc<DbPool>,
+        sync_processor: Arc<DataProcessor>,
+        metadata_generator: Arc<MetadataGenerator>,
+        config: &JobsConfig,
+    ) -> Self {
+        let job_queue = Self::without_workers(db_pool, config);
+
+        match job_queue
+            .db_pool
+            .get()
+            .map_err(anyhow::Error::from)
+            .and_then(|mut conn| {
+                JobInfo::requeue_expired(&mut conn, job_queue.lease).map_err(Into::into)
+            }) {
+            Ok(0) => {}
+            Ok(requeued) => info!("Reclaimed {} jobs whose worker stopped", requeued),
+            Err(e) => error!("Failed to reclaim interrupted jobs: {}", e),
+        }
+
+        job_queue.spawn_workers(sync_processor, metadata_generator);
+
+        info!(
+            "Job queue initialized with {} workers",
+            job_queue.num_workers
+        );
 
         job_queue
     }
 
-    pub fn get_job_status(&self, job_id: Uuid) -> Option<JobStatus> {
-        self.job_statuses
-            .lock()
-            .expect("Failed to lock job statuses")
-            .iter()
-            .find(|job| job.id == job_id)
-            .map(|job| job.status.clone())
+    /// Enqueues and controls jobs without running any until workers are
+    /// spawned, e.g. `spawn_job_tree_workers` in the admin CLI.
+    pub fn without_workers(db_pool: Arc<DbPool>, config: &JobsConfig) -> Self {
+        Self {
+            db_pool,
+            num_workers: config.workers,
+            rate_limit: config.rate_limit(),
+            poll_interval: config.poll_interval(),
+            max_attempts: config.max_attempts,
+            retry_base_delay: config.retry_base_delay(),
+            retry_max_delay: config.retry_max_delay(),
+            lease: config.lease(),
+            controls: Arc::new(Mutex::new(HashMap::new())),
+            events: broadcast::channel(1024).0,
+        }
     }
 
-    pub fn update_job_status(
-        job_statuses: &Arc<Mutex<Vec<JobInfo>>>,
-        job_id: Uuid,
-        status: JobStatus,
-    ) {
-        let mut statuses = job_statuses.lock().expect("Failed to lock job statuses");
-        if let Some(job) = statuses.iter_mut().find(|job| job.id == job_id) {
-            job.status = status;
-            job.updated_at = Utc::now();
-        }
+    pub fn get_job_status(&self, job_id: Uuid) -> Result<Option<JobInfo>, anyhow::Error> {
+        let mut conn = self.db_pool.get()?;
+        Ok(JobInfo::find(&mut conn, job_id)?)
+    }
+
+    pub fn get_job_history(&self, job_id: Uuid) -> Result<Vec<JobStatusChange>, anyhow::Error> {
+        let mut conn = self.db_pool.get()?;
+        Ok(JobStatusChange::for_job(&mut conn, job_id)?)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<JobEvent> {
+        self.events.subscribe()
+    }
+
+    pub(crate) fn emit(&self, job_id: Uuid, parent_id: Option<Uuid>, kind: JobEventKind) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.events.send(JobEvent {
+            job_id,
+            parent_id,
+            kind,
+        });
     }
 
-    pub fn queue_size(&self) -> usize {
-        self.sender.capacity() - self.sender.max_capacity()
+    /// Delay before the next attempt of a job that has failed `attempts` times.
+    pub fn retry_delay(&self, attempts: i32) -> Duration {
+        backoff_delay(self.retry_base_delay, self.retry_max_delay, attempts)
     }
 }
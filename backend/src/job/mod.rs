@@ -1,45 +1,192 @@
 use crate::errors::SyncError;
 use crate::models::articles::Article;
 use crate::models::{articles::ArticleRef, Collection};
-use crate::services::DataProcessor;
+use crate::services::data_processor::SyncMode;
+use crate::services::{DataProcessor, MetadataGenerator};
 
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
 use uuid::Uuid;
 
+pub mod control;
 pub mod enqueue;
 pub mod job_queue;
+pub mod progress;
+pub mod scheduler;
 pub mod worker;
 
+pub use control::{JobCancelled, JobControl};
 pub use job_queue::JobQueue;
+pub use progress::{JobEvent, JobEventKind, JobProgress, ProgressSnapshot};
+pub use scheduler::Scheduler;
 
-#[derive(Debug, Clone)]
+/// What a running job can reach: its own queue row and control, and the
+/// services it drives.
+pub struct JobContext<'a> {
+    pub job_id: Uuid,
+    pub control: &'a JobControl,
+    pub progress: &'a JobProgress,
+    pub job_queue: &'a JobQueue,
+    pub processor: &'a DataProcessor,
+    pub metadata_generator: &'a MetadataGenerator,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Job {
+    /// Lists every collection, archives articles removed upstream and fans out
+    /// one `SyncArticle` child job per listed article.
+    SyncAll(SyncMode),
     SyncCollection(Collection),
     StoreCollection(Collection),
-    SyncArticle(ArticleRef, Collection),
+    SyncArticle(ArticleRef, Collection, SyncMode),
+    /// Syncs an article named by a Help Scout webhook, fetching it from Help
+    /// Scout whatever `source.kind` is.
+    SyncHelpScoutArticle(ArticleRef, Collection),
+    /// Archives the article with this Help Scout ID after it was deleted upstream.
+    ArchiveArticle(String),
     StoreArticle(Article),
     ConvertHtmlToMarkdown(Article),
+    /// Imports the Markdown files under this directory, see
+    /// `DataProcessor::import_markdown_dir`.
+    ImportMarkdown(String),
     EnqueueJobs(Vec<Job>),
+    /// Generates metadata for up to `limit` articles.
+    GenerateMetadata(usize),
+    RetryFailedMetadata,
+    GenerateEmbeddings,
+    /// Embeds active articles that have no chunks yet.
+    BackfillEmbeddings,
+    /// Re-embeds every active article into a new generation and switches
+    /// search to it once complete.
+    ReembedAll,
 }
 
 impl Job {
-    async fn process(
-        &self,
-        processor: &DataProcessor,
-        job_queue: &JobQueue,
-    ) -> Result<(Uuid, Result<(), anyhow::Error>), anyhow::Error> {
-        let job_id = Uuid::new_v4();
-        log::info!("Starting to process job ID: {}", job_id);
+    /// Stored in `jobs.job_type` so queued work can be inspected without
+    /// decoding the payload.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Job::SyncAll(_) => "sync_all",
+            Job::SyncCollection(_) => "sync_collection",
+            Job::StoreCollection(_) => "store_collection",
+            Job::SyncArticle(_, _, _) => "sync_article",
+            Job::SyncHelpScoutArticle(_, _) => "sync_helpscout_article",
+            Job::ArchiveArticle(_) => "archive_article",
+            Job::StoreArticle(_) => "store_article",
+            Job::ConvertHtmlToMarkdown(_) => "convert_html_to_markdown",
+            Job::ImportMarkdown(_) => "import_markdown",
+            Job::EnqueueJobs(_) => "enqueue_jobs",
+            Job::GenerateMetadata(_) => "generate_metadata",
+            Job::RetryFailedMetadata => "retry_failed_metadata",
+            Job::GenerateEmbeddings => "generate_embeddings",
+            Job::BackfillEmbeddings => "backfill_embeddings",
+            Job::ReembedAll => "reembed_all",
+        }
+    }
+
+    /// Runs the job. Jobs that fan out work enqueue it as children of
+    /// `ctx.job_id`; long jobs check `ctx.control` between units of work and
+    /// report each item to `ctx.progress`. The returned value is stored as
+    /// the job's result.
+    async fn process(&self, ctx: &JobContext<'_>) -> Result<Option<serde_json::Value>> {
+        let JobContext {
+            job_id,
+            control,
+            progress,
+            job_queue,
+            processor,
+            metadata_generator,
+        } = *ctx;
 
-        let result = match self {
+        control.checkpoint().await?;
+        match self {
+            Job::SyncAll(mode) => {
+                log::info!("Processing SyncAll ({:?}) as job: {}", mode, job_id);
+                let listing = processor.list_all_articles().await?;
+
+                let listed_article_ids: HashSet<String> = listing
+                    .iter()
+                    .flat_map(|(_, article_refs)| article_refs.iter().map(|r| r.id.clone()))
+                    .collect();
+                control.checkpoint().await?;
+                processor.reconcile_articles(&listed_article_ids).await?;
+
+                // Enqueued last: a retry of this job must not fan out twice
+                let children: Vec<Job> = listing
+                    .into_iter()
+                    .flat_map(|(collection, article_refs)| {
+                        article_refs.into_iter().map(move |article_ref| {
+                            Job::SyncArticle(article_ref, collection.clone(), *mode)
+                        })
+                    })
+                    .collect();
+                let count = children.len();
+                job_queue
+                    .enqueue_child_jobs(job_id, children)
+                    .await
+                    .map_err(SyncError::JobEnqueueError)?;
+                log::info!("SyncAll job {} enqueued {} SyncArticle jobs", job_id, count);
+                Ok(None)
+            }
+            Job::GenerateMetadata(limit) => {
+                let (successful, failed) = metadata_generator
+                    .generate_article_metadata(*limit, control, progress)
+                    .await
+                    .map_err(|e| anyhow::anyhow!(e))?;
+                log::info!(
+                    "GenerateMetadata completed: {} successful, {} failed",
+                    successful.len(),
+                    failed.len()
+                );
+                Ok(None)
+            }
+            Job::RetryFailedMetadata => {
+                let (successful, failed) = metadata_generator
+                    .generate_failed_article_metadata(control, progress)
+                    .await
+                    .map_err(|e| anyhow::anyhow!(e))?;
+                log::info!(
+                    "RetryFailedMetadata completed: {} successful, {} failed",
+                    successful.len(),
+                    failed.len()
+                );
+                Ok(None)
+            }
+            Job::GenerateEmbeddings => {
+                processor
+                    .embedding_service()
+                    .generate_all_embeddings(&job_queue.db_pool, control, progress)
+                    .await?;
+                Ok(None)
+            }
+            Job::BackfillEmbeddings => {
+                processor
+                    .embedding_service()
+                    .backfill_embeddings(&job_queue.db_pool, control, progress)
+                    .await?;
+                Ok(None)
+            }
+            Job::ReembedAll => {
+                let mut conn = job_queue.db_pool.get()?;
+                processor
+                    .embedding_service()
+                    .reembed_all_articles(&mut conn, control, progress)
+                    .await
+                    .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+                Ok(None)
+            }
             Job::SyncCollection(collection) => {
                 log::info!(
                     "Processing SyncCollection for collection: {}",
                     collection.id
                 );
-                let result = processor.prepare_sync_collection(collection).await;
+                let result = processor
+                    .prepare_sync_collection(collection, SyncMode::Incremental)
+                    .await;
                 log::info!("SyncCollection completed for collection: {}", collection.id);
-                result.map(|_| ())
+                result.map(|_| None)
             }
             Job::StoreCollection(collection) => {
                 log::info!(
@@ -51,28 +198,68 @@ impl Job {
                     "StoreCollection completed for collection: {}",
                     collection.id
                 );
-                result
+                result.map(|_| None)
             }
-            Job::SyncArticle(article_ref, collection) => {
+            Job::SyncArticle(article_ref, collection, mode) => {
                 log::info!("Processing SyncArticle for article: {}", article_ref.id);
-                let result = processor.sync_article(article_ref, collection).await;
-                log::info!("SyncArticle completed for article: {}", article_ref.id);
-                result
+                let outcome = processor.sync_article(article_ref, collection, *mode).await?;
+                log::info!(
+                    "SyncArticle completed for article: {} ({:?})",
+                    article_ref.id,
+                    outcome
+                );
+                Ok(Some(serde_json::to_value(outcome)?))
+            }
+            Job::SyncHelpScoutArticle(article_ref, collection) => {
+                log::info!(
+                    "Processing SyncHelpScoutArticle for article: {}",
+                    article_ref.id
+                );
+                let outcome = processor
+                    .sync_helpscout_article(article_ref, collection)
+                    .await?;
+                log::info!(
+                    "SyncHelpScoutArticle completed for article: {} ({:?})",
+                    article_ref.id,
+                    outcome
+                );
+                Ok(Some(serde_json::to_value(outcome)?))
+            }
+            Job::ArchiveArticle(helpscout_article_id) => {
+                log::info!(
+                    "Processing ArchiveArticle for article: {}",
+                    helpscout_article_id
+                );
+                let outcome = processor
+                    .archive_deleted_article(helpscout_article_id)
+                    .await?;
+                log::info!(
+                    "ArchiveArticle completed for article: {} ({:?})",
+                    helpscout_article_id,
+                    outcome
+                );
+                Ok(Some(serde_json::to_value(outcome)?))
+            }
+            Job::ImportMarkdown(dir) => {
+                log::info!("Processing ImportMarkdown for directory: {}", dir);
+                let report = processor
+                    .import_markdown_dir(Path::new(dir), control, progress)
+                    .await?;
+                log::info!("ImportMarkdown completed for directory: {} ({})", dir, report);
+                Ok(None)
             }
             Job::EnqueueJobs(jobs) => {
-                for job in jobs {
-                    job_queue
-                        .enqueue_job(job.clone())
-                        .await
-                        .map_err(SyncError::JobEnqueueError)?;
-                }
-                Ok(())
+                job_queue
+                    .enqueue_child_jobs(job_id, jobs.clone())
+                    .await
+                    .map_err(SyncError::JobEnqueueError)?;
+                Ok(None)
             }
             Job::StoreArticle(article) => {
                 log::info!("Processing StoreArticle for article: {}", article.id);
                 processor.store_article(article).await?;
                 log::info!("StoreArticle completed for article: {}", article.id);
-                Ok(())
+                Ok(None)
             }
             Job::ConvertHtmlToMarkdown(article) => {
                 log::info!(
@@ -84,11 +271,8 @@ impl Job {
                     "ConvertHtmlToMarkdown completed for article: {}",
                     article.id
                 );
-                result
+                result.map(|_| None)
             }
-        };
-
-        log::info!("Finished processing job: {:?} (ID: {})", self, job_id);
-        Ok((job_id, result))
+        }
     }
 }
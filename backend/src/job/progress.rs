@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::models::JobStatus;
+
+/// Published on the queue's event channel and streamed by `/job/{id}/events`.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobEvent {
+    pub job_id: Uuid,
+    pub parent_id: Option<Uuid>,
+    #[serde(flatten)]
+    pub kind: JobEventKind,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum JobEventKind {
+    Status {
+        status: JobStatus,
+        message: Option<String>,
+    },
+    ItemStarted {
+        item: String,
+    },
+    ItemSucceeded {
+        item: String,
+        progress: ProgressSnapshot,
+    },
+    ItemFailed {
+        item: String,
+        reason: String,
+        progress: ProgressSnapshot,
+    },
+    /// Aggregate progress of a parent job's children, with the completed
+    /// ones counted by result.
+    Progress {
+        progress: ProgressSnapshot,
+        outcomes: BTreeMap<String, i64>,
+    },
+}
+
+impl JobEventKind {
+    /// Used as the SSE `event:` field.
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobEventKind::Status { .. } => "status",
+            JobEventKind::ItemStarted { .. } => "item_started",
+            JobEventKind::ItemSucceeded { .. } => "item_succeeded",
+            JobEventKind::ItemFailed { .. } => "item_failed",
+            JobEventKind::Progress { .. } => "progress",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ProgressSnapshot {
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub percentage: f64,
+    /// Estimated from the average time per finished item so far.
+    pub eta_seconds: Option<u64>,
+}
+
+impl ProgressSnapshot {
+    pub fn compute(total: usize, succeeded: usize, failed: usize, elapsed: Duration) -> Self {
+        let finished = succeeded + failed;
+        let percentage = if total == 0 {
+            100.0
+        } else {
+            (finished as f64 / total as f64 * 100.0).min(100.0)
+        };
+        let eta_seconds = (finished > 0).then(|| {
+            let remaining = total.saturating_sub(finished) as f64;
+            (elapsed.as_secs_f64() / finished as f64 * remaining).round() as u64
+        });
+
+        Self {
+            total,
+            succeeded,
+            failed,
+            percentage,
+            eta_seconds,
+        }
+    }
+}
+
+/// Per-item progress reporter handed to a running job.
+#[derive(Clone)]
+pub struct JobProgress {
+    inner: Arc<ProgressState>,
+}
+
+struct ProgressState {
+    job_id: Uuid,
+    parent_id: Option<Uuid>,
+    events: broadcast::Sender<JobEvent>,
+    total: AtomicUsize,
+    succeeded: AtomicUsize,
+    failed: AtomicUsize,
+    started: Instant,
+}
+
+impl JobProgress {
+    pub fn new(job_id: Uuid, parent_id: Option<Uuid>, events: broadcast::Sender<JobEvent>) -> Self {
+        Self {
+            inner: Arc::new(ProgressState {
+                job_id,
+                parent_id,
+                events,
+                total: AtomicUsize::new(0),
+                succeeded: AtomicUsize::new(0),
+                failed: AtomicUsize::new(0),
+                started: Instant::now(),
+            }),
+        }
+    }
+
+    /// A reporter nobody listens to, for work run outside the job queue.
+    pub fn detached() -> Self {
+        let (events, _) = broadcast::channel(1);
+        Self::new(Uuid::nil(), None, events)
+    }
+
+    pub fn set_total(&self, total: usize) {
+        self.inner.total.store(total, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> ProgressSnapshot {
+        ProgressSnapshot::compute(
+            self.inner.total.load(Ordering::Relaxed),
+            self.inner.succeeded.load(Ordering::Relaxed),
+            self.inner.failed.load(Ordering::Relaxed),
+            self.inner.started.elapsed(),
+        )
+    }
+
+    pub fn item_started(&self, item: impl ToString) {
+        self.emit(JobEventKind::ItemStarted {
+            item: item.to_string(),
+        });
+    }
+
+    pub fn item_succeeded(&self, item: impl ToString) {
+        self.inner.succeeded.fetch_add(1, Ordering::Relaxed);
+        self.emit(JobEventKind::ItemSucceeded {
+            item: item.to_string(),
+            progress: self.snapshot(),
+        });
+    }
+
+    pub fn item_failed(&self, item: impl ToString, reason: impl ToString) {
+        self.inner.failed.fetch_add(1, Ordering::Relaxed);
+        self.emit(JobEventKind::ItemFailed {
+            item: item.to_string(),
+            reason: reason.to_string(),
+            progress: self.snapshot(),
+        });
+    }
+
+    fn emit(&self, kind: JobEventKind) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.inner.events.send(JobEvent {
+            job_id: self.inner.job_id,
+            parent_id: self.inner.parent_id,
+            kind,
+        });
+    }
+}
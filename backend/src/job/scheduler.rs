@@ -0,0 +1,154 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use log::{error, info, warn};
+use tokio::time::sleep;
+
+use crate::config::ScheduleConfig;
+use crate::errors::{ConfigError, EnqueueError};
+use crate::models::{RunOutcome, ScheduledRun};
+use crate::services::data_processor::SyncMode;
+
+use super::{Job, JobQueue};
+
+/// A job enqueued whenever its cron expression fires. Expressions use the
+/// `cron` crate syntax, which includes seconds: `0 0 * * * *` is hourly.
+#[derive(Clone)]
+pub struct ScheduledTask {
+    pub name: String,
+    pub expression: String,
+    pub job: Job,
+    schedule: Schedule,
+}
+
+impl ScheduledTask {
+    pub fn new(name: &str, expression: &str, job: Job) -> Result<Self, ConfigError> {
+        let schedule = Schedule::from_str(expression).map_err(|e| {
+            ConfigError::Invalid(vec![format!(
+                "schedule.{} is not a valid cron expression: {:?} ({})",
+                name, expression, e
+            )])
+        })?;
+        Ok(Self {
+            name: name.to_string(),
+            expression: expression.to_string(),
+            job,
+            schedule,
+        })
+    }
+
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.schedule.after(&after).next()
+    }
+}
+
+pub struct Scheduler {
+    job_queue: Arc<JobQueue>,
+    tasks: Vec<ScheduledTask>,
+}
+
+impl Scheduler {
+    pub fn new(job_queue: Arc<JobQueue>, tasks: Vec<ScheduledTask>) -> Self {
+        Self { job_queue, tasks }
+    }
+
+    /// Builds the schedules from the `[schedule]` config section. Unset or
+    /// empty expressions disable the corresponding schedule.
+    pub fn from_config(
+        job_queue: Arc<JobQueue>,
+        config: &ScheduleConfig,
+    ) -> Result<Self, ConfigError> {
+        let candidates = [
+            ("sync", &config.sync, Job::SyncAll(SyncMode::Incremental)),
+            (
+                "retry_failed_metadata",
+                &config.retry_failed_metadata,
+                Job::RetryFailedMetadata,
+            ),
+            (
+                "backfill_embeddings",
+                &config.backfill_embeddings,
+                Job::BackfillEmbeddings,
+            ),
+        ];
+
+        let mut tasks = Vec::new();
+        for (name, expression, job) in candidates {
+            match expression.as_deref().map(str::trim) {
+                Some(expression) if !expression.is_empty() => {
+                    tasks.push(ScheduledTask::new(name, expression, job)?)
+                }
+                _ => info!("No schedule configured for {}", name),
+            }
+        }
+        Ok(Self::new(job_queue, tasks))
+    }
+
+    pub fn tasks(&self) -> &[ScheduledTask] {
+        &self.tasks
+    }
+
+    /// Spawns one loop per schedule.
+    pub fn start(self: &Arc<Self>) {
+        for index in 0..self.tasks.len() {
+            let scheduler = Arc::clone(self);
+            tokio::spawn(async move {
+                let task = &scheduler.tasks[index];
+                info!("Scheduling {} with {:?}", task.name, task.expression);
+                loop {
+                    let Some(next) = task.next_after(Utc::now()) else {
+                        warn!("Schedule for {} has no upcoming runs", task.name);
+                        break;
+                    };
+                    sleep((next - Utc::now()).to_std().unwrap_or_default()).await;
+
+                    match scheduler.fire(task, next).await {
+                        Ok(run) => info!(
+                            "Scheduled run of {} {}: {}",
+                            task.name,
+                            run.status,
+                            run.message.as_deref().unwrap_or("")
+                        ),
+                        Err(e) => error!("Failed to record scheduled run of {}: {}", task.name, e),
+                    }
+                }
+            });
+        }
+    }
+
+    /// Enqueues the task's job unless a job of the same type is still active,
+    /// whether scheduled or started by hand, and records what happened.
+    pub async fn fire(
+        &self,
+        task: &ScheduledTask,
+        scheduled_for: DateTime<Utc>,
+    ) -> Result<ScheduledRun, anyhow::Error> {
+        let run = match self.job_queue.enqueue_exclusive(task.job.clone()).await {
+            Ok(job_id) => ScheduledRun::new(
+                &task.name,
+                scheduled_for,
+                RunOutcome::Enqueued,
+                Some(job_id),
+                None,
+            ),
+            Err(e @ EnqueueError::AlreadyActive { .. }) => ScheduledRun::new(
+                &task.name,
+                scheduled_for,
+                RunOutcome::Skipped,
+                None,
+                Some(e.to_string()),
+            ),
+            Err(e) => ScheduledRun::new(
+                &task.name,
+                scheduled_for,
+                RunOutcome::Failed,
+                None,
+                Some(e.to_string()),
+            ),
+        };
+        let mut conn = self.job_queue.db_pool.get()?;
+        Ok(run.store(&mut conn)?)
+    }
+}
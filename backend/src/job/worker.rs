@@ -1,69 +1,304 @@
-use log::{error, info};
+use chrono::Utc;
+use futures::FutureExt;
+use log::{error, info, warn};
+use std::any::Any;
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
-use tokio::sync::mpsc;
-use tokio::sync::Mutex as TokioMutex;
+use tokio::task::JoinHandle;
 use tokio::time::sleep;
+use uuid::Uuid;
 
-use crate::models::JobStatus;
-use crate::services::DataProcessor;
+use crate::db::DbPool;
+use crate::models::{JobInfo, JobStatus};
+use crate::services::{DataProcessor, MetadataGenerator};
 
-use super::{Job, JobQueue};
+use super::{Job, JobContext, JobControl, JobEventKind, JobProgress, JobQueue, ProgressSnapshot};
 
 impl JobQueue {
-    pub fn spawn_workers(&self, receiver: mpsc::Receiver<Job>, data_processor: Arc<DataProcessor>) {
-        let job_statuses = self.job_statuses.clone();
-        let rate_limit = self.rate_limit;
+    pub fn spawn_workers(
+        &self,
+        data_processor: Arc<DataProcessor>,
+        metadata_generator: Arc<MetadataGenerator>,
+    ) {
+        self.spawn_claiming_workers(None, data_processor, metadata_generator);
+    }
 
-        // Create a single shared receiver
-        let shared_receiver = Arc::new(TokioMutex::new(receiver));
+    /// Starts workers that only run `root_id` and its children, leaving every
+    /// other queued job to the server's workers.
+    pub fn spawn_job_tree_workers(
+        &self,
+        root_id: Uuid,
+        data_processor: Arc<DataProcessor>,
+        metadata_generator: Arc<MetadataGenerator>,
+    ) {
+        self.spawn_claiming_workers(Some(root_id), data_processor, metadata_generator);
+    }
 
-        for _ in 0..self.num_workers {
-            let job_statuses = job_statuses.clone();
+    fn spawn_claiming_workers(
+        &self,
+        tree: Option<Uuid>,
+        data_processor: Arc<DataProcessor>,
+        metadata_generator: Arc<MetadataGenerator>,
+    ) {
+        for worker_id in 0..self.num_workers {
             let data_processor = data_processor.clone();
-            let shared_receiver = shared_receiver.clone();
+            let metadata_generator = metadata_generator.clone();
             let job_queue = self.clone(); // Clone the JobQueue for use in the spawned task
 
-            info!("Starting worker");
+            info!("Starting worker {}", worker_id);
 
             tokio::spawn(async move {
                 loop {
-                    let job = shared_receiver.lock().await.recv().await;
-
-                    match job {
-                        Some(job) => {
-                            let (job_id, result) = job
-                                .process(&data_processor, &job_queue)
-                                .await
-                                .expect("Failed to process job");
-
-                            Self::update_job_status(&job_statuses, job_id, JobStatus::Running);
-                            info!("Starting sync job: {}", job_id);
-
-                            match result {
-                                Ok(_) => {
-                                    info!("Sync job completed successfully: {}", job_id);
-                                    Self::update_job_status(
-                                        &job_statuses,
-                                        job_id,
-                                        JobStatus::Completed,
-                                    );
-                                }
-                                Err(e) => {
-                                    let error_msg = format!("Sync job failed: {}", e);
-                                    error!("{}", error_msg);
-                                    Self::update_job_status(
-                                        &job_statuses,
-                                        job_id,
-                                        JobStatus::Failed(error_msg),
-                                    );
-                                }
-                            }
+                    let claimed = job_queue
+                        .db_pool
+                        .get()
+                        .map_err(anyhow::Error::from)
+                        .and_then(|mut conn| {
+                            JobInfo::claim_next(&mut conn, job_queue.lease, tree)
+                                .map_err(Into::into)
+                        });
+
+                    match claimed {
+                        Ok(Some(job_info)) => {
+                            job_queue
+                                .run_job(worker_id, job_info, &data_processor, &metadata_generator)
+                                .await;
+                            sleep(job_queue.rate_limit).await;
+                        }
+                        Ok(None) => sleep(job_queue.poll_interval).await,
+                        Err(e) => {
+                            error!("Worker {} failed to claim a job: {}", worker_id, e);
+                            sleep(job_queue.poll_interval).await;
                         }
-                        None => break, // Channel closed, exit the loop
                     }
-                    sleep(rate_limit).await;
                 }
             });
         }
     }
+
+    async fn run_job(
+        &self,
+        worker_id: usize,
+        job_info: JobInfo,
+        data_processor: &DataProcessor,
+        metadata_generator: &MetadataGenerator,
+    ) {
+        info!(
+            "Worker {} starting {} job: {} (attempt {}/{})",
+            worker_id, job_info.job_type, job_info.id, job_info.attempts, job_info.max_attempts
+        );
+
+        self.emit_status(&job_info, JobStatus::Running, None);
+
+        let job = match serde_json::from_value::<Job>(job_info.payload.clone()) {
+            Ok(job) => job,
+            Err(e) => {
+                // Retrying cannot fix a payload that doesn't decode
+                self.record_failure(&job_info, &format!("Invalid job payload: {}", e), false);
+                self.report_to_parent(&job_info);
+                return;
+            }
+        };
+
+        let control = self.register_control(job_info.id);
+        let heartbeat = self.spawn_heartbeat(job_info.id, control.clone());
+        let progress = JobProgress::new(job_info.id, job_info.parent_id, self.events.clone());
+        let ctx = JobContext {
+            job_id: job_info.id,
+            control: &control,
+            progress: &progress,
+            job_queue: self,
+            processor: data_processor,
+            metadata_generator,
+        };
+        // A panicking job must not take the worker down with it
+        let result = AssertUnwindSafe(job.process(&ctx))
+            .catch_unwind()
+            .await
+            .unwrap_or_else(|panic| {
+                Err(anyhow::anyhow!("Job panicked: {}", panic_message(&*panic)))
+            });
+        heartbeat.abort();
+        // A failure may have raced a cancellation the heartbeat hasn't seen yet
+        if result.is_err() {
+            follow_stored_requests(&self.db_pool, job_info.id, &control);
+        }
+        self.unregister_control(job_info.id);
+
+        if result.is_err() && control.is_cancelled() {
+            info!("Job cancelled: {}", job_info.id);
+            let cancelled = self
+                .db_pool
+                .get()
+                .map_err(anyhow::Error::from)
+                .and_then(|mut conn| {
+                    JobInfo::transition(
+                        &mut conn,
+                        job_info.id,
+                        &[JobStatus::Running, JobStatus::Paused],
+                        JobStatus::Cancelled,
+                        "Stopped after cancellation",
+                    )
+                    .map_err(Into::into)
+                });
+            match cancelled {
+                Ok(true) => self.emit_status(&job_info, JobStatus::Cancelled, None),
+                Ok(false) => {}
+                Err(e) => error!("Failed to mark job {} cancelled: {}", job_info.id, e),
+            }
+            self.report_to_parent(&job_info);
+            return;
+        }
+
+        match result {
+            Ok(output) => {
+                info!("Job completed successfully: {}", job_info.id);
+                let completed =
+                    self.db_pool
+                        .get()
+                        .map_err(anyhow::Error::from)
+                        .and_then(|mut conn| {
+                            job_info
+                                .mark_completed(&mut conn, output)
+                                .map_err(Into::into)
+                        });
+                match completed {
+                    Ok(()) => self.emit_status(&job_info, JobStatus::Completed, None),
+                    Err(e) => error!("Failed to mark job {} completed: {}", job_info.id, e),
+                }
+            }
+            Err(e) => self.record_failure(&job_info, &e.to_string(), true),
+        }
+        self.report_to_parent(&job_info);
+    }
+
+    /// Refreshes the job's heartbeat until aborted, so other workers don't
+    /// reclaim it while it runs, and applies the pause, resume and cancel
+    /// requests stored with it.
+    fn spawn_heartbeat(&self, job_id: Uuid, control: JobControl) -> JoinHandle<()> {
+        let db_pool = self.db_pool.clone();
+        let interval = self.poll_interval.min(self.lease / 3);
+        tokio::spawn(async move {
+            loop {
+                sleep(interval).await;
+                follow_stored_requests(&db_pool, job_id, &control);
+            }
+        })
+    }
+
+    pub(crate) fn emit_status(
+        &self,
+        job_info: &JobInfo,
+        status: JobStatus,
+        message: Option<String>,
+    ) {
+        self.emit(
+            job_info.id,
+            job_info.parent_id,
+            JobEventKind::Status { status, message },
+        );
+    }
+
+    /// Publishes the aggregate progress of `job_info`'s parent, if it has one.
+    fn report_to_parent(&self, job_info: &JobInfo) {
+        let Some(parent_id) = job_info.parent_id else {
+            return;
+        };
+
+        let progress = self
+            .db_pool
+            .get()
+            .map_err(anyhow::Error::from)
+            .and_then(|mut conn| {
+                let parent = JobInfo::find(&mut conn, parent_id)?
+                    .ok_or_else(|| anyhow::anyhow!("Parent job {} not found", parent_id))?;
+                let counts = JobInfo::child_counts(&mut conn, parent_id)?;
+                Ok((parent, counts))
+            });
+
+        match progress {
+            Ok((parent, counts)) => {
+                let started_at = parent.started_at.unwrap_or(parent.created_at);
+                let elapsed = (Utc::now() - started_at).to_std().unwrap_or_default();
+                let progress = ProgressSnapshot::compute(
+                    counts.total as usize,
+                    counts.done as usize,
+                    counts.failed as usize,
+                    elapsed,
+                );
+                self.emit(
+                    parent.id,
+                    parent.parent_id,
+                    JobEventKind::Progress {
+                        progress,
+                        outcomes: counts.outcomes,
+                    },
+                );
+            }
+            Err(e) => error!("Failed to report progress of job {}: {}", parent_id, e),
+        }
+    }
+
+    fn record_failure(&self, job_info: &JobInfo, error_msg: &str, retryable: bool) {
+        let retry_at = (retryable && job_info.attempts < job_info.max_attempts).then(|| {
+            let delay = self.retry_delay(job_info.attempts);
+            Utc::now()
+                + chrono::Duration::from_std(delay).unwrap_or_else(|_| chrono::Duration::zero())
+        });
+
+        let status = self
+            .db_pool
+            .get()
+            .map_err(anyhow::Error::from)
+            .and_then(|mut conn| {
+                job_info
+                    .mark_failed(&mut conn, error_msg, retry_at)
+                    .map_err(Into::into)
+            });
+
+        match status {
+            Ok(status) => {
+                if status == JobStatus::DeadLetter {
+                    error!(
+                        "Job {} failed after {} attempts, moved to dead letter: {}",
+                        job_info.id, job_info.attempts, error_msg
+                    );
+                } else {
+                    warn!(
+                        "Job {} failed (attempt {}/{}), retrying at {:?}: {}",
+                        job_info.id, job_info.attempts, job_info.max_attempts, retry_at, error_msg
+                    );
+                }
+                self.emit_status(job_info, status, Some(error_msg.to_string()));
+            }
+            Err(e) => error!("Failed to record failure of job {}: {}", job_info.id, e),
+        }
+    }
+}
+
+/// Records a heartbeat for the job and applies the operator requests stored
+/// with it, which may come from another process.
+fn follow_stored_requests(db_pool: &DbPool, job_id: Uuid, control: &JobControl) {
+    let beat = db_pool
+        .get()
+        .map_err(anyhow::Error::from)
+        .and_then(|mut conn| JobInfo::heartbeat(&mut conn, job_id).map_err(Into::into));
+    match beat {
+        Ok(Some(job)) => match job.status() {
+            Ok(status) => control.follow(status, job.cancel_requested),
+            Err(e) => warn!("Job {} has an unreadable status: {}", job_id, e),
+        },
+        Ok(None) => warn!("Job {} disappeared while running", job_id),
+        Err(e) => warn!("Failed to record heartbeat of job {}: {}", job_id, e),
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
 }
@@ -1,3 +1,4 @@
+pub mod config;
 pub mod db;
 pub mod errors;
 pub mod job;
@@ -5,3 +6,4 @@ pub mod models;
 pub mod routes;
 pub mod schema;
 pub mod services;
+pub mod utils;
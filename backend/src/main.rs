@@ -4,7 +4,7 @@ use actix_web::middleware::Logger;
 use actix_web::{web, App, HttpServer};
 
 use backend::services::search::SearchService;
-use backend::services::{AIService, EmbeddingService, MetadataGenerator};
+use backend::services::{AIService, MetadataGenerator};
 use dotenv::dotenv;
 use log::{error, info};
 use log4rs;
@@ -12,8 +12,11 @@ use std::env;
 use std::sync::Arc;
 // use std::process::Command;
 
+use backend::config::{self, Config};
 use backend::db;
 use backend::db::DbPool;
+use backend::job::{JobQueue, Scheduler};
+use backend::models::embedding::ensure_vector_indexes;
 use backend::routes;
 use backend::services::{chat::chat_server::ChatServer, data_processor::DataProcessor};
 
@@ -32,6 +35,16 @@ async fn main() -> std::io::Result<()> {
 
     info!("Starting application");
 
+    let config = match Config::load().and_then(|config| config.validate().map(|_| config)) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("Failed to load configuration: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, e));
+        }
+    };
+    config::init(config.clone()).expect("Configuration initialized twice");
+    let config = Arc::new(config);
+
     // Python Service for Embedding
     // let mut python_service = Command::new("python")
     //     .arg("python_services/embedding_service.py")
@@ -39,7 +52,7 @@ async fn main() -> std::io::Result<()> {
     //     .expect("Failed to start Python service");
     // info!("Python service started with PID: {:?}", python_service.id());
 
-    let pool: DbPool = db::init_pool();
+    let pool: DbPool = db::init_pool_with(&config.database);
     let arc_pool = Arc::new(pool.clone());
 
     info!("Initializing DataProcessor");
@@ -50,46 +63,86 @@ async fn main() -> std::io::Result<()> {
     );
     info!("DataProcessor initialized");
 
+    // Loaded once by the DataProcessor and shared with everything else
+    let embedding_service = data_processor.embedding_service().clone();
+
+    // Searches only use an index when one exists for the model's dimension
+    match pool.get() {
+        Ok(mut conn) => {
+            if let Err(e) = ensure_vector_indexes(&mut conn, embedding_service.dimensions()) {
+                error!("Failed to create vector indexes: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to get DB connection for vector indexes: {}", e),
+    }
+
     info!("Initializing ChatServer");
-    let chat_server = ChatServer::new(arc_pool.clone()).start();
+    let chat_server = ChatServer::new(arc_pool.clone(), embedding_service.clone()).start();
     info!("ChatServer initialized and started");
 
-    info!("Initializing EmbeddingService");
-    let embedding_service = Arc::new(EmbeddingService::new());
-    info!("EmbeddingService initialized");
-
     info!("Initializing AIService");
-    let ai_service = Arc::new(AIService::new());
+    let ai_service = Arc::new(AIService::with_config(&config.ai));
     info!("AIService initialized");
 
     info!("Initializing SearchService");
-    let search_service = Arc::new(SearchService::new(arc_pool.clone(), ai_service.clone()));
+    let search_service = Arc::new(SearchService::with_services(
+        arc_pool.clone(),
+        ai_service.clone(),
+        embedding_service.clone(),
+        config.search.clone(),
+    ));
     info!("SearchService initialized");
 
     info!("Initializing MetadataGenerator");
-    let metadata_generator = Arc::new(
-        MetadataGenerator::new(6)
-            .await
-            .expect("Failed to create MetadataGenerator"),
-    );
+    let metadata_generator = Arc::new(MetadataGenerator::with_processor(
+        data_processor.clone(),
+        arc_pool.clone(),
+        config.metadata.concurrency,
+    ));
     info!("MetadataGenerator initialized");
 
+    info!("Initializing JobQueue");
+    let job_queue = Arc::new(JobQueue::with_config(
+        arc_pool.clone(),
+        data_processor.clone(),
+        metadata_generator.clone(),
+        &config.jobs,
+    ));
+    info!("JobQueue initialized");
+
+    info!("Initializing Scheduler");
+    let scheduler = match Scheduler::from_config(job_queue.clone(), &config.schedule) {
+        Ok(scheduler) => Arc::new(scheduler),
+        Err(e) => {
+            error!("Failed to initialize Scheduler: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, e));
+        }
+    };
+    scheduler.start();
+    info!(
+        "Scheduler initialized with {} schedules",
+        scheduler.tasks().len()
+    );
+
     // Start the server
-    info!("Server listening on 127.0.0.1:3000");
+    let bind_address = config.bind_address();
+    info!("Server listening on {}:{}", bind_address.0, bind_address.1);
     let server = HttpServer::new(move || {
         App::new()
+            .app_data(web::Data::new(config.clone()))
             .app_data(web::Data::new(arc_pool.clone()))
             .app_data(web::Data::new(data_processor.clone()))
+            .app_data(web::Data::new(job_queue.clone()))
+            .app_data(web::Data::new(scheduler.clone()))
             .app_data(web::Data::new(chat_server.clone()))
             .app_data(web::Data::new(embedding_service.clone()))
             .app_data(web::Data::new(search_service.clone()))
             .app_data(web::Data::new(ai_service.clone()))
-            .app_data(web::Data::new(metadata_generator.clone()))
             .wrap(Logger::default())
             .wrap(Cors::permissive())
             .configure(routes::init_routes)
     })
-    .bind("127.0.0.1:3000")?
+    .bind(bind_address)?
     .run();
 
     let server_result = server.await;
@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::schema::article_removals;
+
+/// Why an article was archived by the sync reconciliation pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemovalReason {
+    /// The article no longer appears in its Help Scout collection.
+    Deleted,
+    /// The article still exists upstream but is no longer published.
+    Unpublished,
+}
+
+impl RemovalReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RemovalReason::Deleted => "deleted",
+            RemovalReason::Unpublished => "unpublished",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+#[diesel(table_name = article_removals)]
+pub struct ArticleRemoval {
+    pub id: Uuid,
+    pub article_id: Uuid,
+    pub helpscout_article_id: Option<String>,
+    pub title: String,
+    pub reason: String,
+    pub removed_at: DateTime<Utc>,
+}
+
+impl ArticleRemoval {
+    pub fn new(
+        article_id: Uuid,
+        helpscout_article_id: Option<String>,
+        title: String,
+        reason: RemovalReason,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            article_id,
+            helpscout_article_id,
+            title,
+            reason: reason.as_str().to_string(),
+            removed_at: Utc::now(),
+        }
+    }
+
+    pub fn store(&self, conn: &mut PgConnection) -> Result<Self, diesel::result::Error> {
+        diesel::insert_into(article_removals::table)
+            .values(self)
+            .get_result(conn)
+    }
+
+    pub fn load_recent(
+        conn: &mut PgConnection,
+        limit: i64,
+    ) -> Result<Vec<ArticleRemoval>, diesel::result::Error> {
+        article_removals::table
+            .order(article_removals::removed_at.desc())
+            .limit(limit)
+            .load::<ArticleRemoval>(conn)
+    }
+}
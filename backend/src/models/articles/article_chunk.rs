@@ -2,7 +2,8 @@ use diesel::prelude::*;
 use uuid::Uuid;
 
 use super::Article;
-use crate::schema::article_chunks;
+use crate::models::embedding::Embedding;
+use crate::schema::{article_chunks, embeddings};
 
 #[derive(Queryable, Insertable)]
 #[diesel(table_name = crate::schema::article_chunks)]
@@ -12,6 +13,13 @@ pub struct ArticleChunk {
     pub content: String,
     pub is_title: bool,
     pub embedding_id: Option<Uuid>,
+    /// The `EmbeddingGeneration` the chunk belongs to.
+    pub generation_id: i32,
+    /// Headings the chunk sits under, outermost first.
+    pub heading_path: Vec<String>,
+    /// Character range of `content` in the article's `markdown_content`.
+    pub start_offset: Option<i32>,
+    pub end_offset: Option<i32>,
 }
 
 impl ArticleChunk {
@@ -21,63 +29,36 @@ impl ArticleChunk {
             .get_result(conn)?;
         Ok(chunk)
     }
+
+    /// Inserts `embeddings` and then `chunks`, which may reference them, in
+    /// one transaction of two multi-row inserts.
+    pub fn store_with_embeddings(
+        conn: &mut PgConnection,
+        chunks: &[ArticleChunk],
+        chunk_embeddings: &[Embedding],
+    ) -> Result<(), diesel::result::Error> {
+        conn.transaction(|conn| {
+            diesel::insert_into(embeddings::table)
+                .values(chunk_embeddings)
+                .execute(conn)?;
+            diesel::insert_into(article_chunks::table)
+                .values(chunks)
+                .execute(conn)?;
+            Ok(())
+        })
+    }
 }
 
 impl Article {
-    pub fn create_chunks(&self, chunk_size: usize) -> Vec<ArticleChunk> {
-        let mut chunks = Vec::new();
-
-        // Add title as a separate chunk
-        let title_chunk = ArticleChunk {
-            id: Uuid::new_v4(),
-            article_id: self.id,
-            content: self.title.clone(),
-            is_title: true,
-            embedding_id: None,
-        };
-        chunks.push(title_chunk);
-
-        if let Some(content) = &self.markdown_content {
-            // Split content into words
-            let words: Vec<&str> = content.split_whitespace().collect();
-            let mut current_chunk = String::new();
-            let mut word_count = 0;
-
-            for word in words {
-                if word_count >= chunk_size {
-                    // If the current chunk has reached or exceeded the chunk size,
-                    // add it to the chunks vector and start a new chunk
-                    chunks.push(ArticleChunk {
-                        id: Uuid::new_v4(),
-                        article_id: self.id,
-                        content: current_chunk.trim().to_string(),
-                        is_title: false,
-                        embedding_id: None,
-                    });
-                    current_chunk.clear();
-                    word_count = 0;
-                }
-
-                // Add the word to the current chunk
-                if !current_chunk.is_empty() {
-                    current_chunk.push(' ');
-                }
-                current_chunk.push_str(word);
-                word_count += 1;
-            }
-
-            // Add any remaining content as the last chunk
-            if !current_chunk.is_empty() {
-                chunks.push(ArticleChunk {
-                    id: Uuid::new_v4(),
-                    article_id: self.id,
-                    content: current_chunk.trim().to_string(),
-                    is_title: false,
-                    embedding_id: None,
-                });
-            }
-        }
-
-        chunks
+    /// Removes the chunks and chunk embeddings generated from this article's
+    /// previous content, in every generation.
+    pub fn delete_chunks(&self, conn: &mut PgConnection) -> Result<(), diesel::result::Error> {
+        conn.transaction(|conn| {
+            diesel::delete(article_chunks::table.filter(article_chunks::article_id.eq(self.id)))
+                .execute(conn)?;
+            diesel::delete(embeddings::table.filter(embeddings::article_id.eq(self.id)))
+                .execute(conn)?;
+            Ok(())
+        })
     }
 }
@@ -18,6 +18,10 @@ pub mod update;
 pub use self::article_chunk::*;
 pub use self::parse::*;
 
+/// Prefix of the `helpscout_article_id` of articles imported from a Markdown
+/// directory rather than synced from a document source.
+pub const MARKDOWN_IMPORT_PREFIX: &str = "markdown:";
+
 #[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, Insertable, Associations)]
 #[diesel(table_name = crate::schema::articles)]
 #[diesel(belongs_to(Collection, foreign_key = collection_id))]
@@ -41,6 +45,13 @@ pub struct Article {
     pub paragraph_description_embedding: Option<Vector>,
     pub bullet_points_embedding: Option<Vector>,
     pub keywords_embedding: Option<Vector>,
+    pub archived_at: Option<DateTime<Utc>>,
+    /// Set for articles imported from a Markdown directory.
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    /// Model the metadata vectors were generated with, see `Embedder::model`.
+    #[serde(default)]
+    pub metadata_embedding_model: Option<String>,
 }
 
 impl Article {
@@ -71,9 +82,28 @@ impl Article {
             paragraph_description_embedding: None,
             bullet_points_embedding: None,
             keywords_embedding: None,
+            archived_at: None,
+            content_hash: None,
+            metadata_embedding_model: None,
         }
     }
 
+    pub fn is_archived(&self) -> bool {
+        self.archived_at.is_some()
+    }
+
+    /// The keywords an imported article took from its front-matter, which
+    /// metadata generation keeps instead of replacing them.
+    pub fn imported_keywords(&self) -> Option<Vec<Option<String>>> {
+        let imported = self
+            .helpscout_article_id
+            .as_deref()
+            .is_some_and(|id| id.starts_with(MARKDOWN_IMPORT_PREFIX));
+        self.keywords
+            .clone()
+            .filter(|keywords| imported && keywords.iter().any(Option::is_some))
+    }
+
     pub fn ids_from_articles(articles: &[Article]) -> Vec<Uuid> {
         articles.iter().map(|article| article.id).collect()
     }
@@ -87,6 +117,18 @@ impl Article {
         articles.find(article_id).first(conn).optional()
     }
 
+    pub fn find_by_helpscout_id(
+        conn: &mut PgConnection,
+        helpscout_id: &str,
+    ) -> Result<Option<Article>, diesel::result::Error> {
+        use crate::schema::articles::dsl::*;
+
+        articles
+            .filter(helpscout_article_id.eq(helpscout_id))
+            .first(conn)
+            .optional()
+    }
+
     pub fn get_all_by_ids(
         conn: &mut PgConnection,
         article_ids: &[Uuid],
@@ -118,6 +160,63 @@ impl Article {
         articles::table.load::<Article>(conn)
     }
 
+    /// Loads every article that has not been archived by a sync.
+    pub fn load_active(conn: &mut PgConnection) -> Result<Vec<Article>, diesel::result::Error> {
+        articles::table
+            .filter(articles::archived_at.is_null())
+            .load::<Article>(conn)
+    }
+
+    /// Loads active articles that have no chunks embedded by `model` in
+    /// generation `generation_id` yet, i.e. were never embedded with it or had
+    /// their chunks dropped when their content changed.
+    pub fn load_active_without_embeddings(
+        conn: &mut PgConnection,
+        generation_id: i32,
+        model: &str,
+    ) -> Result<Vec<Article>, diesel::result::Error> {
+        use crate::schema::{article_chunks, embeddings};
+        use diesel::dsl::{exists, not};
+
+        articles::table
+            .filter(articles::archived_at.is_null())
+            .filter(not(exists(
+                article_chunks::table
+                    .inner_join(
+                        embeddings::table
+                            .on(embeddings::id.nullable().eq(article_chunks::embedding_id)),
+                    )
+                    .filter(article_chunks::article_id.eq(articles::id))
+                    .filter(article_chunks::generation_id.eq(generation_id))
+                    .filter(embeddings::model.eq(model)),
+            )))
+            .load::<Article>(conn)
+    }
+
+    /// Loads every article that came from a document source and is still served
+    /// in search. Articles imported from Markdown are left to the importer.
+    pub fn load_active_synced(conn: &mut PgConnection) -> Result<Vec<Article>, diesel::result::Error> {
+        articles::table
+            .filter(articles::helpscout_article_id.is_not_null())
+            .filter(
+                articles::helpscout_article_id
+                    .not_like(format!("{}%", escape_like(MARKDOWN_IMPORT_PREFIX))),
+            )
+            .filter(articles::archived_at.is_null())
+            .load::<Article>(conn)
+    }
+
+    /// Loads the active articles whose source ID starts with `prefix`.
+    pub fn load_active_with_source_prefix(
+        conn: &mut PgConnection,
+        prefix: &str,
+    ) -> Result<Vec<Article>, diesel::result::Error> {
+        articles::table
+            .filter(articles::helpscout_article_id.like(format!("{}%", escape_like(prefix))))
+            .filter(articles::archived_at.is_null())
+            .load::<Article>(conn)
+    }
+
     pub fn load_batch(
         conn: &mut PgConnection,
         offset: usize,
@@ -146,6 +245,33 @@ impl Article {
 
         Ok(article)
     }
+
+    /// Inserts the article, or refreshes the synced fields of the existing row
+    /// with the same `helpscout_article_id`. The stored row keeps its `id`,
+    /// `created_at` and generated metadata.
+    pub fn upsert(&self, conn: &mut PgConnection) -> Result<Self, diesel::result::Error> {
+        use diesel::upsert::excluded;
+
+        log::info!("Upserting article: ID:{:?}, Title: {:?}", self.id, self.title);
+
+        diesel::insert_into(articles::table)
+            .values(self)
+            .on_conflict(articles::helpscout_article_id)
+            .do_update()
+            .set((
+                articles::collection_id.eq(excluded(articles::collection_id)),
+                articles::helpscout_collection_id.eq(excluded(articles::helpscout_collection_id)),
+                articles::title.eq(excluded(articles::title)),
+                articles::slug.eq(excluded(articles::slug)),
+                articles::html_content.eq(excluded(articles::html_content)),
+                articles::markdown_content.eq(excluded(articles::markdown_content)),
+                articles::updated_at.eq(excluded(articles::updated_at)),
+                articles::archived_at.eq(excluded(articles::archived_at)),
+                articles::content_hash.eq(excluded(articles::content_hash)),
+                articles::version.eq(articles::version + 1),
+            ))
+            .get_result(conn)
+    }
 }
 
 impl HasTable for Article {
@@ -155,3 +281,11 @@ impl HasTable for Article {
         articles::table
     }
 }
+
+/// Escapes `LIKE` wildcards so `value` only matches literally.
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
@@ -1,4 +1,5 @@
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct ArticleResponse {
@@ -13,33 +14,90 @@ pub struct ArticleData {
     pub items: Vec<ArticleRef>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ArticleRef {
     pub id: String,
-    pub number: i32,
+    /// Help Scout's article number; unknown for other sources.
+    pub number: Option<i32>,
     #[serde(rename = "collectionId")]
     pub collection_id: String,
-    pub status: String,
+    /// Help Scout's publication state; other sources only list what they
+    /// publish and leave it unset.
+    pub status: Option<String>,
     #[serde(rename = "hasDraft")]
     pub has_draft: bool,
     pub name: String,
     #[serde(rename = "publicUrl")]
     pub public_url: String,
-    pub popularity: f64,
+    pub popularity: Option<f64>,
     #[serde(rename = "viewCount")]
-    pub view_count: i32,
+    pub view_count: Option<i32>,
     #[serde(rename = "createdBy")]
-    pub created_by: i32,
+    pub created_by: Option<i32>,
     #[serde(rename = "updatedBy")]
     pub updated_by: Option<i32>,
     #[serde(rename = "createdAt")]
-    pub created_at: String,
+    pub created_at: Option<String>,
     #[serde(rename = "updatedAt")]
     pub updated_at: Option<String>,
     #[serde(rename = "lastPublishedAt")]
     pub last_published_at: Option<String>,
 }
 
+impl ArticleRef {
+    /// A published article reference from a source other than Help Scout,
+    /// which only knows an ID, a title, a URL and possibly a modification time.
+    pub fn new(
+        id: String,
+        collection_id: String,
+        name: String,
+        public_url: String,
+        modified_at: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            id,
+            number: None,
+            collection_id,
+            status: None,
+            has_draft: false,
+            name,
+            public_url,
+            popularity: None,
+            view_count: None,
+            created_by: None,
+            updated_by: None,
+            created_at: None,
+            updated_at: modified_at.map(|modified_at| modified_at.to_rfc3339()),
+            last_published_at: None,
+        }
+    }
+
+    /// References without a status come from sources that only list
+    /// published documents.
+    pub fn is_published(&self) -> bool {
+        self.status
+            .as_deref()
+            .is_none_or(|status| status == "published")
+    }
+
+    pub fn last_modified_at(&self) -> Option<DateTime<Utc>> {
+        latest_timestamp(&[
+            self.created_at.as_deref(),
+            self.updated_at.as_deref(),
+            self.last_published_at.as_deref(),
+        ])
+    }
+
+    /// Whether Help Scout has changed this article since `stored_at`. Articles
+    /// without a parseable timestamp are always treated as changed.
+    pub fn is_newer_than(&self, stored_at: DateTime<Utc>) -> bool {
+        match self.last_modified_at() {
+            Some(modified_at) => modified_at > stored_at,
+            None => true,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct ArticleFullResponse {
     pub article: ArticleFull,
@@ -75,3 +133,22 @@ pub struct ArticleFull {
     #[serde(rename = "lastPublishedAt")]
     pub last_published_at: Option<String>,
 }
+
+impl ArticleFull {
+    pub fn last_modified_at(&self) -> Option<DateTime<Utc>> {
+        latest_timestamp(&[
+            Some(self.created_at.as_str()),
+            Some(self.updated_at.as_str()),
+            self.last_published_at.as_deref(),
+        ])
+    }
+}
+
+fn latest_timestamp(timestamps: &[Option<&str>]) -> Option<DateTime<Utc>> {
+    timestamps
+        .iter()
+        .flatten()
+        .filter_map(|timestamp| DateTime::parse_from_rfc3339(timestamp).ok())
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .max()
+}
@@ -1,19 +1,44 @@
+use diesel::dsl::sql;
 use diesel::prelude::*;
-use diesel::sql_types::Integer;
+use diesel::sql_types::{Bool, Double, Text};
 use diesel::ExpressionMethods;
 use log::info;
 use pgvector::Vector;
 use pgvector::VectorExpressionMethods;
+use serde::Serialize;
 use std::collections::HashMap;
 use uuid::Uuid;
 
-use super::Article;
+use super::{Article, ArticleChunk};
+use crate::config::SearchConfig;
+use crate::models::embedding::{has_dims, vector_with_dims};
+use crate::models::GenerationStatus;
+
+/// A chunk of an article that matched a search query.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkMatch {
+    pub chunk_id: Uuid,
+    pub content: String,
+    /// Headings the chunk sits under, outermost first.
+    pub heading_path: Vec<String>,
+    /// Character range of `content` in the article's `markdown_content`.
+    pub start_offset: Option<i32>,
+    pub end_offset: Option<i32>,
+    /// Cosine similarity to the query.
+    pub similarity: f64,
+}
 
 impl Article {
+    /// Articles of `collection_ids` whose metadata embeddings are closest to
+    /// `query_embedding`, which must come from `model`, with the cosine
+    /// similarity of the closest one. Metadata embedded by other models is
+    /// ignored.
     pub async fn find_relevant_articles_by_collection_ids(
         query_embedding: &Vector,
+        model: &str,
         conn: &mut PgConnection,
         collection_ids: &Vec<Uuid>,
+        limits: &SearchConfig,
     ) -> Result<Vec<(Article, f64)>, Box<dyn std::error::Error + Send + Sync>> {
         use crate::schema::articles;
         use diesel::prelude::*;
@@ -21,152 +46,234 @@ impl Article {
         info!("Finding relevant articles based on query embedding and collection IDs");
 
         let article_table = articles::table;
+        let dimensions = query_embedding.as_slice().len();
 
         let paragraph_description_results: Vec<(Article, Option<f64>)> = article_table
             .select((
                 articles::all_columns,
-                articles::paragraph_description_embedding
+                vector_with_dims("articles.paragraph_description_embedding", dimensions)
                     .cosine_distance(query_embedding)
                     .nullable(),
             ))
             .filter(articles::collection_id.eq_any(collection_ids))
-            .filter(articles::paragraph_description_embedding.is_not_null())
-            .order(articles::paragraph_description_embedding.cosine_distance(query_embedding))
-            .limit(3)
+            .filter(articles::archived_at.is_null())
+            .filter(articles::metadata_embedding_model.eq(model))
+            .filter(has_dims(
+                "articles.paragraph_description_embedding",
+                dimensions,
+            ))
+            .order(
+                vector_with_dims("articles.paragraph_description_embedding", dimensions)
+                    .cosine_distance(query_embedding),
+            )
+            .limit(limits.metadata_matches)
             .load::<(Article, Option<f64>)>(conn)?;
 
         let bullet_points_results: Vec<(Article, Option<f64>)> = article_table
             .select((
                 articles::all_columns,
-                articles::bullet_points_embedding
+                vector_with_dims("articles.bullet_points_embedding", dimensions)
                     .cosine_distance(query_embedding)
                     .nullable(),
             ))
             .filter(articles::collection_id.eq_any(collection_ids))
-            .filter(articles::bullet_points_embedding.is_not_null())
-            .order(articles::bullet_points_embedding.cosine_distance(query_embedding))
-            .limit(3)
+            .filter(articles::archived_at.is_null())
+            .filter(articles::metadata_embedding_model.eq(model))
+            .filter(has_dims("articles.bullet_points_embedding", dimensions))
+            .order(
+                vector_with_dims("articles.bullet_points_embedding", dimensions)
+                    .cosine_distance(query_embedding),
+            )
+            .limit(limits.metadata_matches)
             .load::<(Article, Option<f64>)>(conn)?;
 
         let keywords_results: Vec<(Article, Option<f64>)> = article_table
             .select((
                 articles::all_columns,
-                articles::keywords_embedding
+                vector_with_dims("articles.keywords_embedding", dimensions)
                     .cosine_distance(query_embedding)
                     .nullable(),
             ))
             .filter(articles::collection_id.eq_any(collection_ids))
-            .filter(articles::keywords_embedding.is_not_null())
-            .order(articles::keywords_embedding.cosine_distance(query_embedding))
-            .limit(3)
+            .filter(articles::archived_at.is_null())
+            .filter(articles::metadata_embedding_model.eq(model))
+            .filter(has_dims("articles.keywords_embedding", dimensions))
+            .order(
+                vector_with_dims("articles.keywords_embedding", dimensions)
+                    .cosine_distance(query_embedding),
+            )
+            .limit(limits.metadata_matches)
             .load::<(Article, Option<f64>)>(conn)?;
 
         // Combine all results
-        combine_and_deduplicate_results(vec![
-            paragraph_description_results,
-            bullet_points_results,
-            keywords_results,
-        ])
+        combine_and_deduplicate_results(
+            vec![
+                paragraph_description_results,
+                bullet_points_results,
+                keywords_results,
+            ],
+            limits.article_results,
+        )
     }
 
+    /// Ranks articles by their chunks in the active generation closest to
+    /// `query_embedding`, which must come from `model`; chunks embedded by
+    /// other models are ignored.
     pub async fn find_relevant_articles(
         query_embedding: &Vector,
+        model: &str,
         conn: &mut PgConnection,
+        limits: &SearchConfig,
     ) -> Result<Vec<(Article, f64)>, Box<dyn std::error::Error + Send + Sync>> {
+        let results = Self::find_relevant_chunks(query_embedding, model, conn, limits).await?;
+        Ok(results
+            .into_iter()
+            .map(|(article, score, _)| (article, score))
+            .collect())
+    }
+
+    /// Like `find_relevant_articles`, but also returns each article's best
+    /// matching chunks, best first, at most `limits.passages_per_article`.
+    /// Title chunks count towards the score but are not returned.
+    pub async fn find_relevant_chunks(
+        query_embedding: &Vector,
+        model: &str,
+        conn: &mut PgConnection,
+        limits: &SearchConfig,
+    ) -> Result<Vec<(Article, f64, Vec<ChunkMatch>)>, Box<dyn std::error::Error + Send + Sync>>
+    {
         info!("Finding relevant articles based on query embedding");
-        use crate::schema::{article_chunks, articles, embeddings};
+        use crate::schema::{article_chunks, articles, embedding_generations, embeddings};
         use diesel::prelude::*;
 
-        let results: Vec<(Article, f64, bool)> = article_chunks::table
+        let dimensions = query_embedding.as_slice().len();
+        let results: Vec<(Article, f64, ArticleChunk)> = article_chunks::table
             .inner_join(articles::table.on(articles::id.eq(article_chunks::article_id)))
             .inner_join(
                 embeddings::table.on(embeddings::id.nullable().eq(article_chunks::embedding_id)),
             )
+            .inner_join(embedding_generations::table)
             .select((
                 articles::all_columns,
-                embeddings::embedding_vector.cosine_distance(query_embedding),
-                article_chunks::is_title,
+                vector_with_dims("embeddings.embedding_vector", dimensions)
+                    .cosine_distance(query_embedding)
+                    .assume_not_null(),
+                article_chunks::all_columns,
             ))
-            .order(embeddings::embedding_vector.cosine_distance(query_embedding))
-            .limit(20)
+            .filter(articles::archived_at.is_null())
+            .filter(embedding_generations::status.eq(GenerationStatus::Active.as_str()))
+            .filter(embeddings::model.eq(model))
+            .filter(has_dims("embeddings.embedding_vector", dimensions))
+            .order(
+                vector_with_dims("embeddings.embedding_vector", dimensions)
+                    .cosine_distance(query_embedding),
+            )
+            .limit(limits.chunk_candidates)
             .load(conn)?;
 
         // Group by article and calculate weighted similarity
-        process_results(results)
+        Ok(rank_chunks(
+            results,
+            limits.article_results,
+            limits.passages_per_article,
+        ))
     }
 
+    /// Full-text search over the articles' `search_vector`, which weights
+    /// the title above keywords, bullet points and body. `query` is parsed
+    /// by `websearch_to_tsquery`, so it supports quoted phrases, `or` and
+    /// `-word`. Scores are `ts_rank_cd` normalized by document length and
+    /// into [0, 1), best first.
+    ///
+    /// `query` only ever reaches Postgres as a bound parameter; NUL bytes,
+    /// which Postgres rejects in text, are replaced by spaces.
     pub fn keyword_search(
         conn: &mut PgConnection,
         query: &str,
         ids: Option<&[Uuid]>,
-    ) -> Result<Vec<Article>, diesel::result::Error> {
+        limits: &SearchConfig,
+    ) -> Result<Vec<(Article, f64)>, diesel::result::Error> {
         use crate::schema::articles::dsl::*;
 
         info!("Performing keyword search for query: {}", query);
-        let words: Vec<String> = query.split_whitespace().map(|w| w.to_lowercase()).collect();
-
-        let mut query = articles.into_boxed();
-
-        // Add ILIKE conditions for each word
-        for word in &words {
-            let like_word = format!("%{}%", word);
-            query = query.filter(
-                title
-                    .ilike(like_word.clone())
-                    .or(markdown_content.ilike(like_word)),
-            );
+        let query = query.replace('\0', " ");
+        if query.trim().is_empty() {
+            return Ok(Vec::new());
         }
 
+        // `search_vector` is a generated column left out of the schema so
+        // that `Article` keeps loading every column
+        let tsquery = || {
+            sql::<Bool>("search_vector @@ websearch_to_tsquery('english', ")
+                .bind::<Text, _>(query.clone())
+                .sql(")")
+        };
+        // Normalization 1 | 32: divide by 1 + log(length), then rank / (rank + 1)
+        let rank = || {
+            sql::<Double>("ts_rank_cd(search_vector, websearch_to_tsquery('english', ")
+                .bind::<Text, _>(query.clone())
+                .sql("), 33)::float8")
+        };
+
+        let mut search = articles
+            .filter(archived_at.is_null())
+            .filter(tsquery())
+            .select((Article::as_select(), rank()))
+            .into_boxed();
+
         // Add filter for specific IDs if provided
         if let Some(article_ids) = ids {
-            query = query.filter(id.eq_any(article_ids));
+            search = search.filter(id.eq_any(article_ids));
         }
 
-        // Add ordering
-        query = query
-            .order(
-                (diesel::dsl::sql::<Integer>(&format!(
-                    "{}",
-                    words
-                        .iter()
-                        .map(|w| format!("CASE WHEN LOWER(title) LIKE '%{}%' THEN 1 ELSE 0 END", w))
-                        .collect::<Vec<_>>()
-                        .join(" + ")
-                )))
-                .desc(),
-            )
-            .then_order_by(updated_at.desc());
-
-        // Execute the query
-        let results = query.limit(10).load::<Article>(conn)?;
+        let results = search
+            .order(rank().desc())
+            .then_order_by(updated_at.desc())
+            .limit(limits.keyword_results)
+            .load::<(Article, f64)>(conn)?;
         info!("Keyword search found {} results", results.len());
         Ok(results)
     }
 }
 
-fn process_results(
-    results: Vec<(Article, f64, bool)>,
-) -> Result<Vec<(Article, f64)>, Box<dyn std::error::Error + Send + Sync>> {
-    let mut article_similarities: HashMap<Uuid, (Article, f64)> = HashMap::new();
-    for (article, distance, is_title) in results {
+/// Sums the weighted similarities of each article's chunks, which must come
+/// nearest first, and keeps the first `passages` non-title chunks of each.
+fn rank_chunks(
+    results: Vec<(Article, f64, ArticleChunk)>,
+    limit: usize,
+    passages: usize,
+) -> Vec<(Article, f64, Vec<ChunkMatch>)> {
+    let mut article_similarities: HashMap<Uuid, (Article, f64, Vec<ChunkMatch>)> = HashMap::new();
+    for (article, distance, chunk) in results {
         let similarity = 1.0 - distance;
-        let weight = if is_title { 2.0 } else { 1.0 };
+        let weight = if chunk.is_title { 2.0 } else { 1.0 };
         let entry = article_similarities
             .entry(article.id)
-            .or_insert_with(|| (article, 0.0));
+            .or_insert_with(|| (article, 0.0, Vec::new()));
         entry.1 += similarity * weight;
+        if !chunk.is_title && entry.2.len() < passages {
+            entry.2.push(ChunkMatch {
+                chunk_id: chunk.id,
+                content: chunk.content,
+                heading_path: chunk.heading_path,
+                start_offset: chunk.start_offset,
+                end_offset: chunk.end_offset,
+                similarity,
+            });
+        }
     }
 
-    let mut sorted_articles: Vec<(Article, f64)> = article_similarities.into_values().collect();
+    let mut sorted_articles: Vec<_> = article_similarities.into_values().collect();
     sorted_articles.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-    sorted_articles.truncate(5);
+    sorted_articles.truncate(limit);
 
     info!("Found {} relevant articles", sorted_articles.len());
-    Ok(sorted_articles)
+    sorted_articles
 }
 
 fn combine_and_deduplicate_results(
     results: Vec<Vec<(Article, Option<f64>)>>,
+    limit: usize,
 ) -> Result<Vec<(Article, f64)>, Box<dyn std::error::Error + Send + Sync>> {
     let mut combined_results: Vec<(Article, f64)> = results
         .into_iter()
@@ -177,14 +284,14 @@ fn combine_and_deduplicate_results(
     // Sort by distance (lower is better)
     combined_results.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
 
-    // Take the top 5 unique results
+    // Take the top unique results
     let mut unique_results = Vec::new();
     let mut seen_ids = std::collections::HashSet::new();
 
     for (article, distance) in combined_results {
         if seen_ids.insert(article.id) {
-            unique_results.push((article, distance));
-            if unique_results.len() == 5 {
+            unique_results.push((article, 1.0 - distance));
+            if unique_results.len() == limit {
                 break;
             }
         }
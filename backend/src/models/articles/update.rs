@@ -1,7 +1,10 @@
+use chrono::Utc;
 use diesel::prelude::*;
 use diesel::ExpressionMethods;
+use pgvector::Vector;
 
 use super::Article;
+use crate::models::{ArticleRemoval, RemovalReason};
 use crate::schema::articles;
 use crate::services::data_processor::ProcessResult;
 
@@ -21,6 +24,28 @@ impl Article {
                 articles::columns::bullet_points_embedding
                     .eq(process_result.bullet_points_embedding),
                 articles::columns::keywords_embedding.eq(process_result.keywords_embedding),
+                articles::columns::metadata_embedding_model.eq(process_result.embedding_model),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Copies the cheap, synced columns of `fetched` onto the stored row
+    /// without touching content, chunks or `version`, for articles whose
+    /// timestamp moved but whose content did not.
+    pub fn update_sync_metadata(
+        &self,
+        conn: &mut PgConnection,
+        fetched: &Article,
+    ) -> Result<(), diesel::result::Error> {
+        diesel::update(articles::table.find(self.id))
+            .set((
+                articles::collection_id.eq(fetched.collection_id),
+                articles::helpscout_collection_id.eq(&fetched.helpscout_collection_id),
+                articles::slug.eq(&fetched.slug),
+                articles::last_edited_by.eq(&fetched.last_edited_by),
+                articles::updated_at.eq(fetched.updated_at),
             ))
             .execute(conn)?;
 
@@ -40,4 +65,53 @@ impl Article {
 
         Ok(())
     }
+
+    /// Replaces the keywords, clearing them when `keywords` is empty. The
+    /// keywords embedding is dropped either way so it gets regenerated.
+    pub fn update_keywords(
+        &self,
+        conn: &mut PgConnection,
+        keywords: Vec<String>,
+    ) -> Result<(), diesel::result::Error> {
+        let keywords =
+            (!keywords.is_empty()).then(|| keywords.into_iter().map(Some).collect::<Vec<_>>());
+        diesel::update(articles::table.find(self.id))
+            .set((
+                articles::keywords.eq(keywords),
+                articles::keywords_embedding.eq(None::<Vector>),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Soft deletes the article: marks it archived, removes its chunks,
+    /// chunk embeddings and metadata vectors so it no longer shows up in
+    /// search or chat, and records the removal for review.
+    pub fn archive(
+        &self,
+        conn: &mut PgConnection,
+        reason: RemovalReason,
+    ) -> Result<ArticleRemoval, diesel::result::Error> {
+        conn.transaction(|conn| {
+            diesel::update(articles::table.find(self.id))
+                .set((
+                    articles::archived_at.eq(Some(Utc::now())),
+                    articles::paragraph_description_embedding.eq(None::<Vector>),
+                    articles::bullet_points_embedding.eq(None::<Vector>),
+                    articles::keywords_embedding.eq(None::<Vector>),
+                ))
+                .execute(conn)?;
+
+            self.delete_chunks(conn)?;
+
+            ArticleRemoval::new(
+                self.id,
+                self.helpscout_article_id.clone(),
+                self.title.clone(),
+                reason,
+            )
+            .store(conn)
+        })
+    }
 }
@@ -4,6 +4,8 @@ use pgvector::{Vector, VectorExpressionMethods};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::config::SearchConfig;
+use crate::models::embedding::{has_dims, vector_with_dims};
 use crate::schema::collections;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, Insertable, Identifiable)]
@@ -23,6 +25,9 @@ pub struct Collection {
     pub paragraph_description_embedding: Option<Vector>,
     pub bullet_points_embedding: Option<Vector>,
     pub keywords_embedding: Option<Vector>,
+    /// Model the metadata vectors were generated with, see `Embedder::model`.
+    #[serde(default)]
+    pub metadata_embedding_model: Option<String>,
 }
 
 impl Collection {
@@ -47,6 +52,7 @@ impl Collection {
             paragraph_description_embedding: None,
             bullet_points_embedding: None,
             keywords_embedding: None,
+            metadata_embedding_model: None,
         }
     }
 
@@ -61,42 +67,91 @@ impl Collection {
         Ok(())
     }
 
+    pub fn find_by_helpscout_id(
+        conn: &mut PgConnection,
+        helpscout_id: &str,
+    ) -> Result<Option<Collection>, diesel::result::Error> {
+        collections::table
+            .filter(collections::helpscout_collection_id.eq(helpscout_id))
+            .first(conn)
+            .optional()
+    }
+
+    /// Inserts the collection, or updates the existing row with the same
+    /// `helpscout_collection_id`. Returns the stored row, whose `id` may differ
+    /// from `self.id` when the collection was already synced.
+    pub fn upsert(&self, conn: &mut PgConnection) -> Result<Collection, diesel::result::Error> {
+        match Collection::find_by_helpscout_id(conn, &self.helpscout_collection_id)? {
+            Some(existing) => diesel::update(collections::table.find(existing.id))
+                .set((
+                    collections::name.eq(&self.name),
+                    collections::description.eq(&self.description),
+                    collections::slug.eq(&self.slug),
+                    collections::updated_at.eq(Utc::now()),
+                ))
+                .get_result(conn),
+            None => diesel::insert_into(collections::table)
+                .values(self)
+                .get_result(conn),
+        }
+    }
+
     pub fn find_relevant_collections(
         query_embedding: &Vector,
+        model: &str,
         conn: &mut PgConnection,
+        limits: &SearchConfig,
     ) -> Result<Vec<(Collection, f64)>, diesel::result::Error> {
         let collection_table = collections::table;
+        let dimensions = query_embedding.as_slice().len();
 
         let paragraph_description_embedding: Vec<(Collection, Option<f64>)> = collection_table
             .select((
                 collections::all_columns,
-                collections::paragraph_description_embedding
+                vector_with_dims("collections.paragraph_description_embedding", dimensions)
                     .cosine_distance(query_embedding)
                     .nullable(),
             ))
-            .filter(collections::paragraph_description_embedding.is_not_null())
-            .order(collections::paragraph_description_embedding.cosine_distance(query_embedding))
-            .limit(3)
+            .filter(collections::metadata_embedding_model.eq(model))
+            .filter(has_dims(
+                "collections.paragraph_description_embedding",
+                dimensions,
+            ))
+            .order(
+                vector_with_dims("collections.paragraph_description_embedding", dimensions)
+                    .cosine_distance(query_embedding),
+            )
+            .limit(limits.collection_matches)
             .load::<(Collection, Option<f64>)>(conn)?;
 
         let bullet_points_embedding: Vec<(Collection, Option<f64>)> = collection_table
             .select((
                 collections::all_columns,
-                collections::bullet_points_embedding.cosine_distance(query_embedding),
+                vector_with_dims("collections.bullet_points_embedding", dimensions)
+                    .cosine_distance(query_embedding),
             ))
-            .filter(collections::bullet_points_embedding.is_not_null())
-            .order(collections::bullet_points_embedding.cosine_distance(query_embedding))
-            .limit(3)
+            .filter(collections::metadata_embedding_model.eq(model))
+            .filter(has_dims("collections.bullet_points_embedding", dimensions))
+            .order(
+                vector_with_dims("collections.bullet_points_embedding", dimensions)
+                    .cosine_distance(query_embedding),
+            )
+            .limit(limits.collection_matches)
             .load::<(Collection, Option<f64>)>(conn)?;
 
         let keywords_embedding: Vec<(Collection, Option<f64>)> = collection_table
             .select((
                 collections::all_columns,
-                collections::keywords_embedding.cosine_distance(query_embedding),
+                vector_with_dims("collections.keywords_embedding", dimensions)
+                    .cosine_distance(query_embedding),
             ))
-            .filter(collections::keywords_embedding.is_not_null())
-            .order(collections::keywords_embedding.cosine_distance(query_embedding))
-            .limit(3)
+            .filter(collections::metadata_embedding_model.eq(model))
+            .filter(has_dims("collections.keywords_embedding", dimensions))
+            .order(
+                vector_with_dims("collections.keywords_embedding", dimensions)
+                    .cosine_distance(query_embedding),
+            )
+            .limit(limits.collection_matches)
             .load::<(Collection, Option<f64>)>(conn)?;
 
         // Combine all results
@@ -110,14 +165,14 @@ impl Collection {
         // Sort by distance (lower is better)
         combined_results.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
 
-        // Take the top 3 unique results
+        // Take the top unique results
         let mut unique_results = Vec::new();
         let mut seen_ids = std::collections::HashSet::new();
 
         for (collection, distance) in combined_results {
             if seen_ids.insert(collection.id) {
                 unique_results.push((collection, distance));
-                if unique_results.len() == 3 {
+                if unique_results.len() as i64 == limits.collection_matches {
                     break;
                 }
             }
@@ -128,40 +183,60 @@ impl Collection {
 
     pub fn find_relevant_collection_ids(
         query_embedding: &Vector,
+        model: &str,
         conn: &mut PgConnection,
+        limits: &SearchConfig,
     ) -> Result<Vec<(Uuid, f64)>, diesel::result::Error> {
         let collection_table = collections::table;
+        let dimensions = query_embedding.as_slice().len();
 
         let paragraph_description_embedding: Vec<(Collection, Option<f64>)> = collection_table
             .select((
                 collections::all_columns,
-                collections::paragraph_description_embedding
+                vector_with_dims("collections.paragraph_description_embedding", dimensions)
                     .cosine_distance(query_embedding)
                     .nullable(),
             ))
-            .filter(collections::paragraph_description_embedding.is_not_null())
-            .order(collections::paragraph_description_embedding.cosine_distance(query_embedding))
-            .limit(3)
+            .filter(collections::metadata_embedding_model.eq(model))
+            .filter(has_dims(
+                "collections.paragraph_description_embedding",
+                dimensions,
+            ))
+            .order(
+                vector_with_dims("collections.paragraph_description_embedding", dimensions)
+                    .cosine_distance(query_embedding),
+            )
+            .limit(limits.collection_matches)
             .load::<(Collection, Option<f64>)>(conn)?;
 
         let bullet_points_embedding: Vec<(Collection, Option<f64>)> = collection_table
             .select((
                 collections::all_columns,
-                collections::bullet_points_embedding.cosine_distance(query_embedding),
+                vector_with_dims("collections.bullet_points_embedding", dimensions)
+                    .cosine_distance(query_embedding),
             ))
-            .filter(collections::bullet_points_embedding.is_not_null())
-            .order(collections::bullet_points_embedding.cosine_distance(query_embedding))
-            .limit(3)
+            .filter(collections::metadata_embedding_model.eq(model))
+            .filter(has_dims("collections.bullet_points_embedding", dimensions))
+            .order(
+                vector_with_dims("collections.bullet_points_embedding", dimensions)
+                    .cosine_distance(query_embedding),
+            )
+            .limit(limits.collection_matches)
             .load::<(Collection, Option<f64>)>(conn)?;
 
         let keywords_embedding: Vec<(Collection, Option<f64>)> = collection_table
             .select((
                 collections::all_columns,
-                collections::keywords_embedding.cosine_distance(query_embedding),
+                vector_with_dims("collections.keywords_embedding", dimensions)
+                    .cosine_distance(query_embedding),
             ))
-            .filter(collections::keywords_embedding.is_not_null())
-            .order(collections::keywords_embedding.cosine_distance(query_embedding))
-            .limit(3)
+            .filter(collections::metadata_embedding_model.eq(model))
+            .filter(has_dims("collections.keywords_embedding", dimensions))
+            .order(
+                vector_with_dims("collections.keywords_embedding", dimensions)
+                    .cosine_distance(query_embedding),
+            )
+            .limit(limits.collection_matches)
             .load::<(Collection, Option<f64>)>(conn)?;
 
         // Combine all results
@@ -175,14 +250,14 @@ impl Collection {
         // Sort by distance (lower is better)
         combined_results.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
 
-        // Take the top 3 unique results
+        // Take the top unique results
         let mut unique_results = Vec::new();
         let mut seen_ids = std::collections::HashSet::new();
 
         for (collection, distance) in combined_results {
             if seen_ids.insert(collection.id) {
                 unique_results.push((collection.id, distance));
-                if unique_results.len() == 3 {
+                if unique_results.len() as i64 == limits.collection_matches {
                     break;
                 }
             }
@@ -200,6 +275,7 @@ impl Collection {
         paragraph_description_embedding: Vector,
         bullet_points_embedding: Vector,
         keywords_embedding: Vector,
+        embedding_model: &str,
     ) -> Result<(), diesel::result::Error> {
         diesel::update(collections::table.find(self.id))
             .set((
@@ -210,6 +286,7 @@ impl Collection {
                     .eq(paragraph_description_embedding),
                 collections::columns::bullet_points_embedding.eq(bullet_points_embedding),
                 collections::columns::keywords_embedding.eq(keywords_embedding),
+                collections::columns::metadata_embedding_model.eq(embedding_model),
             ))
             .execute(conn)?;
 
@@ -2,6 +2,9 @@
 
 
 use diesel::prelude::*;
+use diesel::dsl::sql;
+use diesel::expression::SqlLiteral;
+use diesel::sql_types::{Bool, Nullable};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use pgvector::Vector as PgVector;
@@ -9,6 +12,69 @@ use diesel::pg::PgConnection;
 
 use crate::schema::embeddings;
 
+/// The model every embedding was generated with before embeddings recorded
+/// their model, i.e. all-MiniLM-L6-v2 behind the Python service.
+pub const LEGACY_EMBEDDING_MODEL: &str = "all-MiniLM-L6-v2";
+
+/// Every vector column, as `(table, column)`. Each has one partial index per
+/// dimension in use, see `ensure_vector_indexes`.
+pub const VECTOR_COLUMNS: &[(&str, &str)] = &[
+    ("embeddings", "embedding_vector"),
+    ("articles", "paragraph_description_embedding"),
+    ("articles", "bullet_points_embedding"),
+    ("articles", "keywords_embedding"),
+    ("collections", "paragraph_description_embedding"),
+    ("collections", "bullet_points_embedding"),
+    ("collections", "keywords_embedding"),
+];
+
+/// pgvector can't index vectors with more dimensions than this.
+const MAX_INDEXED_DIMENSIONS: usize = 2000;
+
+/// Creates the HNSW indexes over the vectors with `dimensions` dimensions
+/// that don't exist yet. Indexes need a fixed dimension, so each is built on
+/// the vectors cast to it and limited to those that have it; queries use them
+/// through `has_dims` and `vector_with_dims`.
+pub fn ensure_vector_indexes(
+    conn: &mut PgConnection,
+    dimensions: usize,
+) -> Result<(), diesel::result::Error> {
+    if dimensions > MAX_INDEXED_DIMENSIONS {
+        log::warn!(
+            "Can't index {}-dimensional vectors, searches over them scan every row",
+            dimensions
+        );
+        return Ok(());
+    }
+
+    for (table, column) in VECTOR_COLUMNS {
+        diesel::sql_query(format!(
+            "CREATE INDEX IF NOT EXISTS idx_{table}_{column}_{dimensions} ON {table} \
+             USING hnsw (({column}::vector({dimensions})) vector_cosine_ops) \
+             WHERE vector_dims({column}) = {dimensions}"
+        ))
+        .execute(conn)?;
+    }
+    Ok(())
+}
+
+/// Whether `column` (`table.column`) holds a vector with `dimensions`
+/// dimensions. This is the predicate of the column's index for `dimensions`,
+/// so it is inlined rather than bound for the planner to match it.
+pub fn has_dims(column: &str, dimensions: usize) -> SqlLiteral<Bool> {
+    sql(&format!("vector_dims({}) = {}", column, dimensions))
+}
+
+/// `column` (`table.column`) cast to `vector(dimensions)`, the expression its
+/// index for `dimensions` is built on. Order by distances to this, next to a
+/// `has_dims` filter, for searches to use the index.
+pub fn vector_with_dims(
+    column: &str,
+    dimensions: usize,
+) -> SqlLiteral<Nullable<pgvector::sql_types::Vector>> {
+    sql(&format!("{}::vector({})", column, dimensions))
+}
+
 
 #[derive(Insertable, Queryable, Serialize, Deserialize)]
 #[diesel(table_name = crate::schema::embeddings)]
@@ -16,16 +82,21 @@ pub struct Embedding {
     pub id: Uuid,
     pub article_id: Uuid,
     pub embedding_vector: PgVector,
+    /// Identifier of the model that produced the vector, see `Embedder::model`.
+    pub model: String,
+    pub dimensions: i32,
 }
 
 impl Embedding {
-    pub fn new(article_id: Uuid, embedding_vector: Vec<f32>) -> Self {
-        assert_eq!(embedding_vector.len(), 384, "Embedding vector must be 384 dimensions");
+    pub fn new(article_id: Uuid, model: &str, embedding_vector: Vec<f32>) -> Self {
+        let dimensions = embedding_vector.len() as i32;
         let embedding = PgVector::from(embedding_vector);
         Self {
             id: Uuid::new_v4(),
             article_id,
             embedding_vector: embedding,
+            model: model.to_string(),
+            dimensions,
         }
     }
 
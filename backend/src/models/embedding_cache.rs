@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use pgvector::Vector;
+use std::collections::HashMap;
+
+use crate::schema::embedding_cache;
+
+/// A vector remembered by the hash of the text it was generated from, see
+/// `EmbeddingService::embed_chunks`.
+#[derive(Debug, Clone, Queryable, Insertable)]
+#[diesel(table_name = embedding_cache)]
+pub struct CachedEmbedding {
+    /// SHA-256 of the embedded text, hex-encoded.
+    pub content_hash: String,
+    pub model: String,
+    pub embedding_vector: Vector,
+    pub created_at: DateTime<Utc>,
+    /// When `load` last returned the entry, for `evict`.
+    pub last_used_at: DateTime<Utc>,
+}
+
+impl CachedEmbedding {
+    pub fn new(content_hash: String, model: &str, embedding_vector: Vec<f32>) -> Self {
+        let now = Utc::now();
+        Self {
+            content_hash,
+            model: model.to_string(),
+            embedding_vector: Vector::from(embedding_vector),
+            created_at: now,
+            last_used_at: now,
+        }
+    }
+
+    /// The cached vectors of `model` for those of `content_hashes` it has,
+    /// by hash. The entries are marked as used.
+    pub fn load(
+        conn: &mut PgConnection,
+        model: &str,
+        content_hashes: &[String],
+    ) -> Result<HashMap<String, Vec<f32>>, diesel::result::Error> {
+        let rows: Vec<(String, Vector)> = diesel::update(
+            embedding_cache::table
+                .filter(embedding_cache::model.eq(model))
+                .filter(embedding_cache::content_hash.eq_any(content_hashes)),
+        )
+        .set(embedding_cache::last_used_at.eq(Utc::now()))
+        .returning((
+            embedding_cache::content_hash,
+            embedding_cache::embedding_vector,
+        ))
+        .get_results(conn)?;
+        Ok(rows
+            .into_iter()
+            .map(|(content_hash, vector)| (content_hash, vector.to_vec()))
+            .collect())
+    }
+
+    /// Stores the entries, keeping existing ones for the same text and model.
+    pub fn store_all(
+        conn: &mut PgConnection,
+        entries: &[CachedEmbedding],
+    ) -> Result<usize, diesel::result::Error> {
+        diesel::insert_into(embedding_cache::table)
+            .values(entries)
+            .on_conflict_do_nothing()
+            .execute(conn)
+    }
+
+    /// Deletes the least recently used entries, of any model, until at most
+    /// `keep` are left. Returns how many were deleted.
+    pub fn evict(conn: &mut PgConnection, keep: usize) -> Result<usize, diesel::result::Error> {
+        let cutoff: Option<DateTime<Utc>> = embedding_cache::table
+            .select(embedding_cache::last_used_at)
+            .order(embedding_cache::last_used_at.desc())
+            .offset(keep as i64)
+            .first(conn)
+            .optional()?;
+        match cutoff {
+            Some(cutoff) => diesel::delete(
+                embedding_cache::table.filter(embedding_cache::last_used_at.le(cutoff)),
+            )
+            .execute(conn),
+            None => Ok(0),
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use chrono::{DateTime, Utc};
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::sql_types::Bool;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::{article_chunks, embedding_generations, embeddings};
+
+/// Where a generation of the search index is in its life cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GenerationStatus {
+    /// Being filled by a re-embedding run; not visible to search.
+    Building,
+    /// The generation search reads. There is at most one.
+    Active,
+    /// The previously active generation, kept for `rollback`.
+    Retired,
+    /// The build did not complete; its chunks were deleted.
+    Failed,
+    /// Superseded by newer generations; its chunks were deleted.
+    Discarded,
+}
+
+impl GenerationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GenerationStatus::Building => "building",
+            GenerationStatus::Active => "active",
+            GenerationStatus::Retired => "retired",
+            GenerationStatus::Failed => "failed",
+            GenerationStatus::Discarded => "discarded",
+        }
+    }
+}
+
+/// A complete set of article chunks and their embeddings. Re-embedding fills
+/// a new generation while search keeps reading the active one, then switches
+/// over in one transaction.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+#[diesel(table_name = embedding_generations)]
+pub struct EmbeddingGeneration {
+    pub id: i32,
+    /// Model the generation was built with; incremental embedding may add
+    /// chunks of the configured model to the active generation.
+    pub model: String,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub activated_at: Option<DateTime<Utc>>,
+}
+
+impl EmbeddingGeneration {
+    /// The generation search reads and incremental embedding writes to.
+    pub fn active(conn: &mut PgConnection) -> Result<Self, diesel::result::Error> {
+        embedding_generations::table
+            .filter(embedding_generations::status.eq(GenerationStatus::Active.as_str()))
+            .first(conn)
+    }
+
+    /// All generations, newest first.
+    pub fn load_all(conn: &mut PgConnection) -> Result<Vec<Self>, diesel::result::Error> {
+        embedding_generations::table
+            .order(embedding_generations::id.desc())
+            .load(conn)
+    }
+
+    /// Starts building a new generation for `model`, or returns `None` when
+    /// another build is running. The session-level advisory lock taken here
+    /// keeps builds one at a time until `activate` or `fail` releases it, or
+    /// the connection closes, so builds still marked as building while it is
+    /// held were interrupted and are failed.
+    pub fn start(
+        conn: &mut PgConnection,
+        model: &str,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        let locked = diesel::select(sql::<Bool>(
+            "pg_try_advisory_lock(hashtext('embedding_generations'))",
+        ))
+        .get_result::<bool>(conn)?;
+        if !locked {
+            return Ok(None);
+        }
+
+        let started = conn.transaction(|conn| {
+            let stale: Vec<Self> = embedding_generations::table
+                .filter(embedding_generations::status.eq(GenerationStatus::Building.as_str()))
+                .load(conn)?;
+            for generation in stale {
+                warn!(
+                    "Failing embedding generation {} left over from an earlier run",
+                    generation.id
+                );
+                generation.close(conn, GenerationStatus::Failed)?;
+            }
+
+            let generation: Self = diesel::insert_into(embedding_generations::table)
+                .values((
+                    embedding_generations::model.eq(model),
+                    embedding_generations::status.eq(GenerationStatus::Building.as_str()),
+                ))
+                .get_result(conn)?;
+            info!(
+                "Started embedding generation {} for {}",
+                generation.id, model
+            );
+            Ok(generation)
+        });
+        if started.is_err() {
+            Self::release_build_lock(conn)?;
+        }
+        started.map(Some)
+    }
+
+    /// Makes this generation, which must still be building, the one search
+    /// reads, and ends the build started by `start`. The previously active
+    /// one is retired and kept for `rollback`; older retired ones are
+    /// discarded. Nothing changes when the build was failed meanwhile.
+    pub fn activate(&self, conn: &mut PgConnection) -> Result<(), diesel::result::Error> {
+        let activated = conn.transaction(|conn| {
+            let superseded: Vec<Self> = embedding_generations::table
+                .filter(embedding_generations::status.eq(GenerationStatus::Retired.as_str()))
+                .load(conn)?;
+            for generation in superseded {
+                generation.close(conn, GenerationStatus::Discarded)?;
+            }
+            Self::swap_active(conn, self.id, GenerationStatus::Building)
+        });
+        Self::release_build_lock(conn)?;
+        activated?;
+        info!("Activated embedding generation {}", self.id);
+        Ok(())
+    }
+
+    /// Gives up on a build, deletes what it stored and ends the build
+    /// started by `start`.
+    pub fn fail(&self, conn: &mut PgConnection) -> Result<(), diesel::result::Error> {
+        let failed = conn.transaction(|conn| self.close(conn, GenerationStatus::Failed));
+        Self::release_build_lock(conn)?;
+        failed
+    }
+
+    fn release_build_lock(conn: &mut PgConnection) -> Result<(), diesel::result::Error> {
+        diesel::select(sql::<Bool>(
+            "pg_advisory_unlock(hashtext('embedding_generations'))",
+        ))
+        .execute(conn)?;
+        Ok(())
+    }
+
+    /// Switches search back to the retired generation, retiring the active
+    /// one in its place, so a second rollback undoes the first. Returns the
+    /// generation now active, or `None` when there is nothing to go back to.
+    ///
+    /// Articles changed since the retired generation was active have no chunks
+    /// in it; the next embedding backfill adds them.
+    pub fn rollback(conn: &mut PgConnection) -> Result<Option<Self>, diesel::result::Error> {
+        conn.transaction(|conn| {
+            let previous: Option<Self> = embedding_generations::table
+                .filter(embedding_generations::status.eq(GenerationStatus::Retired.as_str()))
+                .order(embedding_generations::activated_at.desc())
+                .first(conn)
+                .optional()?;
+            let Some(previous) = previous else {
+                return Ok(None);
+            };
+            Self::swap_active(conn, previous.id, GenerationStatus::Retired)?;
+            info!("Rolled back to embedding generation {}", previous.id);
+            Ok(Some(Self::active(conn)?))
+        })
+    }
+
+    /// Retires the active generation and activates `id`, which must have
+    /// status `from`; fails with `NotFound` otherwise. Must run in a
+    /// transaction so search never sees zero or two active generations.
+    fn swap_active(
+        conn: &mut PgConnection,
+        id: i32,
+        from: GenerationStatus,
+    ) -> Result<(), diesel::result::Error> {
+        diesel::update(
+            embedding_generations::table
+                .filter(embedding_generations::status.eq(GenerationStatus::Active.as_str())),
+        )
+        .set(embedding_generations::status.eq(GenerationStatus::Retired.as_str()))
+        .execute(conn)?;
+        let activated = diesel::update(
+            embedding_generations::table
+                .find(id)
+                .filter(embedding_generations::status.eq(from.as_str())),
+        )
+        .set((
+            embedding_generations::status.eq(GenerationStatus::Active.as_str()),
+            embedding_generations::activated_at.eq(Utc::now()),
+        ))
+        .execute(conn)?;
+        if activated == 0 {
+            warn!(
+                "Embedding generation {} is no longer {}, not activating it",
+                id,
+                from.as_str()
+            );
+            return Err(diesel::result::Error::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Deletes the generation's chunks and their embeddings and records
+    /// `status`.
+    fn close(
+        &self,
+        conn: &mut PgConnection,
+        status: GenerationStatus,
+    ) -> Result<(), diesel::result::Error> {
+        let chunks = article_chunks::table.filter(article_chunks::generation_id.eq(self.id));
+        diesel::delete(
+            embeddings::table.filter(
+                embeddings::id
+                    .nullable()
+                    .eq_any(chunks.select(article_chunks::embedding_id)),
+            ),
+        )
+        .execute(conn)?;
+        diesel::delete(chunks).execute(conn)?;
+        diesel::update(embedding_generations::table.find(self.id))
+            .set(embedding_generations::status.eq(status.as_str()))
+            .execute(conn)?;
+        Ok(())
+    }
+}
@@ -1,19 +1,613 @@
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::schema::{job_status_history, jobs};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum JobStatus {
     Queued,
     Running,
     Completed,
-    Failed(String),
+    /// Failed at least once and waiting for its next attempt.
+    Retrying,
+    /// Failed `max_attempts` times and will not be retried.
+    DeadLetter,
+    /// Held by an operator; queued jobs are not claimed until resumed.
+    Paused,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Retrying => "retrying",
+            JobStatus::DeadLetter => "dead_letter",
+            JobStatus::Paused => "paused",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Completed | JobStatus::DeadLetter | JobStatus::Cancelled
+        )
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for JobStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "queued" => Ok(JobStatus::Queued),
+            "running" => Ok(JobStatus::Running),
+            "completed" => Ok(JobStatus::Completed),
+            "retrying" => Ok(JobStatus::Retrying),
+            "dead_letter" => Ok(JobStatus::DeadLetter),
+            "paused" => Ok(JobStatus::Paused),
+            "cancelled" => Ok(JobStatus::Cancelled),
+            other => Err(anyhow::anyhow!("Unknown job status: {}", other)),
+        }
+    }
+}
+
+/// Statuses a job never leaves.
+const FINISHED_STATUSES: [&str; 3] = ["completed", "dead_letter", "cancelled"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+#[diesel(table_name = jobs)]
 pub struct JobInfo {
     pub id: Uuid,
-    pub status: JobStatus,
-    pub created_at: chrono::DateTime<Utc>,
-    pub updated_at: chrono::DateTime<Utc>,
+    pub job_type: String,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub last_error: Option<String>,
+    pub run_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub parent_id: Option<Uuid>,
+    /// Last sign of life from the worker running the job.
+    pub heartbeat_at: Option<DateTime<Utc>>,
+    /// What the job produced on completion, e.g. a `SyncArticle` outcome.
+    pub result: Option<serde_json::Value>,
+    /// Set when an operator cancels the job while a worker, possibly in
+    /// another process, is running it.
+    pub cancel_requested: bool,
+}
+
+/// Aggregate state of the jobs fanned out by a parent job.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChildJobCounts {
+    pub total: i64,
+    pub done: i64,
+    pub failed: i64,
+    pub pending: i64,
+    /// Completed children by their result, e.g. `{"created": 3, "unchanged": 40}`
+    /// for the `SyncArticle` jobs of a sync.
+    pub outcomes: BTreeMap<String, i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+#[diesel(table_name = job_status_history)]
+pub struct JobStatusChange {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub status: String,
+    pub message: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl JobStatusChange {
+    pub fn record(
+        conn: &mut PgConnection,
+        job_id: Uuid,
+        status: JobStatus,
+        message: Option<String>,
+    ) -> Result<(), diesel::result::Error> {
+        diesel::insert_into(job_status_history::table)
+            .values(JobStatusChange {
+                id: Uuid::new_v4(),
+                job_id,
+                status: status.as_str().to_string(),
+                message,
+                created_at: Utc::now(),
+            })
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn for_job(
+        conn: &mut PgConnection,
+        job_id: Uuid,
+    ) -> Result<Vec<JobStatusChange>, diesel::result::Error> {
+        job_status_history::table
+            .filter(job_status_history::job_id.eq(job_id))
+            .order(job_status_history::created_at.asc())
+            .load::<JobStatusChange>(conn)
+    }
+}
+
+impl JobInfo {
+    pub fn new(job_type: &str, payload: serde_json::Value, max_attempts: i32) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            job_type: job_type.to_string(),
+            payload,
+            status: JobStatus::Queued.as_str().to_string(),
+            attempts: 0,
+            max_attempts,
+            last_error: None,
+            run_at: now,
+            started_at: None,
+            finished_at: None,
+            created_at: now,
+            updated_at: now,
+            parent_id: None,
+            heartbeat_at: None,
+            result: None,
+            cancel_requested: false,
+        }
+    }
+
+    pub fn with_parent(mut self, parent_id: Uuid) -> Self {
+        self.parent_id = Some(parent_id);
+        self
+    }
+
+    pub fn status(&self) -> Result<JobStatus, anyhow::Error> {
+        self.status.parse()
+    }
+
+    pub fn store(&self, conn: &mut PgConnection) -> Result<Self, diesel::result::Error> {
+        conn.transaction(|conn| {
+            let job: Self = diesel::insert_into(jobs::table)
+                .values(self)
+                .get_result(conn)?;
+            JobStatusChange::record(conn, job.id, JobStatus::Queued, None)?;
+            Ok(job)
+        })
+    }
+
+    /// Stores several jobs in one transaction, so a fan-out is either fully
+    /// enqueued or not at all.
+    pub fn store_all(
+        conn: &mut PgConnection,
+        jobs: &[JobInfo],
+    ) -> Result<(), diesel::result::Error> {
+        conn.transaction(|conn| {
+            for job in jobs {
+                job.store(conn)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Stores the job unless a top-level job of the same type, or one of its
+    /// children, is unfinished, in which case that job's ID is returned. A
+    /// transaction-level advisory lock on the job type serializes concurrent
+    /// callers, so two of them cannot both see no active job.
+    pub fn store_unless_active(
+        &self,
+        conn: &mut PgConnection,
+    ) -> Result<Result<Self, Uuid>, diesel::result::Error> {
+        use diesel::dsl::{exists, sql};
+        use diesel::sql_types::{Bool, Text};
+
+        conn.transaction(|conn| {
+            diesel::select(
+                sql::<Bool>("pg_advisory_xact_lock(hashtext(")
+                    .bind::<Text, _>(&self.job_type)
+                    .sql(")) IS NOT NULL"),
+            )
+            .execute(conn)?;
+
+            let children = diesel::alias!(jobs as children);
+            let active: Option<Uuid> = jobs::table
+                .filter(jobs::job_type.eq(&self.job_type))
+                .filter(jobs::parent_id.is_null())
+                .filter(
+                    jobs::status.ne_all(FINISHED_STATUSES).or(exists(
+                        children
+                            .filter(children.field(jobs::parent_id).eq(jobs::id.nullable()))
+                            .filter(children.field(jobs::status).ne_all(FINISHED_STATUSES)),
+                    )),
+                )
+                .select(jobs::id)
+                .first(conn)
+                .optional()?;
+
+            match active {
+                Some(job_id) => Ok(Err(job_id)),
+                None => self.store(conn).map(Ok),
+            }
+        })
+    }
+
+    pub fn find(
+        conn: &mut PgConnection,
+        job_id: Uuid,
+    ) -> Result<Option<JobInfo>, diesel::result::Error> {
+        jobs::table.find(job_id).first(conn).optional()
+    }
+
+    /// Claims the next runnable job and marks it running. Rows locked by other
+    /// workers are skipped, so concurrent workers never claim the same job.
+    ///
+    /// Jobs whose worker stopped sending heartbeats for `lease` are requeued
+    /// first, and runnable jobs without attempts left are dead-lettered. With
+    /// a `tree`, only that job and its children are claimed.
+    pub fn claim_next(
+        conn: &mut PgConnection,
+        lease: Duration,
+        tree: Option<Uuid>,
+    ) -> Result<Option<JobInfo>, diesel::result::Error> {
+        use diesel::sql_types::Bool;
+
+        conn.transaction(|conn| {
+            Self::requeue_expired(conn, lease)?;
+            Self::dead_letter_exhausted(conn)?;
+
+            // Without a tree the first operand is true and the others are NULL.
+            let in_tree = tree
+                .is_none()
+                .into_sql::<Bool>()
+                .or(jobs::id.nullable().eq(tree))
+                .or(jobs::parent_id.eq(tree));
+            let next = jobs::table
+                .filter(in_tree)
+                .filter(
+                    jobs::status.eq_any([JobStatus::Queued.as_str(), JobStatus::Retrying.as_str()]),
+                )
+                .filter(jobs::attempts.lt(jobs::max_attempts))
+                .filter(jobs::run_at.le(Utc::now()))
+                .order(jobs::run_at.asc())
+                .for_update()
+                .skip_locked()
+                .first::<JobInfo>(conn)
+                .optional()?;
+
+            let Some(next) = next else {
+                return Ok(None);
+            };
+
+            let now = Utc::now();
+            let claimed: JobInfo = diesel::update(jobs::table.find(next.id))
+                .set((
+                    jobs::status.eq(JobStatus::Running.as_str()),
+                    jobs::attempts.eq(jobs::attempts + 1),
+                    jobs::started_at.eq(Some(now)),
+                    jobs::heartbeat_at.eq(Some(now)),
+                    jobs::updated_at.eq(now),
+                ))
+                .get_result(conn)?;
+            JobStatusChange::record(
+                conn,
+                claimed.id,
+                JobStatus::Running,
+                Some(format!(
+                    "Attempt {}/{}",
+                    claimed.attempts, claimed.max_attempts
+                )),
+            )?;
+
+            Ok(Some(claimed))
+        })
+    }
+
+    pub fn mark_completed(
+        &self,
+        conn: &mut PgConnection,
+        result: Option<serde_json::Value>,
+    ) -> Result<(), diesel::result::Error> {
+        conn.transaction(|conn| {
+            let now = Utc::now();
+            diesel::update(jobs::table.find(self.id))
+                .set((
+                    jobs::status.eq(JobStatus::Completed.as_str()),
+                    jobs::result.eq(result),
+                    jobs::heartbeat_at.eq(None::<DateTime<Utc>>),
+                    jobs::finished_at.eq(Some(now)),
+                    jobs::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+            JobStatusChange::record(conn, self.id, JobStatus::Completed, None)
+        })
+    }
+
+    /// Records a failed attempt. The job is retried at `retry_at`, or moved to
+    /// the dead-letter state when `retry_at` is `None`.
+    pub fn mark_failed(
+        &self,
+        conn: &mut PgConnection,
+        error: &str,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<JobStatus, diesel::result::Error> {
+        conn.transaction(|conn| {
+            let now = Utc::now();
+            let status = match retry_at {
+                Some(retry_at) => {
+                    diesel::update(jobs::table.find(self.id))
+                        .set((
+                            jobs::status.eq(JobStatus::Retrying.as_str()),
+                            jobs::last_error.eq(Some(error)),
+                            jobs::heartbeat_at.eq(None::<DateTime<Utc>>),
+                            jobs::run_at.eq(retry_at),
+                            jobs::updated_at.eq(now),
+                        ))
+                        .execute(conn)?;
+                    JobStatus::Retrying
+                }
+                None => {
+                    diesel::update(jobs::table.find(self.id))
+                        .set((
+                            jobs::status.eq(JobStatus::DeadLetter.as_str()),
+                            jobs::last_error.eq(Some(error)),
+                            jobs::heartbeat_at.eq(None::<DateTime<Utc>>),
+                            jobs::finished_at.eq(Some(now)),
+                            jobs::updated_at.eq(now),
+                        ))
+                        .execute(conn)?;
+                    JobStatus::DeadLetter
+                }
+            };
+            JobStatusChange::record(conn, self.id, status, Some(error.to_string()))?;
+            Ok(status)
+        })
+    }
+
+    /// Records that the worker running the job is still alive and returns the
+    /// job, so the worker can follow operator requests made in other
+    /// processes.
+    pub fn heartbeat(
+        conn: &mut PgConnection,
+        job_id: Uuid,
+    ) -> Result<Option<JobInfo>, diesel::result::Error> {
+        diesel::update(jobs::table.find(job_id))
+            .set(jobs::heartbeat_at.eq(Some(Utc::now())))
+            .get_result(conn)
+            .optional()
+    }
+
+    /// Whether a worker, in this or another process, sent a heartbeat for the
+    /// job within `lease`.
+    pub fn has_live_worker(&self, lease: Duration) -> bool {
+        let cutoff = Utc::now() - chrono::Duration::milliseconds(lease.as_millis() as i64);
+        self.heartbeat_at.is_some_and(|beat| beat >= cutoff)
+    }
+
+    /// Asks the worker running the job to stop. Returns whether the job was
+    /// running.
+    pub fn request_cancel(
+        conn: &mut PgConnection,
+        job_id: Uuid,
+    ) -> Result<bool, diesel::result::Error> {
+        let updated = diesel::update(
+            jobs::table
+                .filter(jobs::id.eq(job_id))
+                .filter(jobs::status.eq(JobStatus::Running.as_str())),
+        )
+        .set((
+            jobs::cancel_requested.eq(true),
+            jobs::updated_at.eq(Utc::now()),
+        ))
+        .execute(conn)?;
+        Ok(updated > 0)
+    }
+
+    /// Puts running jobs whose worker sent no heartbeat for `lease` back in
+    /// the queue, e.g. after a crash or restart. Jobs of live workers, in this
+    /// or another process, keep running. The interrupted attempt counts
+    /// towards `max_attempts`, so a job that was on its last attempt, such as
+    /// one that crashes the process, is dead-lettered instead.
+    pub fn requeue_expired(
+        conn: &mut PgConnection,
+        lease: Duration,
+    ) -> Result<usize, diesel::result::Error> {
+        let cutoff = Utc::now() - chrono::Duration::milliseconds(lease.as_millis() as i64);
+        conn.transaction(|conn| {
+            let expired = jobs::table
+                .filter(jobs::status.eq(JobStatus::Running.as_str()))
+                .filter(
+                    jobs::heartbeat_at
+                        .is_null()
+                        .or(jobs::heartbeat_at.lt(cutoff)),
+                );
+
+            let now = Utc::now();
+            let message = "Interrupted on its last attempt";
+            let exhausted: Vec<Uuid> =
+                diesel::update(expired.filter(jobs::attempts.ge(jobs::max_attempts)))
+                    .set((
+                        jobs::status.eq(JobStatus::DeadLetter.as_str()),
+                        jobs::last_error.eq(Some(message)),
+                        jobs::finished_at.eq(Some(now)),
+                        jobs::updated_at.eq(now),
+                    ))
+                    .returning(jobs::id)
+                    .get_results(conn)?;
+            for job_id in &exhausted {
+                JobStatusChange::record(
+                    conn,
+                    *job_id,
+                    JobStatus::DeadLetter,
+                    Some(message.to_string()),
+                )?;
+            }
+
+            let requeued: Vec<Uuid> = diesel::update(expired)
+                .set((
+                    jobs::status.eq(JobStatus::Queued.as_str()),
+                    jobs::run_at.eq(now),
+                    jobs::updated_at.eq(now),
+                ))
+                .returning(jobs::id)
+                .get_results(conn)?;
+            for job_id in &requeued {
+                JobStatusChange::record(
+                    conn,
+                    *job_id,
+                    JobStatus::Queued,
+                    Some("Requeued after its worker stopped responding".to_string()),
+                )?;
+            }
+            Ok(exhausted.len() + requeued.len())
+        })
+    }
+
+    /// Dead-letters queued jobs that have no attempts left, e.g. ones resumed
+    /// after being interrupted on their last attempt.
+    fn dead_letter_exhausted(conn: &mut PgConnection) -> Result<usize, diesel::result::Error> {
+        let now = Utc::now();
+        let message = "No attempts left";
+        let exhausted: Vec<Uuid> = diesel::update(
+            jobs::table
+                .filter(
+                    jobs::status.eq_any([JobStatus::Queued.as_str(), JobStatus::Retrying.as_str()]),
+                )
+                .filter(jobs::attempts.ge(jobs::max_attempts)),
+        )
+        .set((
+            jobs::status.eq(JobStatus::DeadLetter.as_str()),
+            jobs::finished_at.eq(Some(now)),
+            jobs::updated_at.eq(now),
+        ))
+        .returning(jobs::id)
+        .get_results(conn)?;
+        for job_id in &exhausted {
+            JobStatusChange::record(
+                conn,
+                *job_id,
+                JobStatus::DeadLetter,
+                Some(message.to_string()),
+            )?;
+        }
+        Ok(exhausted.len())
+    }
+
+    pub fn child_counts(
+        conn: &mut PgConnection,
+        parent_id: Uuid,
+    ) -> Result<ChildJobCounts, diesel::result::Error> {
+        let by_status: Vec<(String, Option<serde_json::Value>, i64)> = jobs::table
+            .filter(jobs::parent_id.eq(parent_id))
+            .group_by((jobs::status, jobs::result))
+            .select((jobs::status, jobs::result, diesel::dsl::count_star()))
+            .load(conn)?;
+
+        let mut counts = ChildJobCounts::default();
+        for (status, result, count) in by_status {
+            counts.total += count;
+            match status.parse::<JobStatus>() {
+                Ok(JobStatus::Completed) => {
+                    counts.done += count;
+                    if let Some(serde_json::Value::String(outcome)) = result {
+                        *counts.outcomes.entry(outcome).or_default() += count;
+                    }
+                }
+                Ok(JobStatus::DeadLetter) => counts.failed += count,
+                _ => counts.pending += count,
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Children that failed at least their latest attempt, including ones
+    /// still waiting to be retried.
+    pub fn failed_children(
+        conn: &mut PgConnection,
+        parent_id: Uuid,
+    ) -> Result<Vec<JobInfo>, diesel::result::Error> {
+        jobs::table
+            .filter(jobs::parent_id.eq(parent_id))
+            .filter(
+                jobs::status.eq_any([JobStatus::Retrying.as_str(), JobStatus::DeadLetter.as_str()]),
+            )
+            .order(jobs::updated_at.desc())
+            .load(conn)
+    }
+
+    /// Moves the job to `to` if its status is one of `from`. Returns whether
+    /// the job changed, so callers can race workers without locking.
+    pub fn transition(
+        conn: &mut PgConnection,
+        job_id: Uuid,
+        from: &[JobStatus],
+        to: JobStatus,
+        message: &str,
+    ) -> Result<bool, diesel::result::Error> {
+        let from: Vec<&str> = from.iter().map(JobStatus::as_str).collect();
+        conn.transaction(|conn| {
+            let now = Utc::now();
+            let target = jobs::table
+                .filter(jobs::id.eq(job_id))
+                .filter(jobs::status.eq_any(from));
+            let updated = if to.is_terminal() {
+                diesel::update(target)
+                    .set((
+                        jobs::status.eq(to.as_str()),
+                        jobs::heartbeat_at.eq(None::<DateTime<Utc>>),
+                        jobs::finished_at.eq(Some(now)),
+                        jobs::updated_at.eq(now),
+                    ))
+                    .execute(conn)?
+            } else if to == JobStatus::Queued {
+                diesel::update(target)
+                    .set((
+                        jobs::status.eq(to.as_str()),
+                        jobs::heartbeat_at.eq(None::<DateTime<Utc>>),
+                        jobs::run_at.eq(now),
+                        jobs::updated_at.eq(now),
+                    ))
+                    .execute(conn)?
+            } else {
+                diesel::update(target)
+                    .set((jobs::status.eq(to.as_str()), jobs::updated_at.eq(now)))
+                    .execute(conn)?
+            };
+
+            if updated > 0 {
+                JobStatusChange::record(conn, job_id, to, Some(message.to_string()))?;
+            }
+            Ok(updated > 0)
+        })
+    }
+
+    /// Whether the job and all of its children reached a terminal status.
+    pub fn is_finished(&self, conn: &mut PgConnection) -> Result<bool, diesel::result::Error> {
+        let terminal = self.status().map(|s| s.is_terminal()).unwrap_or(false);
+        Ok(terminal && self.unfinished_children(conn)?.is_empty())
+    }
+
+    pub fn unfinished_children(
+        &self,
+        conn: &mut PgConnection,
+    ) -> Result<Vec<Uuid>, diesel::result::Error> {
+        jobs::table
+            .filter(jobs::parent_id.eq(self.id))
+            .filter(jobs::status.ne_all(FINISHED_STATUSES))
+            .select(jobs::id)
+            .load(conn)
+    }
 }
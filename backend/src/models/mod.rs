@@ -1,13 +1,22 @@
+pub mod article_removal;
 pub mod articles;
 pub mod collection;
 pub mod embedding;
+pub mod embedding_cache;
+pub mod embedding_generation;
 pub mod job_info;
 pub mod message;
+pub mod scheduled_run;
 
+pub use self::article_removal::{ArticleRemoval, RemovalReason};
 pub use self::articles::{
     Article, ArticleChunk, ArticleFull, ArticleFullResponse, ArticleRef, ArticleResponse,
+    MARKDOWN_IMPORT_PREFIX,
 };
 pub use self::collection::{Collection, CollectionItem, CollectionResponse};
 pub use self::embedding::Embedding;
-pub use self::job_info::{JobInfo, JobStatus};
+pub use self::embedding_cache::CachedEmbedding;
+pub use self::embedding_generation::{EmbeddingGeneration, GenerationStatus};
+pub use self::job_info::{ChildJobCounts, JobInfo, JobStatus, JobStatusChange};
 pub use self::message::Message;
+pub use self::scheduled_run::{RunOutcome, ScheduledRun};
@@ -0,0 +1,79 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::schema::{jobs, scheduled_runs};
+
+/// What the scheduler did when a schedule fired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunOutcome {
+    /// A job was enqueued; its result is tracked on the job itself.
+    Enqueued,
+    /// The job of the previous run was still active.
+    Skipped,
+    /// The job could not be enqueued.
+    Failed,
+}
+
+impl RunOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunOutcome::Enqueued => "enqueued",
+            RunOutcome::Skipped => "skipped",
+            RunOutcome::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+#[diesel(table_name = scheduled_runs)]
+pub struct ScheduledRun {
+    pub id: Uuid,
+    pub schedule_name: String,
+    pub job_id: Option<Uuid>,
+    pub status: String,
+    pub message: Option<String>,
+    pub scheduled_for: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ScheduledRun {
+    pub fn new(
+        schedule_name: &str,
+        scheduled_for: DateTime<Utc>,
+        outcome: RunOutcome,
+        job_id: Option<Uuid>,
+        message: Option<String>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            schedule_name: schedule_name.to_string(),
+            job_id,
+            status: outcome.as_str().to_string(),
+            message,
+            scheduled_for,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn store(&self, conn: &mut PgConnection) -> Result<Self, diesel::result::Error> {
+        diesel::insert_into(scheduled_runs::table)
+            .values(self)
+            .get_result(conn)
+    }
+
+    /// Recent runs, newest first, each with the current status of its job.
+    pub fn load_recent(
+        conn: &mut PgConnection,
+        limit: i64,
+    ) -> Result<Vec<(ScheduledRun, Option<String>)>, diesel::result::Error> {
+        scheduled_runs::table
+            .left_join(jobs::table)
+            .select((scheduled_runs::all_columns, jobs::status.nullable()))
+            .order(scheduled_runs::created_at.desc())
+            .limit(limit)
+            .load(conn)
+    }
+}
@@ -0,0 +1,34 @@
+use actix_web::{http::header::AUTHORIZATION, HttpRequest, HttpResponse};
+use log::{error, warn};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+
+/// Guards routes that dump, replace or drop data: the request must carry
+/// `Authorization: Bearer <server.admin_token>`. Returns the response to send
+/// when it doesn't.
+pub fn reject_unless_admin(req: &HttpRequest, config: &Config) -> Option<HttpResponse> {
+    let admin_token = match config.server.admin_token.as_deref() {
+        Some(token) if !token.is_empty() => token,
+        _ => {
+            error!("Rejecting {}: server.admin_token is not set", req.path());
+            return Some(HttpResponse::ServiceUnavailable().body("Admin token not configured"));
+        }
+    };
+
+    let presented = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    // Comparing digests keeps the time taken independent of how much of the
+    // token matched
+    let authorized = presented.is_some_and(|presented| {
+        Sha256::digest(presented.trim().as_bytes()) == Sha256::digest(admin_token.as_bytes())
+    });
+    if !authorized {
+        warn!("Rejecting {}: missing or wrong admin token", req.path());
+        return Some(HttpResponse::Unauthorized().body("Admin token required"));
+    }
+    None
+}
@@ -1,39 +1,32 @@
 use std::sync::Arc;
 
-use actix_web::{get, web, HttpResponse, Responder};
-use log::error;
-use serde_json::json;
+use actix_web::{get, web, Responder};
 
-use crate::services::MetadataGenerator;
+use crate::config::Config;
+use crate::job::{Job, JobQueue};
+use crate::routes::job::enqueue_job;
 
 #[get("/metadata-generation")]
 async fn metadata_generation(
-    metadata_service: web::Data<Arc<MetadataGenerator>>,
+    job_queue: web::Data<Arc<JobQueue>>,
+    config: web::Data<Arc<Config>>,
 ) -> impl Responder {
-    let metadata_service = metadata_service.clone();
-    tokio::spawn(async move {
-        if let Err(e) = metadata_service.generate_article_metadata(30).await {
-            error!("Error generating metadata: {}", e);
-        }
-    });
-
-    HttpResponse::Accepted().json(json!({
-        "message": "Generating metadata",
-    }))
+    enqueue_job(
+        &job_queue,
+        Job::GenerateMetadata(config.metadata.batch_limit),
+        "Metadata generation",
+    )
+    .await
 }
 
 #[get("/failed-articles-metadata-generation")]
 async fn failed_articles_metadata_generation(
-    metadata_service: web::Data<Arc<MetadataGenerator>>,
+    job_queue: web::Data<Arc<JobQueue>>,
 ) -> impl Responder {
-    let metadata_service = metadata_service.clone();
-    tokio::spawn(async move {
-        if let Err(e) = metadata_service.generate_failed_article_metadata().await {
-            error!("Error generating metadata: {}", e);
-        }
-    });
-
-    HttpResponse::Accepted().json(json!({
-        "message": "Generating metadata",
-    }))
+    enqueue_job(
+        &job_queue,
+        Job::RetryFailedMetadata,
+        "Failed article metadata generation",
+    )
+    .await
 }
@@ -0,0 +1,170 @@
+use std::io::{self, BufWriter, Write};
+use std::sync::Arc;
+
+use actix_web::{
+    get,
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    post,
+    web::{self, Bytes, Data, Query},
+    HttpRequest, HttpResponse,
+};
+use chrono::Utc;
+use futures::StreamExt;
+use log::{error, info};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::config::Config;
+use crate::db::DbPool;
+use crate::errors::ArchiveError;
+use crate::routes::admin::reject_unless_admin;
+use crate::services::archive::{export_archive, import_archive};
+
+#[derive(Deserialize)]
+pub struct ExportParams {
+    #[serde(default)]
+    pub embeddings: bool,
+}
+
+/// Buffered archive bytes handed to the response stream at a time.
+const EXPORT_CHUNK_BYTES: usize = 64 * 1024;
+/// Chunks the export may run ahead of a slow download.
+const EXPORT_CHANNEL_CAPACITY: usize = 16;
+
+/// Downloads the knowledge base as a gzip-compressed JSONL archive. Vectors
+/// and chunks are only included with `?embeddings=true`. The archive is
+/// streamed while it is written; an export failing part way aborts the
+/// download instead of ending it cleanly. Requires the admin token, since the
+/// archive includes unpublished and internal articles.
+#[get("/export")]
+pub async fn export(
+    req: HttpRequest,
+    pool: Data<Arc<DbPool>>,
+    config: Data<Arc<Config>>,
+    params: Query<ExportParams>,
+) -> HttpResponse {
+    if let Some(rejection) = reject_unless_admin(&req, &config) {
+        return rejection;
+    }
+    let include_embeddings = params.embeddings;
+    let pool = pool.get_ref().clone();
+    let mut conn = match web::block(move || pool.get()).await {
+        Ok(Ok(conn)) => conn,
+        Ok(Err(e)) => {
+            error!("Failed to get DB connection: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to get DB connection");
+        }
+        Err(e) => {
+            error!("Export task failed: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to export archive");
+        }
+    };
+
+    let (sender, receiver) = mpsc::channel(EXPORT_CHANNEL_CAPACITY);
+    tokio::task::spawn_blocking(move || {
+        let writer = BufWriter::with_capacity(EXPORT_CHUNK_BYTES, ChannelWriter(sender.clone()));
+        match export_archive(&mut conn, writer, include_embeddings) {
+            Ok(summary) => info!("Served export with {}", summary),
+            Err(e) => {
+                error!("Failed to export archive: {}", e);
+                let _ = sender.blocking_send(Err(io::Error::other("Failed to export archive")));
+            }
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type("application/gzip")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(format!(
+                "helpdoc-{}.jsonl.gz",
+                Utc::now().format("%Y%m%d-%H%M%S")
+            ))],
+        })
+        .streaming(ReceiverStream::new(receiver))
+}
+
+/// Passes everything written to it on to a response stream. Writes fail once
+/// the client has gone away, which stops the export.
+struct ChannelWriter(mpsc::Sender<io::Result<Bytes>>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .blocking_send(Ok(Bytes::copy_from_slice(buf)))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "Export download closed"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Imports an archive produced by `/export` from the request body, up to
+/// `import.archive_max_bytes`. Importing the same archive again leaves the
+/// knowledge base unchanged. Requires the admin token.
+#[post("/import")]
+pub async fn import(
+    req: HttpRequest,
+    pool: Data<Arc<DbPool>>,
+    config: Data<Arc<Config>>,
+    mut payload: web::Payload,
+) -> HttpResponse {
+    if let Some(rejection) = reject_unless_admin(&req, &config) {
+        return rejection;
+    }
+    let max_bytes = config.import.archive_max_bytes;
+
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                error!("Failed to read archive upload: {}", e);
+                return HttpResponse::BadRequest().body("Failed to read request body");
+            }
+        };
+        if body.len() + chunk.len() > max_bytes {
+            return HttpResponse::PayloadTooLarge()
+                .body(format!("Archive exceeds {} bytes", max_bytes));
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    let pool = pool.get_ref().clone();
+    let result = web::block(move || {
+        let mut conn = pool.get().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            None
+        })?;
+        import_archive(&mut conn, body.as_ref()).map_err(Some)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(summary)) => HttpResponse::Ok().json(json!({
+            "status": "success",
+            "imported": summary,
+        })),
+        Ok(Err(Some(e @ ArchiveError::Database(_)))) => {
+            error!("Failed to import archive: {}", e);
+            HttpResponse::InternalServerError().body("Failed to import archive")
+        }
+        // The body is already in memory, so I/O errors mean a corrupt archive
+        Ok(Err(Some(e))) => {
+            info!("Rejected archive import: {}", e);
+            HttpResponse::BadRequest().json(json!({
+                "status": "error",
+                "error": e.to_string(),
+            }))
+        }
+        Ok(Err(None)) => HttpResponse::InternalServerError().body("Failed to get DB connection"),
+        Err(e) => {
+            error!("Import task failed: {}", e);
+            HttpResponse::InternalServerError().body("Failed to import archive")
+        }
+    }
+}
@@ -2,26 +2,22 @@
 
 use std::sync::Arc;
 
-use actix_web::{get, post, web, HttpResponse, Responder};
-use diesel::prelude::*;
-use futures::stream::{self, StreamExt};
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
 use log::{error, info};
 use serde_json::json;
 
+use crate::config::Config;
+use crate::db::DbPool;
 use crate::errors::SyncError;
+use crate::job::{Job, JobQueue};
 use crate::models::articles::Article;
-use crate::models::Embedding;
-use crate::schema::articles;
-use crate::{db::DbPool, services::embedding::EmbeddingService};
+use crate::models::{Embedding, EmbeddingGeneration};
+use crate::routes::admin::reject_unless_admin;
+use crate::routes::job::enqueue_job;
 
 #[post("/generate-embeddings")]
-pub async fn generate_embeddings(pool: web::Data<Arc<DbPool>>) -> impl Responder {
-    tokio::spawn(generate_all_embeddings(pool));
-
-    HttpResponse::Accepted().json(json!({
-        "message": "Embedding generation process started",
-        "status": "processing"
-    }))
+pub async fn generate_embeddings(job_queue: web::Data<Arc<JobQueue>>) -> impl Responder {
+    enqueue_job(&job_queue, Job::GenerateEmbeddings, "Embedding generation").await
 }
 
 #[get("/get-failed-embeddings")]
@@ -37,86 +33,73 @@ pub async fn get_failed_embedding_articles(pool: web::Data<Arc<DbPool>>) -> impl
     }))
 }
 
+/// Re-embeds every article into a new generation; search switches to it once
+/// the job completes. Requires the admin token.
 #[post("/reembed-all")]
-pub async fn reembed_all_articles(pool: web::Data<Arc<DbPool>>) -> impl Responder {
-    let embedding_service = EmbeddingService::new();
-    let mut conn = pool.get().expect("couldn't get db connection from pool");
+pub async fn reembed_all_articles(
+    req: HttpRequest,
+    job_queue: web::Data<Arc<JobQueue>>,
+    config: web::Data<Arc<Config>>,
+) -> impl Responder {
+    if let Some(rejection) = reject_unless_admin(&req, &config) {
+        return rejection;
+    }
+    enqueue_job(&job_queue, Job::ReembedAll, "Re-embedding of all articles").await
+}
 
-    tokio::spawn(async move {
-        if let Err(e) = embedding_service.reembed_all_articles(&mut conn).await {
-            error!("Error re-embedding articles: {}", e);
+#[get("/embedding-generations")]
+pub async fn get_embedding_generations(pool: web::Data<Arc<DbPool>>) -> impl Responder {
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to get DB connection: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to get DB connection");
         }
-    });
+    };
 
-    HttpResponse::Ok().json(json!({
-        "message": "Started re-embedding all articles",
-        "status": "success"
-    }))
+    match EmbeddingGeneration::load_all(&mut conn) {
+        Ok(generations) => HttpResponse::Ok().json(json!({
+            "generations": generations,
+            "status": "success"
+        })),
+        Err(e) => {
+            error!("Failed to load embedding generations: {}", e);
+            HttpResponse::InternalServerError().body("Failed to load embedding generations")
+        }
+    }
 }
 
-async fn generate_all_embeddings(pool: web::Data<Arc<DbPool>>) -> Result<(), SyncError> {
-    let mut conn = pool.get().expect("couldn't get db connection from pool");
-    let embedding_service = EmbeddingService::new();
-
-    match articles::table.load::<Article>(&mut conn) {
-        Ok(article_list) => {
-            info!("Successfully retrieved {} articles", article_list.len());
-            let mut success_count = 0;
-            let mut error_count = 0;
-            let article_count = article_list.len();
-
-            // Process articles in batches of 50
-            let results = stream::iter(article_list)
-                .chunks(50)
-                .map(|chunk| async {
-                    let mut batch_success = 0;
-                    let mut batch_error = 0;
-                    for article in chunk {
-                        let mut conn = pool.get().expect("couldn't get db connection from pool");
-                        match embedding_service
-                            .generate_and_store_embedding(&mut conn, &article)
-                            .await
-                        {
-                            Ok(_) => {
-                                batch_success += 1;
-                                info!(
-                                    "Successfully generated and stored embedding for article {}",
-                                    article.id
-                                );
-                            }
-                            Err(e) => {
-                                batch_error += 1;
-                                error!(
-                                    "Failed to generate/store embedding for article {}: {}",
-                                    article.id, e
-                                );
-                            }
-                        }
-                    }
-                    (batch_success, batch_error)
-                })
-                .buffer_unordered(4)
-                .collect::<Vec<_>>()
-                .await;
-
-            for (batch_success, batch_error) in results {
-                success_count += batch_success;
-                error_count += batch_error;
-            }
-
-            info!("Embedding generation process completed: total_articles: {}, successful: {}, failed: {}",
-                article_count,
-                success_count,
-                error_count
-            );
-            Ok(())
+/// Switches search back to the generation that was active before the last
+/// re-embedding. Requires the admin token.
+#[post("/embedding-generations/rollback")]
+pub async fn rollback_embedding_generation(
+    req: HttpRequest,
+    pool: web::Data<Arc<DbPool>>,
+    config: web::Data<Arc<Config>>,
+) -> impl Responder {
+    if let Some(rejection) = reject_unless_admin(&req, &config) {
+        return rejection;
+    }
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to get DB connection: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to get DB connection");
         }
+    };
+
+    match EmbeddingGeneration::rollback(&mut conn) {
+        Ok(Some(generation)) => HttpResponse::Ok().json(json!({
+            "generation": generation,
+            "status": "success"
+        })),
+        Ok(None) => HttpResponse::Conflict().json(json!({
+            "message": "No previous embedding generation to roll back to",
+            "status": "error"
+        })),
         Err(e) => {
-            error!("Error fetching articles: {:?}", e);
-            Err(SyncError::EmbeddingError(anyhow::anyhow!(
-                "Failed to fetch articles: {}",
-                e
-            )))
+            error!("Failed to roll back embedding generation: {}", e);
+            HttpResponse::InternalServerError().body("Failed to roll back embedding generation")
         }
     }
 }
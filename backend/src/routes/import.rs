@@ -0,0 +1,33 @@
+use std::sync::Arc;
+
+use actix_web::{post, web, HttpRequest, HttpResponse, Responder};
+use log::error;
+
+use crate::config::Config;
+use crate::job::{Job, JobQueue};
+use crate::routes::admin::reject_unless_admin;
+use crate::routes::job::enqueue_job;
+
+/// Imports the Markdown directory configured by `import.markdown_dir`. The
+/// directory is not taken from the request so callers can't read arbitrary
+/// paths on the server. Requires the admin token.
+#[post("/import/markdown")]
+pub async fn import_markdown(
+    req: HttpRequest,
+    job_queue: web::Data<Arc<JobQueue>>,
+    config: web::Data<Arc<Config>>,
+) -> impl Responder {
+    if let Some(rejection) = reject_unless_admin(&req, &config) {
+        return rejection;
+    }
+    let dir = match &config.import.markdown_dir {
+        Some(dir) if !dir.is_empty() => dir.clone(),
+        _ => {
+            error!("Rejecting Markdown import: import.markdown_dir is not set");
+            return HttpResponse::ServiceUnavailable()
+                .body("Markdown import directory not configured");
+        }
+    };
+
+    enqueue_job(&job_queue, Job::ImportMarkdown(dir), "Markdown import").await
+}
@@ -1,21 +1,321 @@
 use std::sync::Arc;
 
-use actix_web::{get, web, HttpResponse, Responder};
+use actix_web::{get, post, web, web::Bytes, HttpResponse, Responder};
+use futures::stream::{self, StreamExt};
+use log::{error, warn};
+use serde::Serialize;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tokio::time::{timeout, Duration};
 use uuid::Uuid;
 
-use crate::job::JobQueue;
+use crate::db::DbPool;
+use crate::errors::JobControlError;
+use crate::job::{Job, JobEvent, JobEventKind, JobQueue};
+use crate::models::{JobInfo, JobStatus, JobStatusChange};
 
 #[get("/job/{job_id}/status")]
 pub async fn get_job_status(
+    pool: web::Data<Arc<DbPool>>,
+    job_id: web::Path<Uuid>,
+) -> impl Responder {
+    let job_id = job_id.into_inner();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to get DB connection: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to get DB connection");
+        }
+    };
+
+    let job = match JobInfo::find(&mut conn, job_id) {
+        Ok(Some(job)) => job,
+        Ok(None) => return HttpResponse::NotFound().body("Job not found"),
+        Err(e) => {
+            error!("Failed to load job {}: {}", job_id, e);
+            return HttpResponse::InternalServerError().body("Failed to load job");
+        }
+    };
+
+    let history = match JobStatusChange::for_job(&mut conn, job_id) {
+        Ok(history) => history,
+        Err(e) => {
+            error!("Failed to load history for job {}: {}", job_id, e);
+            return HttpResponse::InternalServerError().body("Failed to load job history");
+        }
+    };
+
+    let children = match JobInfo::child_counts(&mut conn, job_id) {
+        Ok(counts) => counts,
+        Err(e) => {
+            error!("Failed to load child jobs of job {}: {}", job_id, e);
+            return HttpResponse::InternalServerError().body("Failed to load child jobs");
+        }
+    };
+
+    let child_errors = match JobInfo::failed_children(&mut conn, job_id) {
+        Ok(failed) => failed
+            .into_iter()
+            .map(|child| {
+                serde_json::json!({
+                    "job_id": child.id,
+                    "job_type": child.job_type,
+                    "status": child.status,
+                    "attempts": child.attempts,
+                    "error": child.last_error
+                })
+            })
+            .collect::<Vec<_>>(),
+        Err(e) => {
+            error!("Failed to load failed child jobs of job {}: {}", job_id, e);
+            return HttpResponse::InternalServerError().body("Failed to load child jobs");
+        }
+    };
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "job_id": job_id,
+        "job_type": job.job_type,
+        "status": job.status,
+        "attempts": job.attempts,
+        "max_attempts": job.max_attempts,
+        "last_error": job.last_error,
+        "run_at": job.run_at,
+        "started_at": job.started_at,
+        "finished_at": job.finished_at,
+        "parent_id": job.parent_id,
+        "children": children,
+        "child_errors": child_errors,
+        "history": history
+    }))
+}
+
+const EVENTS_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// Streams the job's progress as Server-Sent Events: a `snapshot` of the
+/// stored state first, then live events of the job and its children. The
+/// stream ends once the job and all of its children are finished.
+#[get("/job/{job_id}/events")]
+pub async fn get_job_events(
+    pool: web::Data<Arc<DbPool>>,
     job_queue: web::Data<Arc<JobQueue>>,
     job_id: web::Path<Uuid>,
 ) -> impl Responder {
     let job_id = job_id.into_inner();
-    match job_queue.get_job_status(job_id) {
-        Some(status) => HttpResponse::Ok().json(serde_json::json!({
+    // Subscribe before reading the snapshot so no event falls in between
+    let receiver = job_queue.subscribe();
+
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to get DB connection: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to get DB connection");
+        }
+    };
+    let snapshot = JobInfo::find(&mut conn, job_id).and_then(|job| match job {
+        Some(job) => {
+            let children = JobInfo::child_counts(&mut conn, job_id)?;
+            let finished = job.is_finished(&mut conn)?;
+            Ok(Some((job, children, finished)))
+        }
+        None => Ok(None),
+    });
+    let (job, children, finished) = match snapshot {
+        Ok(Some(snapshot)) => snapshot,
+        Ok(None) => return HttpResponse::NotFound().body("Job not found"),
+        Err(e) => {
+            error!("Failed to load job {}: {}", job_id, e);
+            return HttpResponse::InternalServerError().body("Failed to load job");
+        }
+    };
+
+    let snapshot = sse_frame(
+        "snapshot",
+        &serde_json::json!({
+            "job_id": job.id,
+            "parent_id": job.parent_id,
+            "job_type": job.job_type,
+            "status": job.status,
+            "attempts": job.attempts,
+            "last_error": job.last_error,
+            "children": children
+        }),
+    );
+    let state = EventStream {
+        receiver,
+        pool: pool.get_ref().clone(),
+        job_id,
+        finished,
+    };
+    let events = stream::once(async { Ok::<_, actix_web::Error>(snapshot) })
+        .chain(stream::unfold(state, next_event).map(Ok));
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events)
+}
+
+struct EventStream {
+    receiver: Receiver<JobEvent>,
+    pool: Arc<DbPool>,
+    job_id: Uuid,
+    finished: bool,
+}
+
+async fn next_event(mut state: EventStream) -> Option<(Bytes, EventStream)> {
+    if state.finished {
+        return None;
+    }
+
+    loop {
+        let event = match timeout(EVENTS_KEEP_ALIVE, state.receiver.recv()).await {
+            Ok(Ok(event)) => event,
+            Ok(Err(RecvError::Lagged(skipped))) => {
+                warn!(
+                    "Event stream of job {} skipped {} events",
+                    state.job_id, skipped
+                );
+                // The skipped events may have included the job finishing
+                if let Some(event) = stored_final_status(&state.pool, state.job_id) {
+                    state.finished = true;
+                    return Some((sse_frame(event.kind.name(), &event), state));
+                }
+                continue;
+            }
+            Ok(Err(RecvError::Closed)) => return None,
+            Err(_) => {
+                // The job may have finished without an event reaching us,
+                // e.g. when its last child was finished by another process
+                if let Some(event) = stored_final_status(&state.pool, state.job_id) {
+                    state.finished = true;
+                    return Some((sse_frame(event.kind.name(), &event), state));
+                }
+                // A comment line keeps proxies from closing an idle stream
+                return Some((Bytes::from_static(b": keep-alive\n\n"), state));
+            }
+        };
+
+        if event.job_id != state.job_id && event.parent_id != Some(state.job_id) {
+            continue;
+        }
+
+        let may_finish = match &event.kind {
+            JobEventKind::Status { status, .. } => status.is_terminal(),
+            JobEventKind::Progress { .. } => event.job_id == state.job_id,
+            _ => false,
+        };
+        if may_finish {
+            state.finished = job_finished(&state.pool, state.job_id);
+        }
+
+        return Some((sse_frame(event.kind.name(), &event), state));
+    }
+}
+
+fn job_finished(pool: &DbPool, job_id: Uuid) -> bool {
+    let finished = pool
+        .get()
+        .map_err(anyhow::Error::from)
+        .and_then(|mut conn| {
+            Ok(match JobInfo::find(&mut conn, job_id)? {
+                Some(job) => job.is_finished(&mut conn)?,
+                None => true,
+            })
+        });
+    finished.unwrap_or_else(|e| {
+        error!("Failed to check whether job {} finished: {}", job_id, e);
+        false
+    })
+}
+
+/// The job's stored status as a `status` event, if the job and all of its
+/// children are finished.
+fn stored_final_status(pool: &DbPool, job_id: Uuid) -> Option<JobEvent> {
+    let event = pool
+        .get()
+        .map_err(anyhow::Error::from)
+        .and_then(|mut conn| {
+            let Some(job) = JobInfo::find(&mut conn, job_id)? else {
+                return Ok(None);
+            };
+            if !job.is_finished(&mut conn)? {
+                return Ok(None);
+            }
+            Ok(Some(JobEvent {
+                job_id,
+                parent_id: job.parent_id,
+                kind: JobEventKind::Status {
+                    status: job.status()?,
+                    message: job.last_error,
+                },
+            }))
+        });
+    event.unwrap_or_else(|e| {
+        error!("Failed to check whether job {} finished: {}", job_id, e);
+        None
+    })
+}
+
+fn sse_frame<T: Serialize>(event: &str, data: &T) -> Bytes {
+    let data = serde_json::to_string(data).unwrap_or_else(|_| "null".to_string());
+    Bytes::from(format!("event: {}\ndata: {}\n\n", event, data))
+}
+
+#[post("/job/{job_id}/cancel")]
+pub async fn cancel_job(
+    job_queue: web::Data<Arc<JobQueue>>,
+    job_id: web::Path<Uuid>,
+) -> impl Responder {
+    let job_id = job_id.into_inner();
+    control_response(job_id, job_queue.cancel_job(job_id))
+}
+
+#[post("/job/{job_id}/pause")]
+pub async fn pause_job(
+    job_queue: web::Data<Arc<JobQueue>>,
+    job_id: web::Path<Uuid>,
+) -> impl Responder {
+    let job_id = job_id.into_inner();
+    control_response(job_id, job_queue.pause_job(job_id))
+}
+
+#[post("/job/{job_id}/resume")]
+pub async fn resume_job(
+    job_queue: web::Data<Arc<JobQueue>>,
+    job_id: web::Path<Uuid>,
+) -> impl Responder {
+    let job_id = job_id.into_inner();
+    control_response(job_id, job_queue.resume_job(job_id))
+}
+
+fn control_response(job_id: Uuid, result: Result<JobStatus, JobControlError>) -> HttpResponse {
+    match result {
+        Ok(status) => HttpResponse::Ok().json(serde_json::json!({
+            "job_id": job_id,
+            "status": status
+        })),
+        Err(JobControlError::NotFound(_)) => HttpResponse::NotFound().body("Job not found"),
+        Err(e @ JobControlError::AlreadyFinished(..)) => {
+            HttpResponse::Conflict().body(e.to_string())
+        }
+        Err(e) => {
+            error!("Failed to control job {}: {}", job_id, e);
+            HttpResponse::InternalServerError().body("Failed to control job")
+        }
+    }
+}
+
+/// Enqueues a job on behalf of a route and answers with its ID.
+pub(crate) async fn enqueue_job(job_queue: &JobQueue, job: Job, description: &str) -> HttpResponse {
+    match job_queue.enqueue_job(job).await {
+        Ok(job_id) => HttpResponse::Accepted().json(serde_json::json!({
+            "message": format!("{} enqueued", description),
+            "status": "queued",
             "job_id": job_id,
-            "status": format!("{:?}", status)
+            "status_url": format!("/job/{}/status", job_id)
         })),
-        None => HttpResponse::NotFound().body("Job not found"),
+        Err(e) => {
+            error!("Failed to enqueue job: {}", e);
+            HttpResponse::InternalServerError().body("Failed to enqueue job")
+        }
     }
 }
@@ -1,11 +1,19 @@
 use actix_web::{get, post, web, HttpResponse, Responder};
 use reqwest::Client;
+use std::sync::Arc;
 
+use crate::config::Config;
+
+pub mod admin;
 pub mod ai_generation;
+pub mod archive;
 pub mod embed;
+pub mod import;
 pub mod job;
 pub mod parse;
+pub mod schedule;
 pub mod search;
+pub mod webhook;
 pub mod ws;
 
 pub fn init_routes(cfg: &mut web::ServiceConfig) {
@@ -13,10 +21,22 @@ pub fn init_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(health);
     cfg.service(test_embed);
     cfg.service(parse::parse_data);
+    cfg.service(parse::get_sync_removals);
     cfg.service(job::get_job_status);
+    cfg.service(job::get_job_events);
+    cfg.service(job::cancel_job);
+    cfg.service(job::pause_job);
+    cfg.service(job::resume_job);
+    cfg.service(schedule::get_schedules);
+    cfg.service(webhook::helpscout_webhook);
+    cfg.service(import::import_markdown);
+    cfg.service(archive::export);
+    cfg.service(archive::import);
     cfg.service(embed::generate_embeddings);
     cfg.service(embed::get_failed_embedding_articles);
     cfg.service(embed::reembed_all_articles);
+    cfg.service(embed::get_embedding_generations);
+    cfg.service(embed::rollback_embedding_generation);
     cfg.service(search::search);
     cfg.service(ai_generation::metadata_generation);
     cfg.service(ai_generation::failed_articles_metadata_generation);
@@ -28,10 +48,10 @@ pub async fn index() -> impl Responder {
 }
 
 #[post("/health")]
-pub async fn health() -> impl Responder {
+pub async fn health(config: web::Data<Arc<Config>>) -> impl Responder {
     let client = Client::new();
 
-    match client.get("http://localhost:8080/health").send().await {
+    match client.get(config.embedding.endpoint("health")).send().await {
         Ok(resp) => {
             if resp.status().is_success() {
                 HttpResponse::Ok().body("Embedding service is healthy")
@@ -59,9 +79,12 @@ pub async fn health() -> impl Responder {
 }
 
 #[get("/test-embed")]
-pub async fn test_embed() -> impl Responder {
+pub async fn test_embed(config: web::Data<Arc<Config>>) -> impl Responder {
     let client = Client::new();
-    let resp = client.get("http://localhost:8080/test-embed").send().await;
+    let resp = client
+        .get(config.embedding.endpoint("test-embed"))
+        .send()
+        .await;
     match resp {
         Ok(resp) => {
             if resp.status().is_success() {
@@ -1,36 +1,76 @@
-use crate::{errors::SyncError, services::DataProcessor};
-use actix_web::{post, web::Data, HttpResponse};
-use log::info;
+use crate::{
+    db::DbPool,
+    errors::EnqueueError,
+    job::{Job, JobQueue},
+    models::ArticleRemoval,
+    services::data_processor::SyncMode,
+};
+use actix_web::{
+    get, post,
+    web::{Data, Query},
+    HttpResponse,
+};
+use log::{error, info};
+use serde::Deserialize;
 use serde_json::json;
 use std::sync::Arc;
 
-#[post("/parse")]
-pub async fn parse_data(data_processor: Data<Arc<DataProcessor>>) -> HttpResponse {
-    tokio::spawn(start_job_queue(data_processor));
+#[derive(Deserialize)]
+pub struct ParseParams {
+    #[serde(default)]
+    pub mode: SyncMode,
+}
 
-    HttpResponse::Accepted().json(json!({
-        "message": "Job queue started successfully",
-        "status": "processing"
-    }))
+#[post("/parse")]
+pub async fn parse_data(
+    job_queue: Data<Arc<JobQueue>>,
+    params: Query<ParseParams>,
+) -> HttpResponse {
+    let mode = params.mode;
+    // A sync started while another is still running would fetch every
+    // article twice
+    match job_queue.enqueue_exclusive(Job::SyncAll(mode)).await {
+        Ok(job_id) => {
+            info!("Enqueued {:?} sync as job: {}", mode, job_id);
+            HttpResponse::Accepted().json(json!({
+                "message": "Sync job enqueued successfully",
+                "status": "queued",
+                "mode": mode,
+                "job_id": job_id,
+                "status_url": format!("/job/{}/status", job_id)
+            }))
+        }
+        Err(EnqueueError::AlreadyActive { job_id, .. }) => HttpResponse::Conflict().json(json!({
+            "message": "A sync is already running",
+            "status": "conflict",
+            "job_id": job_id,
+            "status_url": format!("/job/{}/status", job_id)
+        })),
+        Err(e) => {
+            error!("Failed to enqueue sync job: {}", e);
+            HttpResponse::InternalServerError().body("Failed to enqueue sync job")
+        }
+    }
 }
 
-async fn start_job_queue(data_processor: Data<Arc<DataProcessor>>) -> Result<(), SyncError> {
-    info!("Starting job queue");
-    let collections = data_processor
-        .api_client
-        .get_list_collections()
-        .await
-        .map_err(SyncError::CollectionFetchError)?;
+#[get("/sync/removals")]
+pub async fn get_sync_removals(pool: Data<Arc<DbPool>>) -> HttpResponse {
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to get DB connection: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to get DB connection");
+        }
+    };
 
-    for collection in collections {
-        data_processor
-            .prepare_sync_collection(&collection)
-            .await
-            .map_err(|e| SyncError::JobPreparationError {
-                collection_id: collection.id.to_string(),
-                error: e,
-            })?;
+    match ArticleRemoval::load_recent(&mut conn, 100) {
+        Ok(removals) => HttpResponse::Ok().json(json!({
+            "removals": removals,
+            "status": "success"
+        })),
+        Err(e) => {
+            error!("Failed to load article removals: {}", e);
+            HttpResponse::InternalServerError().body("Failed to load article removals")
+        }
     }
-
-    Ok(())
 }
@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use actix_web::{get, web, HttpResponse, Responder};
+use chrono::Utc;
+use log::error;
+use serde_json::json;
+
+use crate::db::DbPool;
+use crate::job::Scheduler;
+use crate::models::ScheduledRun;
+
+#[get("/schedules")]
+pub async fn get_schedules(
+    pool: web::Data<Arc<DbPool>>,
+    scheduler: web::Data<Arc<Scheduler>>,
+) -> impl Responder {
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to get DB connection: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to get DB connection");
+        }
+    };
+
+    let runs = match ScheduledRun::load_recent(&mut conn, 100) {
+        Ok(runs) => runs,
+        Err(e) => {
+            error!("Failed to load scheduled runs: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to load scheduled runs");
+        }
+    };
+
+    let now = Utc::now();
+    let schedules = scheduler
+        .tasks()
+        .iter()
+        .map(|task| {
+            json!({
+                "name": task.name,
+                "expression": task.expression,
+                "job_type": task.job.name(),
+                "next_run": task.next_after(now)
+            })
+        })
+        .collect::<Vec<_>>();
+    let runs = runs
+        .into_iter()
+        .map(|(run, job_status)| {
+            json!({
+                "id": run.id,
+                "schedule_name": run.schedule_name,
+                "status": run.status,
+                "message": run.message,
+                "job_id": run.job_id,
+                "job_status": job_status,
+                "scheduled_for": run.scheduled_for,
+                "created_at": run.created_at
+            })
+        })
+        .collect::<Vec<_>>();
+
+    HttpResponse::Ok().json(json!({
+        "schedules": schedules,
+        "runs": runs,
+        "status": "success"
+    }))
+}
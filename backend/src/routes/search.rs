@@ -1,7 +1,9 @@
 use actix_web::{post, web, HttpResponse, Responder};
 use std::sync::Arc;
 
-use crate::services::search::{ArticleResult, SearchQuery, SearchResult, SearchService};
+use crate::services::search::{
+    ArticleResult, PassageResult, SearchQuery, SearchResult, SearchService,
+};
 use log::info;
 
 #[post("/search")]
@@ -34,17 +36,28 @@ async fn search(
                 "Two-stage retrieval successful, found {} results",
                 article_contents.len()
             );
-            // Step 3: Convert the results to the desired format
+            // Step 3: Convert the results to the desired format, highlighting
+            // the terms the user typed rather than the expanded ones
             let articles: Vec<ArticleResult> = article_contents
                 .into_iter()
-                .enumerate()
-                .map(|(_, article)| ArticleResult {
-                    id: article.id,
-                    title: article.title,
-                    content: article
-                        .markdown_content
-                        .unwrap_or("No content found".to_string()),
-                    slug: article.slug,
+                .map(|(article, chunks)| {
+                    let passages: Vec<PassageResult> = chunks
+                        .into_iter()
+                        .map(|chunk| PassageResult::new(chunk, &original_query))
+                        .collect();
+                    let content = match passages.first() {
+                        Some(passage) => passage.content.clone(),
+                        None => article
+                            .markdown_content
+                            .unwrap_or("No content found".to_string()),
+                    };
+                    ArticleResult {
+                        id: article.id,
+                        title: article.title,
+                        content,
+                        slug: article.slug,
+                        passages,
+                    }
                 })
                 .collect();
 
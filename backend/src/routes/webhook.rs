@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use actix_web::{post, web, HttpRequest, HttpResponse, Responder};
+use log::{error, info, warn};
+use serde_json::json;
+
+use crate::config::Config;
+use crate::errors::WebhookError;
+use crate::job::{Job, JobQueue};
+use crate::services::data_processor::webhook::{
+    parse_event, verify_signature, WebhookEvent, EVENT_HEADER, SIGNATURE_HEADER,
+};
+use crate::services::DataProcessor;
+
+#[post("/webhooks/helpscout")]
+pub async fn helpscout_webhook(
+    req: HttpRequest,
+    body: web::Bytes,
+    data_processor: web::Data<Arc<DataProcessor>>,
+    job_queue: web::Data<Arc<JobQueue>>,
+    config: web::Data<Arc<Config>>,
+) -> impl Responder {
+    let secret = match config.helpscout.webhook_secret.as_deref() {
+        Some(secret) if !secret.is_empty() => secret,
+        _ => {
+            error!("Rejecting Help Scout webhook: helpscout.webhook_secret is not set");
+            return HttpResponse::ServiceUnavailable().body("Webhook secret not configured");
+        }
+    };
+
+    let header = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok());
+    let verified = header(SIGNATURE_HEADER)
+        .ok_or(WebhookError::MissingSignature)
+        .and_then(|signature| verify_signature(secret.as_bytes(), &body, signature));
+    if let Err(e) = verified {
+        warn!("Rejecting Help Scout webhook: {}", e);
+        return HttpResponse::Unauthorized().body(e.to_string());
+    }
+
+    let event_type = header(EVENT_HEADER).unwrap_or_default();
+    let event = match parse_event(event_type, &body) {
+        Ok(event) => event,
+        Err(WebhookError::UnsupportedEvent(event_type)) => {
+            // Acknowledge so Help Scout doesn't keep redelivering it
+            info!("Ignoring Help Scout webhook event: {}", event_type);
+            return HttpResponse::Ok().json(json!({ "status": "ignored" }));
+        }
+        Err(e) => {
+            warn!("Rejecting Help Scout webhook: {}", e);
+            return HttpResponse::BadRequest().body(e.to_string());
+        }
+    };
+
+    let job = match event {
+        WebhookEvent::ArticleCreated(article_ref) | WebhookEvent::ArticleUpdated(article_ref) => {
+            // Whatever source.kind syncs use, the article lives in Help Scout
+            if let Err(e) = data_processor.helpscout_source() {
+                error!("Rejecting Help Scout webhook: {}", e);
+                return HttpResponse::ServiceUnavailable().body("Help Scout API not configured");
+            }
+            match data_processor
+                .resolve_helpscout_collection(&article_ref.collection_id)
+                .await
+            {
+                Ok(collection) => Job::SyncHelpScoutArticle(article_ref, collection),
+                Err(e) => {
+                    error!(
+                        "Failed to resolve collection {} for webhook: {}",
+                        article_ref.collection_id, e
+                    );
+                    return HttpResponse::InternalServerError()
+                        .body("Failed to resolve collection");
+                }
+            }
+        }
+        WebhookEvent::ArticleDeleted(helpscout_article_id) => {
+            Job::ArchiveArticle(helpscout_article_id)
+        }
+    };
+
+    match job_queue.enqueue_job(job).await {
+        Ok(job_id) => {
+            info!(
+                "Enqueued job {} for Help Scout event {}",
+                job_id, event_type
+            );
+            HttpResponse::Accepted().json(json!({
+                "status": "queued",
+                "event": event_type,
+                "job_id": job_id
+            }))
+        }
+        Err(e) => {
+            error!("Failed to enqueue webhook job: {}", e);
+            HttpResponse::InternalServerError().body("Failed to enqueue job")
+        }
+    }
+}
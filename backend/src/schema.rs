@@ -10,6 +10,26 @@ diesel::table! {
         content -> Text,
         is_title -> Bool,
         embedding_id -> Nullable<Uuid>,
+        generation_id -> Int4,
+        heading_path -> Array<Text>,
+        start_offset -> Nullable<Int4>,
+        end_offset -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    article_removals (id) {
+        id -> Uuid,
+        article_id -> Uuid,
+        helpscout_article_id -> Nullable<Varchar>,
+        #[max_length = 255]
+        title -> Varchar,
+        #[max_length = 50]
+        reason -> Varchar,
+        removed_at -> Timestamptz,
     }
 }
 
@@ -40,6 +60,11 @@ diesel::table! {
         paragraph_description_embedding -> Nullable<Vector>,
         bullet_points_embedding -> Nullable<Vector>,
         keywords_embedding -> Nullable<Vector>,
+        archived_at -> Nullable<Timestamptz>,
+        #[max_length = 64]
+        content_hash -> Nullable<Varchar>,
+        #[max_length = 255]
+        metadata_embedding_model -> Nullable<Varchar>,
     }
 }
 
@@ -64,6 +89,8 @@ diesel::table! {
         paragraph_description_embedding -> Nullable<Vector>,
         bullet_points_embedding -> Nullable<Vector>,
         keywords_embedding -> Nullable<Vector>,
+        #[max_length = 255]
+        metadata_embedding_model -> Nullable<Varchar>,
     }
 }
 
@@ -82,6 +109,36 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    embedding_cache (content_hash, model) {
+        #[max_length = 64]
+        content_hash -> Varchar,
+        #[max_length = 255]
+        model -> Varchar,
+        embedding_vector -> Vector,
+        created_at -> Timestamptz,
+        last_used_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    embedding_generations (id) {
+        id -> Int4,
+        #[max_length = 255]
+        model -> Varchar,
+        #[max_length = 50]
+        status -> Varchar,
+        created_at -> Timestamptz,
+        activated_at -> Nullable<Timestamptz>,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use pgvector::sql_types::*;
@@ -90,18 +147,85 @@ diesel::table! {
         id -> Uuid,
         article_id -> Uuid,
         embedding_vector -> Vector,
+        #[max_length = 255]
+        model -> Varchar,
+        dimensions -> Int4,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    job_status_history (id) {
+        id -> Uuid,
+        job_id -> Uuid,
+        #[max_length = 50]
+        status -> Varchar,
+        message -> Nullable<Text>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    jobs (id) {
+        id -> Uuid,
+        #[max_length = 100]
+        job_type -> Varchar,
+        payload -> Jsonb,
+        #[max_length = 50]
+        status -> Varchar,
+        attempts -> Int4,
+        max_attempts -> Int4,
+        last_error -> Nullable<Text>,
+        run_at -> Timestamptz,
+        started_at -> Nullable<Timestamptz>,
+        finished_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        parent_id -> Nullable<Uuid>,
+        heartbeat_at -> Nullable<Timestamptz>,
+        result -> Nullable<Jsonb>,
+        cancel_requested -> Bool,
+    }
+}
+
+diesel::table! {
+    scheduled_runs (id) {
+        id -> Uuid,
+        #[max_length = 100]
+        schedule_name -> Varchar,
+        job_id -> Nullable<Uuid>,
+        #[max_length = 50]
+        status -> Varchar,
+        message -> Nullable<Text>,
+        scheduled_for -> Timestamptz,
+        created_at -> Timestamptz,
     }
 }
 
 diesel::joinable!(article_chunks -> articles (article_id));
+diesel::joinable!(article_chunks -> embedding_generations (generation_id));
+diesel::joinable!(article_removals -> articles (article_id));
 diesel::joinable!(articles -> collections (collection_id));
 diesel::joinable!(content_versions -> articles (article_id));
 diesel::joinable!(embeddings -> articles (article_id));
+diesel::joinable!(job_status_history -> jobs (job_id));
+diesel::joinable!(scheduled_runs -> jobs (job_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     article_chunks,
+    article_removals,
     articles,
     collections,
     content_versions,
+    embedding_cache,
+    embedding_generations,
     embeddings,
+    job_status_history,
+    jobs,
+    scheduled_runs,
 );
@@ -22,7 +22,7 @@ impl AIService {
             .ollama
             .send_chat_messages_with_history_stream(
                 ChatMessageRequest::new(
-                    "llama3.1:latest".to_string(),
+                    self.model.clone(),
                     vec![ChatMessage::user(input.clone())],
                 ),
                 "user".to_string(),
@@ -57,11 +57,9 @@ impl AIService {
         input: String,
     ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
         info!("Starting Model Generation");
-        let model = "llama3.1:latest".to_string();
-
         let res = self
             .ollama
-            .generate(GenerationRequest::new(model, input))
+            .generate(GenerationRequest::new(self.model.clone(), input))
             .await;
 
         match res {
@@ -3,19 +3,31 @@ use ollama_rs::Ollama;
 use std::error::Error as StdError;
 use std::fmt;
 
+use crate::config::{self, AiConfig};
+
 pub mod generate_metadata;
 pub mod generate_response;
 
 #[derive(Clone)]
 pub struct AIService {
     ollama: Ollama,
+    model: String,
 }
 
 impl AIService {
     pub fn new() -> Self {
+        Self::with_config(&config::get().ai)
+    }
+
+    pub fn with_config(config: &AiConfig) -> Self {
         info!("Initializing new AIService");
         Self {
-            ollama: Ollama::new_default_with_history(30),
+            ollama: Ollama::new_with_history(
+                config.ollama_host.clone(),
+                config.ollama_port,
+                config.history_size,
+            ),
+            model: config.model.clone(),
         }
     }
 }
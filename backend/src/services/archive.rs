@@ -0,0 +1,520 @@
+//! Portable export of the knowledge base: gzip-compressed JSON Lines, one
+//! record per line. The first line is an `ArchiveHeader`, followed by every
+//! collection, then every article, each article directly followed by its
+//! chunks when embeddings are included. Only chunks of the active embedding
+//! generation are exported, and imported chunks join the active generation.
+//!
+//! Collections and articles are written as their model structs, so fields
+//! added to `Collection` or `Article` later need `#[serde(default)]` for older
+//! archives to keep importing.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, Read, Write};
+
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use log::info;
+use pgvector::Vector;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::errors::ArchiveError;
+use crate::models::embedding::LEGACY_EMBEDDING_MODEL;
+use crate::models::{
+    Article, ArticleChunk, Collection, Embedding, EmbeddingGeneration, GenerationStatus,
+};
+use crate::schema::{article_chunks, articles, collections, embedding_generations, embeddings};
+
+pub const ARCHIVE_FORMAT: &str = "helpdoc-archive";
+/// Version 2 added `ChunkRecord::embedding_model`, version 3 the chunk's
+/// heading path and offsets.
+pub const ARCHIVE_VERSION: u32 = 3;
+
+/// Articles are exported in pages of this size to bound memory use.
+const EXPORT_BATCH_SIZE: i64 = 500;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveHeader {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    /// Whether metadata vectors and chunks with their embeddings are included.
+    pub includes_embeddings: bool,
+}
+
+impl ArchiveHeader {
+    /// Fails for archives written by something else or by a newer version.
+    pub fn validate(&self) -> Result<(), ArchiveError> {
+        if self.format != ARCHIVE_FORMAT || self.version > ARCHIVE_VERSION {
+            return Err(ArchiveError::UnsupportedVersion {
+                format: self.format.clone(),
+                version: self.version,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkRecord {
+    pub article_id: Uuid,
+    pub content: String,
+    pub is_title: bool,
+    pub embedding: Option<Vector>,
+    /// Model of `embedding`; missing in version 1 archives, whose embeddings
+    /// all came from `LEGACY_EMBEDDING_MODEL`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub heading_path: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_offset: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_offset: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ArchiveRecord {
+    Header(ArchiveHeader),
+    Collection(Collection),
+    Article(Article),
+    Chunk(ChunkRecord),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ArchiveSummary {
+    pub collections: usize,
+    pub articles: usize,
+    pub chunks: usize,
+}
+
+impl std::fmt::Display for ArchiveSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} collections, {} articles, {} chunks",
+            self.collections, self.articles, self.chunks
+        )
+    }
+}
+
+/// Writes the whole knowledge base to `writer` as a compressed archive. The
+/// export reads inside one `REPEATABLE READ` transaction, so the archive is a
+/// consistent snapshot even while syncs write to the database.
+pub fn export_archive<W: Write>(
+    conn: &mut PgConnection,
+    writer: W,
+    include_embeddings: bool,
+) -> Result<ArchiveSummary, ArchiveError> {
+    let mut out = GzEncoder::new(writer, Compression::default());
+
+    let summary = conn
+        .build_transaction()
+        .repeatable_read()
+        .read_only()
+        .run(|conn| write_archive(conn, &mut out, include_embeddings))?;
+
+    out.finish()?.flush()?;
+    info!("Exported archive with {}", summary);
+    Ok(summary)
+}
+
+fn write_archive<W: Write>(
+    conn: &mut PgConnection,
+    out: &mut W,
+    include_embeddings: bool,
+) -> Result<ArchiveSummary, ArchiveError> {
+    let mut summary = ArchiveSummary::default();
+
+    write_record(
+        out,
+        &ArchiveRecord::Header(ArchiveHeader {
+            format: ARCHIVE_FORMAT.to_string(),
+            version: ARCHIVE_VERSION,
+            exported_at: Utc::now(),
+            includes_embeddings: include_embeddings,
+        }),
+    )?;
+
+    for mut collection in collections::table
+        .order(collections::created_at)
+        .load::<Collection>(conn)?
+    {
+        if !include_embeddings {
+            collection.paragraph_description_embedding = None;
+            collection.bullet_points_embedding = None;
+            collection.keywords_embedding = None;
+            collection.metadata_embedding_model = None;
+        }
+        write_record(out, &ArchiveRecord::Collection(collection))?;
+        summary.collections += 1;
+    }
+
+    let mut last_id: Option<Uuid> = None;
+    loop {
+        let mut query = articles::table
+            .order(articles::id)
+            .limit(EXPORT_BATCH_SIZE)
+            .into_boxed();
+        if let Some(last_id) = last_id {
+            query = query.filter(articles::id.gt(last_id));
+        }
+        let batch = query.load::<Article>(conn)?;
+        let Some(last) = batch.last() else {
+            break;
+        };
+        last_id = Some(last.id);
+
+        let mut chunks: HashMap<Uuid, Vec<ChunkRecord>> = HashMap::new();
+        if include_embeddings {
+            let rows: Vec<(ArticleChunk, Option<Vector>, Option<String>)> = article_chunks::table
+                .left_join(
+                    embeddings::table
+                        .on(embeddings::id.nullable().eq(article_chunks::embedding_id)),
+                )
+                .inner_join(embedding_generations::table)
+                .filter(embedding_generations::status.eq(GenerationStatus::Active.as_str()))
+                .filter(article_chunks::article_id.eq_any(batch.iter().map(|a| a.id)))
+                .select((
+                    article_chunks::all_columns,
+                    embeddings::embedding_vector.nullable(),
+                    embeddings::model.nullable(),
+                ))
+                .order((article_chunks::article_id, article_chunks::id))
+                .load(conn)?;
+            for (chunk, embedding, embedding_model) in rows {
+                chunks
+                    .entry(chunk.article_id)
+                    .or_default()
+                    .push(ChunkRecord {
+                        article_id: chunk.article_id,
+                        content: chunk.content,
+                        is_title: chunk.is_title,
+                        embedding,
+                        embedding_model,
+                        heading_path: chunk.heading_path,
+                        start_offset: chunk.start_offset,
+                        end_offset: chunk.end_offset,
+                    });
+            }
+        }
+
+        for mut article in batch {
+            let article_chunks = chunks.remove(&article.id).unwrap_or_default();
+            if !include_embeddings {
+                article.paragraph_description_embedding = None;
+                article.bullet_points_embedding = None;
+                article.keywords_embedding = None;
+                article.metadata_embedding_model = None;
+            }
+            write_record(out, &ArchiveRecord::Article(article))?;
+            summary.articles += 1;
+            for chunk in article_chunks {
+                write_record(out, &ArchiveRecord::Chunk(chunk))?;
+                summary.chunks += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Imports an archive written by `export_archive`, gzip-compressed or not,
+/// in a single transaction. Collections and articles are matched by their
+/// source ID and else by `id`, so importing the same archive twice leaves the
+/// database unchanged.
+///
+/// Archives without embeddings keep an article's existing vectors and chunks
+/// while its content and metadata match the archive, and drop them otherwise
+/// so the next embedding run regenerates them.
+pub fn import_archive<R: Read>(
+    conn: &mut PgConnection,
+    reader: R,
+) -> Result<ArchiveSummary, ArchiveError> {
+    let lines = archive_reader(reader)?;
+
+    conn.transaction(|conn| {
+        let mut importer = Importer::default();
+        for (index, line) in lines.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            importer.import(conn, index + 1, parse_record(index + 1, &line)?)?;
+        }
+        if importer.header.is_none() {
+            return Err(ArchiveError::MissingHeader);
+        }
+        info!("Imported archive with {}", importer.summary);
+        Ok(importer.summary)
+    })
+}
+
+/// Wraps `reader` in a gzip decoder when it starts with the gzip magic bytes.
+pub fn archive_reader<'a, R: Read + 'a>(reader: R) -> std::io::Result<Box<dyn BufRead + 'a>> {
+    let mut reader = BufReader::new(reader);
+    let is_gzip = reader.fill_buf()?.starts_with(&[0x1f, 0x8b]);
+    Ok(if is_gzip {
+        Box::new(BufReader::new(GzDecoder::new(reader)))
+    } else {
+        Box::new(reader)
+    })
+}
+
+/// Parses the record on the 1-based line `line` of an archive.
+pub fn parse_record(line: usize, content: &str) -> Result<ArchiveRecord, ArchiveError> {
+    serde_json::from_str(content).map_err(|source| ArchiveError::InvalidRecord { line, source })
+}
+
+#[derive(Default)]
+struct Importer {
+    header: Option<ArchiveHeader>,
+    /// Archive IDs mapped to the IDs of the stored rows.
+    collection_ids: HashMap<Uuid, Uuid>,
+    article_ids: HashMap<Uuid, Uuid>,
+    /// Stored articles whose chunks are replaced by the archive's.
+    replaced_chunks: HashSet<Uuid>,
+    /// The active generation, which imported chunks are added to.
+    generation_id: Option<i32>,
+    summary: ArchiveSummary,
+}
+
+impl Importer {
+    fn import(
+        &mut self,
+        conn: &mut PgConnection,
+        line: usize,
+        record: ArchiveRecord,
+    ) -> Result<(), ArchiveError> {
+        match (&self.header, record) {
+            (None, ArchiveRecord::Header(header)) => {
+                header.validate()?;
+                self.header = Some(header);
+                Ok(())
+            }
+            (None, _) | (Some(_), ArchiveRecord::Header(_)) => Err(ArchiveError::MissingHeader),
+            (Some(header), record) => {
+                let includes_embeddings = header.includes_embeddings;
+                match record {
+                    ArchiveRecord::Collection(collection) => {
+                        self.import_collection(conn, collection, includes_embeddings)
+                    }
+                    ArchiveRecord::Article(article) => {
+                        self.import_article(conn, article, includes_embeddings)
+                    }
+                    ArchiveRecord::Chunk(chunk) => self.import_chunk(conn, line, chunk),
+                    ArchiveRecord::Header(_) => unreachable!("headers are handled above"),
+                }
+            }
+        }
+    }
+
+    fn import_collection(
+        &mut self,
+        conn: &mut PgConnection,
+        mut collection: Collection,
+        includes_embeddings: bool,
+    ) -> Result<(), ArchiveError> {
+        let archive_id = collection.id;
+        let existing =
+            match Collection::find_by_helpscout_id(conn, &collection.helpscout_collection_id)? {
+                Some(existing) => Some(existing),
+                None => collections::table
+                    .find(collection.id)
+                    .first::<Collection>(conn)
+                    .optional()?,
+            };
+        if let Some(existing) = existing {
+            collection.id = existing.id;
+            if !includes_embeddings {
+                collection.paragraph_description_embedding =
+                    existing.paragraph_description_embedding;
+                collection.bullet_points_embedding = existing.bullet_points_embedding;
+                collection.keywords_embedding = existing.keywords_embedding;
+                collection.metadata_embedding_model = existing.metadata_embedding_model;
+            }
+        }
+
+        diesel::insert_into(collections::table)
+            .values(&collection)
+            .on_conflict(collections::id)
+            .do_update()
+            .set((
+                collections::name.eq(excluded(collections::name)),
+                collections::description.eq(excluded(collections::description)),
+                collections::slug.eq(excluded(collections::slug)),
+                collections::helpscout_collection_id
+                    .eq(excluded(collections::helpscout_collection_id)),
+                collections::created_at.eq(excluded(collections::created_at)),
+                collections::updated_at.eq(excluded(collections::updated_at)),
+                collections::paragraph_description.eq(excluded(collections::paragraph_description)),
+                collections::bullet_points.eq(excluded(collections::bullet_points)),
+                collections::keywords.eq(excluded(collections::keywords)),
+                collections::paragraph_description_embedding
+                    .eq(excluded(collections::paragraph_description_embedding)),
+                collections::bullet_points_embedding
+                    .eq(excluded(collections::bullet_points_embedding)),
+                collections::keywords_embedding.eq(excluded(collections::keywords_embedding)),
+                collections::metadata_embedding_model
+                    .eq(excluded(collections::metadata_embedding_model)),
+            ))
+            .execute(conn)?;
+
+        self.collection_ids.insert(archive_id, collection.id);
+        self.summary.collections += 1;
+        Ok(())
+    }
+
+    fn import_article(
+        &mut self,
+        conn: &mut PgConnection,
+        mut article: Article,
+        includes_embeddings: bool,
+    ) -> Result<(), ArchiveError> {
+        let archive_id = article.id;
+        if let Some(collection_id) = self.collection_ids.get(&article.collection_id) {
+            article.collection_id = *collection_id;
+        }
+
+        let existing = match &article.helpscout_article_id {
+            Some(source_id) => Article::find_by_helpscout_id(conn, source_id)?,
+            None => None,
+        };
+        let existing = match existing {
+            Some(existing) => Some(existing),
+            None => articles::table
+                .find(article.id)
+                .first::<Article>(conn)
+                .optional()?,
+        };
+
+        match &existing {
+            Some(existing) => {
+                article.id = existing.id;
+                if includes_embeddings {
+                    existing.delete_chunks(conn)?;
+                    self.replaced_chunks.insert(existing.id);
+                } else if same_content(existing, &article) {
+                    article.paragraph_description_embedding =
+                        existing.paragraph_description_embedding.clone();
+                    article.bullet_points_embedding = existing.bullet_points_embedding.clone();
+                    article.keywords_embedding = existing.keywords_embedding.clone();
+                    article.metadata_embedding_model = existing.metadata_embedding_model.clone();
+                } else {
+                    existing.delete_chunks(conn)?;
+                }
+            }
+            None => {
+                self.replaced_chunks.insert(article.id);
+            }
+        }
+
+        diesel::insert_into(articles::table)
+            .values(&article)
+            .on_conflict(articles::id)
+            .do_update()
+            .set((
+                articles::collection_id.eq(excluded(articles::collection_id)),
+                articles::title.eq(excluded(articles::title)),
+                articles::slug.eq(excluded(articles::slug)),
+                articles::html_content.eq(excluded(articles::html_content)),
+                articles::markdown_content.eq(excluded(articles::markdown_content)),
+                articles::version.eq(excluded(articles::version)),
+                articles::last_edited_by.eq(excluded(articles::last_edited_by)),
+                articles::helpscout_collection_id.eq(excluded(articles::helpscout_collection_id)),
+                articles::created_at.eq(excluded(articles::created_at)),
+                articles::updated_at.eq(excluded(articles::updated_at)),
+                articles::helpscout_article_id.eq(excluded(articles::helpscout_article_id)),
+                articles::paragraph_description.eq(excluded(articles::paragraph_description)),
+                articles::bullet_points.eq(excluded(articles::bullet_points)),
+                articles::keywords.eq(excluded(articles::keywords)),
+                articles::paragraph_description_embedding
+                    .eq(excluded(articles::paragraph_description_embedding)),
+                articles::bullet_points_embedding.eq(excluded(articles::bullet_points_embedding)),
+                articles::keywords_embedding.eq(excluded(articles::keywords_embedding)),
+                articles::archived_at.eq(excluded(articles::archived_at)),
+                articles::content_hash.eq(excluded(articles::content_hash)),
+                articles::metadata_embedding_model.eq(excluded(articles::metadata_embedding_model)),
+            ))
+            .execute(conn)?;
+
+        self.article_ids.insert(archive_id, article.id);
+        self.summary.articles += 1;
+        Ok(())
+    }
+
+    fn import_chunk(
+        &mut self,
+        conn: &mut PgConnection,
+        line: usize,
+        chunk: ChunkRecord,
+    ) -> Result<(), ArchiveError> {
+        let article_id =
+            *self
+                .article_ids
+                .get(&chunk.article_id)
+                .ok_or(ArchiveError::OrphanChunk {
+                    line,
+                    article_id: chunk.article_id,
+                })?;
+        // Chunks only come with embeddings; without them they'd be regenerated
+        if !self.replaced_chunks.contains(&article_id) {
+            return Ok(());
+        }
+
+        let generation_id = match self.generation_id {
+            Some(generation_id) => generation_id,
+            None => *self
+                .generation_id
+                .insert(EmbeddingGeneration::active(conn)?.id),
+        };
+
+        let embedding_id = match chunk.embedding {
+            Some(embedding_vector) => Some(
+                Embedding::new(
+                    article_id,
+                    chunk
+                        .embedding_model
+                        .as_deref()
+                        .unwrap_or(LEGACY_EMBEDDING_MODEL),
+                    embedding_vector.to_vec(),
+                )
+                .store(conn)?
+                .id,
+            ),
+            None => None,
+        };
+        ArticleChunk {
+            id: Uuid::new_v4(),
+            article_id,
+            content: chunk.content,
+            is_title: chunk.is_title,
+            embedding_id,
+            generation_id,
+            heading_path: chunk.heading_path,
+            start_offset: chunk.start_offset,
+            end_offset: chunk.end_offset,
+        }
+        .store(conn)?;
+
+        self.summary.chunks += 1;
+        Ok(())
+    }
+}
+
+fn same_content(existing: &Article, imported: &Article) -> bool {
+    existing.title == imported.title
+        && existing.markdown_content == imported.markdown_content
+        && existing.paragraph_description == imported.paragraph_description
+        && existing.bullet_points == imported.bullet_points
+        && existing.keywords == imported.keywords
+}
+
+fn write_record<W: Write>(out: &mut W, record: &ArchiveRecord) -> Result<(), ArchiveError> {
+    serde_json::to_writer(&mut *out, record).map_err(std::io::Error::from)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
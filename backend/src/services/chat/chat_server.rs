@@ -1,3 +1,4 @@
+use crate::config;
 use crate::models::Article;
 use crate::services::ai::AIService;
 use crate::services::EmbeddingService;
@@ -35,14 +36,16 @@ pub struct ClientMessage {
 pub struct ChatServer {
     sessions: HashMap<SessionId, Recipient<Message>>,
     ai_service: AIService,
+    embedding_service: Arc<EmbeddingService>,
     db_pool: Arc<DbPool>,
 }
 
 impl ChatServer {
-    pub fn new(db_pool: Arc<DbPool>) -> Self {
+    pub fn new(db_pool: Arc<DbPool>, embedding_service: Arc<EmbeddingService>) -> Self {
         Self {
             sessions: HashMap::new(),
             ai_service: AIService::new(),
+            embedding_service,
             db_pool,
         }
     }
@@ -51,14 +54,16 @@ impl ChatServer {
         &mut self,
         text: String,
     ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-        let embedding_service = EmbeddingService::new();
-        let query_embedding = embedding_service.generate_embedding(&text).await?;
-        let query_embedding = Vector::from(query_embedding);
         let conn = &mut self
             .db_pool
             .get()
             .expect("couldn't get db connection from pool");
-        let relevant_articles = Article::find_relevant_articles(&query_embedding, conn).await?;
+        let model = self.embedding_service.query_model(conn)?;
+        let query_embedding = self.embedding_service.embed_query(model, &text).await?;
+        let query_embedding = Vector::from(query_embedding);
+        let relevant_articles =
+            Article::find_relevant_articles(&query_embedding, model, conn, &config::get().search)
+                .await?;
 
         let context = relevant_articles
             .iter()
@@ -1,68 +1,230 @@
 // File: src/data_processing/fetcher.rs
 
-use anyhow::Result;
-use log::{error, info};
-use reqwest;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::{error, info, warn};
+use rand::Rng;
+use reqwest::{self, header::HeaderMap, StatusCode};
 use serde_json::{from_value, Value};
-use std::{env, time::Duration};
+use std::time::Duration;
 use tokio::time::sleep;
 
+use crate::config::{self, HelpScoutConfig};
 use crate::models::{
     articles::{ArticleFull, ArticleFullResponse, ArticleRef, ArticleResponse},
     collection::{CollectionItem, CollectionResponse},
     Article, Collection,
 };
+use crate::utils::backoff_delay;
+
+use super::rate_limiter::TokenBucket;
+
+/// Timeouts, retries and the client-side rate limit, built from the
+/// `[helpscout]` config section.
+#[derive(Debug, Clone)]
+pub struct ApiClientConfig {
+    /// Whole-request timeout.
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    /// Retries after the first attempt on 429, 5xx, timeouts and connection
+    /// errors.
+    pub max_retries: u32,
+    /// Bounds of the jittered exponential backoff used when the server sends
+    /// no `Retry-After`.
+    pub retry_base_delay: Duration,
+    pub retry_max_delay: Duration,
+    /// Longest `Retry-After` honoured. The server asking for more fails the
+    /// request rather than holding a sync, and its job's lease, that long.
+    pub max_retry_after: Duration,
+    /// Token bucket sized to stay under the Help Scout Docs API quota.
+    pub requests_per_minute: u32,
+    pub burst: u32,
+}
+
+impl Default for ApiClientConfig {
+    fn default() -> Self {
+        Self::from(&HelpScoutConfig::default())
+    }
+}
+
+impl From<&HelpScoutConfig> for ApiClientConfig {
+    fn from(config: &HelpScoutConfig) -> Self {
+        Self {
+            timeout: Duration::from_millis(config.timeout_ms),
+            connect_timeout: Duration::from_millis(config.connect_timeout_ms),
+            max_retries: config.max_retries,
+            retry_base_delay: Duration::from_millis(config.retry_base_ms),
+            retry_max_delay: Duration::from_millis(config.retry_max_ms),
+            max_retry_after: Duration::from_millis(config.max_retry_after_ms),
+            requests_per_minute: config.requests_per_minute,
+            burst: config.burst,
+        }
+    }
+}
+
+impl ApiClientConfig {
+    /// Exponential backoff for the given retry (1-based), with up to half of
+    /// it taken off at random so parallel workers don't retry in lockstep.
+    pub fn retry_delay(&self, retry: u32) -> Duration {
+        let delay = backoff_delay(
+            self.retry_base_delay,
+            self.retry_max_delay,
+            retry.min(i32::MAX as u32) as i32,
+        );
+        delay.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+}
+
+/// Progress of a paginated article listing. When a page fails the listing
+/// keeps everything fetched so far, and passing it back to
+/// `ApiClient::resume_list_articles` continues from the failed page.
+#[derive(Debug, Clone)]
+pub struct ArticleListing {
+    pub helpscout_collection_id: String,
+    pub next_page: i32,
+    pub article_refs: Vec<ArticleRef>,
+    pub complete: bool,
+}
+
+impl ArticleListing {
+    pub fn new(collection: &Collection) -> Self {
+        Self {
+            helpscout_collection_id: collection.helpscout_collection_id.clone(),
+            next_page: 1,
+            article_refs: Vec::new(),
+            complete: false,
+        }
+    }
+}
 
 pub struct ApiClient {
     client: reqwest::Client,
     base_url: String,
     api_key: String,
+    config: ApiClientConfig,
+    rate_limiter: TokenBucket,
 }
 
 impl ApiClient {
+    /// Falls back to the configured `helpscout.base_url` and
+    /// `helpscout.api_key` for whichever argument is `None`.
     pub fn new(base_url: Option<String>, api_key: Option<String>) -> Result<Self> {
+        Self::from_config(base_url, api_key, &config::get().helpscout)
+    }
+
+    pub fn from_config(
+        base_url: Option<String>,
+        api_key: Option<String>,
+        config: &HelpScoutConfig,
+    ) -> Result<Self> {
+        Self::with_config(
+            base_url.or_else(|| config.base_url.clone()),
+            api_key.or_else(|| config.api_key.clone()),
+            config.into(),
+        )
+    }
+
+    pub fn with_config(
+        base_url: Option<String>,
+        api_key: Option<String>,
+        config: ApiClientConfig,
+    ) -> Result<Self> {
         let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(1))
+            .timeout(config.timeout)
+            .connect_timeout(config.connect_timeout)
             .build()?;
-        let api_key = api_key.unwrap_or(env::var("API_KEY").expect("API_KEY must be set"));
-        let base_url =
-            base_url.unwrap_or(env::var("API_BASE_URL").expect("API_BASE_URL must be set"));
+        let api_key = api_key
+            .or_else(|| config::get().helpscout.api_key.clone())
+            .context("helpscout.api_key (API_KEY) must be set")?;
+        let base_url = base_url
+            .or_else(|| config::get().helpscout.base_url.clone())
+            .context("helpscout.base_url (API_BASE_URL) must be set")?;
+        let rate_limiter = TokenBucket::per_minute(config.requests_per_minute, config.burst)?;
         Ok(Self {
             client,
             base_url,
             api_key,
+            config,
+            rate_limiter,
         })
     }
 
+    pub fn config(&self) -> &ApiClientConfig {
+        &self.config
+    }
+
+    /// Sends a GET, retrying rate-limited (429), server (5xx) and transport
+    /// failures. A `Retry-After` header takes precedence over the backoff and
+    /// holds back every request sharing this client; one longer than
+    /// `max_retry_after` fails the request.
     async fn get(&self, endpoint: &str) -> Result<Value> {
         let url = format!("{}{}", self.base_url, endpoint);
+        let mut retries = 0;
 
-        let response = self
-            .client
-            .get(&url)
-            .basic_auth(self.api_key.clone(), Some("DUMMY_PASSWORD"))
-            .send()
-            .await
-            .map_err(|e| {
-                error!("Failed to send request: {:?}", e);
-                e
-            })?;
+        loop {
+            self.rate_limiter.acquire().await;
+            let sent = self
+                .client
+                .get(&url)
+                .basic_auth(self.api_key.clone(), Some("DUMMY_PASSWORD"))
+                .send()
+                .await;
+
+            let (failure, retry_after) = match sent {
+                Ok(response) => {
+                    let status = response.status();
+                    let retry_after = parse_retry_after(response.headers(), Utc::now());
+                    let body = response.text().await.map_err(|e| {
+                        error!("Failed to get response body: {:?}", e);
+                        e
+                    })?;
 
-        let status = response.status();
-        let body = response.text().await.map_err(|e| {
-            error!("Failed to get response body: {:?}", e);
-            e
-        })?;
+                    if status.is_success() {
+                        return serde_json::from_str(&body).map_err(|e| {
+                            error!("Failed to parse JSON: {:?}", e);
+                            e.into()
+                        });
+                    }
+                    if !is_retryable(status) || retries >= self.config.max_retries {
+                        error!("API request failed. Status: {}, Body: {}", status, body);
+                        return Err(anyhow::anyhow!("API request failed: {}", status));
+                    }
+                    if let Some(delay) = retry_after.filter(|d| *d > self.config.max_retry_after) {
+                        error!(
+                            "API request failed. Status: {}, Retry-After {:?} exceeds {:?}",
+                            status, delay, self.config.max_retry_after
+                        );
+                        return Err(anyhow::anyhow!(
+                            "API request failed: {}, retry after {:?}",
+                            status,
+                            delay
+                        ));
+                    }
+                    (status.to_string(), retry_after)
+                }
+                Err(e) => {
+                    if !(e.is_timeout() || e.is_connect()) || retries >= self.config.max_retries {
+                        error!("Failed to send request: {:?}", e);
+                        return Err(e.into());
+                    }
+                    (e.to_string(), None)
+                }
+            };
 
-        if !status.is_success() {
-            error!("API request failed. Status: {}, Body: {}", status, body);
-            return Err(anyhow::anyhow!("API request failed: {}", status));
+            retries += 1;
+            let delay = match retry_after {
+                Some(delay) => {
+                    self.rate_limiter.block_for(delay).await;
+                    delay
+                }
+                None => self.config.retry_delay(retries),
+            };
+            warn!(
+                "Request to {} failed ({}), retry {}/{} in {:?}",
+                endpoint, failure, retries, self.config.max_retries, delay
+            );
+            sleep(delay).await;
         }
-
-        serde_json::from_str(&body).map_err(|e| {
-            error!("Failed to parse JSON: {:?}", e);
-            e.into()
-        })
     }
 
     pub async fn get_list_collections(&self) -> Result<Vec<Collection>> {
@@ -92,55 +254,51 @@ impl ApiClient {
     }
 
     pub async fn get_list_articles(&self, collection: &Collection) -> Result<Vec<ArticleRef>> {
-        let helpscout_collection_id = &collection.helpscout_collection_id;
-        let mut articles_refs = Vec::new();
-        let mut page = 1;
+        let mut listing = ArticleListing::new(collection);
+        self.resume_list_articles(&mut listing).await?;
+        Ok(listing.article_refs)
+    }
 
-        loop {
+    /// Fetches the remaining pages of `listing`. On error the listing stays
+    /// at the page that failed, so calling this again picks up from there.
+    pub async fn resume_list_articles(&self, listing: &mut ArticleListing) -> Result<()> {
+        while !listing.complete {
+            let page = listing.next_page;
             info!("Fetching articles from page: {}", page);
-            let endpoint = format!(
-                "/v1/collections/{}/articles?page={}",
-                helpscout_collection_id, page
-            );
+            // Help Scout serves the first page when none is given
+            let endpoint = match page {
+                1 => format!(
+                    "/v1/collections/{}/articles",
+                    listing.helpscout_collection_id
+                ),
+                _ => format!(
+                    "/v1/collections/{}/articles?page={}",
+                    listing.helpscout_collection_id, page
+                ),
+            };
             info!("Sending request to endpoint: {}", endpoint);
             let data = self.get(&endpoint).await?;
             info!("Received response for page: {}", page);
-            // info!("API Response | Get List Articles: {:?}", data);
-            info!("Attempting to deserialize API response");
-            let api_response: ArticleResponse = match from_value(data.clone()) {
-                Ok(response) => {
-                    info!("Successfully deserialized API response");
-                    response
-                }
-                Err(e) => {
-                    error!("Failed to deserialize API response: {:?}", e);
-                    // error!("Raw API response: {:?}", data);
-                    return Err(anyhow::anyhow!("Failed to deserialize API response: {}", e));
-                }
-            };
+            let api_response: ArticleResponse = from_value(data).map_err(|e| {
+                error!("Failed to deserialize API response: {:?}", e);
+                anyhow::anyhow!("Failed to deserialize API response: {}", e)
+            })?;
             let article_data = api_response.articles;
             info!(
-                "Total pages: {}, Current page: {}",
-                article_data.pages, page
-            );
-
-            info!(
-                "Found {} articles on page {} for collection: {}",
+                "Found {} articles on page {}/{} for collection: {}",
                 article_data.items.len(),
                 page,
-                collection.slug
+                article_data.pages,
+                listing.helpscout_collection_id
             );
-            articles_refs.extend(article_data.items);
 
-            if page >= article_data.pages {
-                break;
-            }
-            page += 1;
-            sleep(Duration::from_millis(1000)).await;
+            listing.article_refs.extend(article_data.items);
+            listing.next_page = page + 1;
+            listing.complete = page >= article_data.pages;
         }
 
-        info!("Total articles fetched: {}", articles_refs.len());
-        Ok(articles_refs)
+        info!("Total articles fetched: {}", listing.article_refs.len());
+        Ok(())
     }
 
     pub async fn get_article(&self, id: &str, collection: &Collection) -> Result<Article> {
@@ -171,7 +329,7 @@ fn parse_collection(collection: &CollectionItem) -> Result<Collection> {
 }
 
 fn parse_article(helpscout_article: &ArticleFull, collection: &Collection) -> Result<Article> {
-    let article = Article::new(
+    let mut article = Article::new(
         collection.id,
         helpscout_article.collection_id.clone(),
         Some(helpscout_article.id.clone()),
@@ -179,5 +337,30 @@ fn parse_article(helpscout_article: &ArticleFull, collection: &Collection) -> Re
         helpscout_article.slug.clone(),
         Some(helpscout_article.text.clone()),
     );
+    // Keep Help Scout's modification time so incremental syncs can compare it
+    // against `ArticleRef::updated_at` / `last_published_at`.
+    if let Some(modified_at) = helpscout_article.last_modified_at() {
+        article.updated_at = modified_at;
+    }
     Ok(article)
 }
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Reads `Retry-After` as either delay-seconds or an HTTP date.
+pub fn parse_retry_after(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = DateTime::parse_from_rfc2822(value)
+        .ok()?
+        .with_timezone(&Utc);
+    Some((at - now).to_std().unwrap_or_default())
+}
@@ -1,40 +1,319 @@
+use super::source::DocumentSource;
 use super::DataProcessor;
-use log::{info, error};
-use crate::{models::{Article, ArticleRef, Collection}, services::data_processor::html_to_markdown};
+use log::{info, error, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use crate::{
+    errors::SyncError,
+    models::{Article, ArticleRef, ArticleRemoval, Collection, RemovalReason},
+    services::data_processor::html_to_markdown,
+};
 
 use anyhow::{Context, Result};
 
+/// How `sync_article` decides whether an article needs to be fetched again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncMode {
+    /// Only fetch articles whose Help Scout timestamps are newer than the stored copy.
+    #[default]
+    Incremental,
+    /// Fetch every article, still upserting by `helpscout_article_id`.
+    Full,
+}
+
+/// Stored as the result of `SyncArticle` and `ArchiveArticle` jobs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncOutcome {
+    Created,
+    Updated,
+    Unchanged,
+    /// A stored article was archived because it is no longer published.
+    Archived,
+    /// An unpublished article that was never stored.
+    Skipped,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub archived: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+impl SyncReport {
+    pub fn record(&mut self, outcome: SyncOutcome) {
+        match outcome {
+            SyncOutcome::Created => self.created += 1,
+            SyncOutcome::Updated => self.updated += 1,
+            SyncOutcome::Unchanged => self.unchanged += 1,
+            SyncOutcome::Archived => self.archived += 1,
+            SyncOutcome::Skipped => self.skipped += 1,
+        }
+    }
+
+    pub fn merge(&mut self, other: &SyncReport) {
+        self.created += other.created;
+        self.updated += other.updated;
+        self.unchanged += other.unchanged;
+        self.archived += other.archived;
+        self.skipped += other.skipped;
+        self.failed += other.failed;
+    }
+}
+
+impl fmt::Display for SyncReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "created: {}, updated: {}, unchanged: {}, archived: {}, skipped: {}, failed: {}",
+            self.created, self.updated, self.unchanged, self.archived, self.skipped, self.failed
+        )
+    }
+}
+
+/// The stored articles that `source` handed out but no longer lists. Articles
+/// of other sources are never returned, see `DocumentSource::owns_id`.
+pub fn unlisted_articles(
+    source: &dyn DocumentSource,
+    stored_articles: Vec<Article>,
+    listed_article_ids: &HashSet<String>,
+) -> Vec<Article> {
+    stored_articles
+        .into_iter()
+        .filter(|article| match &article.helpscout_article_id {
+            Some(source_id) => source.owns_id(source_id) && !listed_article_ids.contains(source_id),
+            None => false,
+        })
+        .collect()
+}
+
 impl DataProcessor {
-    pub async fn prepare_sync_collection(&self, collection: &Collection) -> Result<(), anyhow::Error> {
-        info!("Preparing to sync collection: ID:{:?}, Slug: {:?}", collection.id, collection.slug);
-        self.sync_collection(collection).await?;
+    /// Syncs every collection of the document source, then archives stored articles that
+    /// disappeared upstream. Reconciliation only runs once every collection was
+    /// listed successfully, so a partial listing never archives live articles.
+    pub async fn sync_all(&self, mode: SyncMode) -> Result<SyncReport, SyncError> {
+        info!("Starting {:?} sync of all collections", mode);
+        let listing = self.list_all_articles().await?;
+
+        let mut report = SyncReport::default();
+        let mut listed_article_ids = HashSet::new();
+        for (collection, article_refs) in &listing {
+            listed_article_ids.extend(article_refs.iter().map(|article_ref| article_ref.id.clone()));
+            report.merge(&self.sync_articles(collection, article_refs, mode).await);
+        }
+
+        let removals = self.reconcile_articles(&listed_article_ids).await?;
+        report.archived += removals.len();
+
+        info!("Finished sync of all collections ({})", report);
+        Ok(report)
+    }
+
+    /// Stores every collection of the document source and lists its articles. Fails if any
+    /// collection cannot be listed, since callers reconcile against the result.
+    pub async fn list_all_articles(&self) -> Result<Vec<(Collection, Vec<ArticleRef>)>, SyncError> {
+        let collections = self
+            .source
+            .list_collections()
+            .await
+            .map_err(SyncError::CollectionFetchError)?;
+
+        let mut listing = Vec::with_capacity(collections.len());
+        for collection in collections {
+            let collection = self.sync_collection(&collection).await.map_err(|e| {
+                SyncError::JobPreparationError {
+                    collection_id: collection.helpscout_collection_id.clone(),
+                    error: e,
+                }
+            })?;
+            let article_refs = self
+                .source
+                .list_article_refs(&collection)
+                .await
+                .map_err(|e| SyncError::ArticleFetchError {
+                    collection_id: collection.helpscout_collection_id.clone(),
+                    error: e,
+                })?;
+            listing.push((collection, article_refs));
+        }
+        Ok(listing)
+    }
+
+    pub async fn prepare_sync_collection(&self, collection: &Collection, mode: SyncMode) -> Result<SyncReport, anyhow::Error> {
+        info!("Preparing to sync collection: ID:{:?}, Slug: {:?}, Mode: {:?}", collection.id, collection.slug, mode);
+        let collection = self.sync_collection(collection).await?;
+
+        let article_refs = self.source.list_article_refs(&collection).await?;
+        let report = self.sync_articles(&collection, &article_refs, mode).await;
+
+        info!("Finished syncing collection: ID:{:?}, Slug: {:?} ({})", collection.id, collection.slug, report);
+        Ok(report)
+    }
 
-        let article_refs = self.api_client.get_list_articles(collection).await?;
+    async fn sync_articles(&self, collection: &Collection, article_refs: &[ArticleRef], mode: SyncMode) -> SyncReport {
+        let mut report = SyncReport::default();
         for article_ref in article_refs {
-            self.sync_article(&article_ref, &collection).await?;
+            match self.sync_article(article_ref, collection, mode).await {
+                Ok(outcome) => report.record(outcome),
+                Err(e) => {
+                    error!("Failed to sync article ID:{}: {}", article_ref.id, e);
+                    report.failed += 1;
+                }
+            }
         }
+        report
+    }
 
-        Ok(())
+    /// Archives every active article of the document source whose ID was not
+    /// listed by the latest sync.
+    pub async fn reconcile_articles(&self, listed_article_ids: &HashSet<String>) -> Result<Vec<ArticleRemoval>, SyncError> {
+        let mut conn = self.db_pool.get()
+            .context("Failed to get DB connection")?;
+        let stored_articles = Article::load_active_synced(&mut conn)
+            .context("Failed to load synced articles")?;
+
+        let mut removals = Vec::new();
+        for article in unlisted_articles(self.source.as_ref(), stored_articles, listed_article_ids) {
+            warn!("Article ID:{:?}, Title: {:?} was deleted upstream, archiving", article.id, article.title);
+            let removal = article
+                .archive(&mut conn, RemovalReason::Deleted)
+                .with_context(|| format!("Failed to archive article ID:{}", article.id))?;
+            removals.push(removal);
+        }
+
+        info!("Reconciliation archived {} deleted articles", removals.len());
+        Ok(removals)
     }
 
-    pub async fn sync_collection(&self, collection: &Collection) -> Result<()> {
+    /// Stores the collection, returning the persisted row. Collections that were
+    /// synced before keep their original `id`.
+    pub async fn sync_collection(&self, collection: &Collection) -> Result<Collection> {
         info!("Storing collection: ID:{:?}, Slug: {:?}", collection.id, collection.slug);
         let mut conn = self.db_pool.get()
             .context("Failed to get DB connection")?;
 
-        collection.store(&mut conn)
+        let stored = collection.upsert(&mut conn)
             .with_context(|| format!("Failed to store collection: ID:{:?}, Slug:{:?}", collection.id, collection.slug))?;
 
-        info!("Successfully stored collection: ID:{:?}, Slug:{:?}", collection.id, collection.slug);
-        Ok(())
+        info!("Successfully stored collection: ID:{:?}, Slug:{:?}", stored.id, stored.slug);
+        Ok(stored)
+    }
+
+    /// Looks up a stored collection by its Help Scout ID, fetching it from Help
+    /// Scout and storing it first if it was never synced. Used for webhook
+    /// events, which come from Help Scout whatever `source.kind` is.
+    pub async fn resolve_helpscout_collection(&self, helpscout_collection_id: &str) -> Result<Collection> {
+        let stored = Collection::find_by_helpscout_id(
+            &mut *self.db_pool.get().context("Failed to get DB connection")?,
+            helpscout_collection_id,
+        )
+        .with_context(|| format!("Failed to look up collection ID:{}", helpscout_collection_id))?;
+
+        match stored {
+            Some(collection) => Ok(collection),
+            None => {
+                info!("Collection ID:{} not stored yet, fetching it", helpscout_collection_id);
+                let collection = self
+                    .helpscout_source()?
+                    .fetch_collection(helpscout_collection_id)
+                    .await?;
+                self.sync_collection(&collection).await
+            }
+        }
+    }
+
+    /// Archives a single article that was deleted upstream.
+    pub async fn archive_deleted_article(&self, helpscout_article_id: &str) -> Result<SyncOutcome> {
+        let mut conn = self.db_pool.get().context("Failed to get DB connection")?;
+        let existing = Article::find_by_helpscout_id(&mut conn, helpscout_article_id)
+            .with_context(|| format!("Failed to look up article ID:{}", helpscout_article_id))?;
+
+        match existing {
+            Some(article) if !article.is_archived() => {
+                warn!("Article ID:{} was deleted upstream, archiving", helpscout_article_id);
+                article
+                    .archive(&mut conn, RemovalReason::Deleted)
+                    .with_context(|| format!("Failed to archive article ID:{}", helpscout_article_id))?;
+                Ok(SyncOutcome::Archived)
+            }
+            Some(_) => Ok(SyncOutcome::Unchanged),
+            None => {
+                info!("Deleted article ID:{} was never stored, skipping", helpscout_article_id);
+                Ok(SyncOutcome::Skipped)
+            }
+        }
     }
 
     pub async fn sync_article(
         &self,
         article_ref: &ArticleRef,
         collection: &Collection,
-    ) -> Result<()> {
-        let article = match self.api_client.get_article(&article_ref.id.to_string(), collection).await {
+        mode: SyncMode,
+    ) -> Result<SyncOutcome> {
+        self.sync_article_from(self.source.as_ref(), article_ref, collection, mode).await
+    }
+
+    /// Syncs an article named by a Help Scout webhook. It is always fetched
+    /// from Help Scout, skipping the timestamp check since the event says it
+    /// changed.
+    pub async fn sync_helpscout_article(
+        &self,
+        article_ref: &ArticleRef,
+        collection: &Collection,
+    ) -> Result<SyncOutcome> {
+        let source = self.helpscout_source()?;
+        self.sync_article_from(source.as_ref(), article_ref, collection, SyncMode::Full).await
+    }
+
+    async fn sync_article_from(
+        &self,
+        source: &dyn DocumentSource,
+        article_ref: &ArticleRef,
+        collection: &Collection,
+        mode: SyncMode,
+    ) -> Result<SyncOutcome> {
+        let existing = Article::find_by_helpscout_id(
+            &mut *self.db_pool.get().context("Failed to get DB connection")?,
+            &article_ref.id,
+        )
+        .with_context(|| format!("Failed to look up article ID:{}", article_ref.id))?;
+
+        if !article_ref.is_published() {
+            return match existing {
+                Some(existing) if !existing.is_archived() => {
+                    warn!("Article ID:{} is now {:?}, archiving", article_ref.id, article_ref.status);
+                    existing
+                        .archive(
+                            &mut *self.db_pool.get().context("Failed to get DB connection")?,
+                            RemovalReason::Unpublished,
+                        )
+                        .with_context(|| format!("Failed to archive article ID:{}", article_ref.id))?;
+                    Ok(SyncOutcome::Archived)
+                }
+                Some(_) => Ok(SyncOutcome::Unchanged),
+                None => {
+                    info!("Article ID:{} is {:?}, skipping", article_ref.id, article_ref.status);
+                    Ok(SyncOutcome::Skipped)
+                }
+            };
+        }
+
+        // Archived articles that are published again are always re-fetched
+        // since their chunks were removed when they were archived.
+        if let Some(existing) = existing.as_ref().filter(|existing| !existing.is_archived()) {
+            if mode == SyncMode::Incremental && !article_ref.is_newer_than(existing.updated_at) {
+                info!("Article ID:{} unchanged since {}, skipping", article_ref.id, existing.updated_at);
+                return Ok(SyncOutcome::Unchanged);
+            }
+        }
+
+        let article = match source.fetch_article(article_ref, collection).await {
             Ok(article) => article,
             Err(e) => {
                 error!("Failed to fetch article ID:{}: {}", article_ref.id, e);
@@ -42,25 +321,58 @@ impl DataProcessor {
             }
         };
 
+        if let Some(existing) = existing.as_ref().filter(|existing| !existing.is_archived()) {
+            let same_content = match &article.html_content {
+                Some(_) => existing.html_content == article.html_content,
+                None => existing.markdown_content == article.markdown_content,
+            };
+            if existing.title == article.title && same_content {
+                // Record the new timestamp so the next incremental sync does
+                // not fetch the article again
+                info!("Article ID:{} content unchanged, updating timestamp only", article_ref.id);
+                existing
+                    .update_sync_metadata(
+                        &mut *self.db_pool.get().context("Failed to get DB connection")?,
+                        &article,
+                    )
+                    .with_context(|| format!("Failed to update article ID:{}", article_ref.id))?;
+                return Ok(SyncOutcome::Unchanged);
+            }
+        }
+
         info!(
-            "Processing article: ID:{:?}, Title: {:?}, Collection ID: {:?}, Helpscout Collection ID: {:?}", 
-            article.id, 
-            article.title, 
-            collection.id, 
+            "Processing article: ID:{:?}, Title: {:?}, Collection ID: {:?}, Helpscout Collection ID: {:?}",
+            article.id,
+            article.title,
+            collection.id,
             collection.helpscout_collection_id
         );
 
-        if let Err(e) = self.store_article(&article).await {
-            error!("Failed to store article ID:{}: {}", article.id, e);
-            return Err(anyhow::anyhow!("Failed to store article: {}", e));
+        let article = match self.store_article(&article).await {
+            Ok(article) => article,
+            Err(e) => {
+                error!("Failed to store article ID:{}: {}", article.id, e);
+                return Err(anyhow::anyhow!("Failed to store article: {}", e));
+            }
+        };
+
+        if existing.is_some() {
+            // Chunks and their embeddings describe the old content; drop them so
+            // the next embedding run regenerates them.
+            article
+                .delete_chunks(&mut *self.db_pool.get().context("Failed to get DB connection")?)
+                .with_context(|| format!("Failed to remove stale chunks for article ID:{}", article.id))?;
         }
 
-        if let Err(e) = self.convert_html_to_markdown(&article).await {
-            error!("Failed to convert HTML to Markdown for article ID:{}: {}", article.id, e);
-            return Err(anyhow::anyhow!("Failed to convert HTML to Markdown: {}", e));
+        // Sources that provide Markdown directly have nothing to convert
+        if article.html_content.is_some() {
+            if let Err(e) = self.convert_html_to_markdown(&article).await {
+                error!("Failed to convert HTML to Markdown for article ID:{}: {}", article.id, e);
+                return Err(anyhow::anyhow!("Failed to convert HTML to Markdown: {}", e));
+            }
         }
 
-        Ok(())
+        Ok(if existing.is_some() { SyncOutcome::Updated } else { SyncOutcome::Created })
     }
 
     pub async fn store_article(&self, article: &Article) -> Result<Article> {
@@ -71,7 +383,7 @@ impl DataProcessor {
             article.collection_id,
             article.helpscout_collection_id
         );
-        let article = article.store(&mut self.db_pool.get().expect("Failed to get DB connection"))?;
+        let article = article.upsert(&mut *self.db_pool.get().context("Failed to get DB connection")?)?;
         Ok(article)
     }
 
@@ -88,7 +400,7 @@ impl DataProcessor {
             &mut *self.db_pool.get().context("Failed to get DB connection")?,
             markdown,
         ).context(format!("Failed to update markdown content for article ID:{}", article.id))?;
-        
+
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,205 @@
+use anyhow::{Context, Result};
+use log::{error, info, warn};
+use std::{collections::HashSet, path::Path};
+
+use super::source::front_matter::parse_front_matter;
+use super::source::local_dir::{FileFormat, LocalDirSource};
+use super::source::{slugify, DocumentSource};
+use super::{DataProcessor, SyncOutcome, SyncReport};
+use crate::job::{JobControl, JobProgress};
+use crate::models::{Article, ArticleRef, Collection, RemovalReason, MARKDOWN_IMPORT_PREFIX};
+use crate::utils::content_hash;
+
+/// A Markdown file listed by the importer.
+#[derive(Debug, Clone)]
+pub struct MarkdownDocument {
+    /// The article ID handed out by the `LocalDirSource`, stored as the
+    /// article's `helpscout_article_id`.
+    pub source_id: String,
+    /// Path relative to the import root, with `/` separators.
+    pub relative_path: String,
+    pub title: String,
+    pub slug: String,
+    /// `None` when the front-matter doesn't set keywords.
+    pub keywords: Option<Vec<String>>,
+    /// The Markdown without its front-matter.
+    pub body: String,
+    /// SHA-256 of the whole file, front-matter included.
+    pub content_hash: String,
+}
+
+impl MarkdownDocument {
+    /// Builds the document from a ref listed under `source_prefix` and the
+    /// file's content. The title is the one the source already read off the
+    /// front-matter, first heading or file name.
+    pub fn parse(source_prefix: &str, article_ref: &ArticleRef, content: &str) -> Self {
+        let (front_matter, body) = parse_front_matter(content);
+        let relative_path = article_ref
+            .id
+            .strip_prefix(source_prefix)
+            .unwrap_or(&article_ref.id);
+        let file_name = relative_path
+            .rsplit_once('/')
+            .map_or(relative_path, |(_, file_name)| file_name);
+        let stem = file_name
+            .rsplit_once('.')
+            .map_or(file_name, |(stem, _)| stem);
+
+        Self {
+            source_id: article_ref.id.clone(),
+            relative_path: relative_path.to_string(),
+            title: article_ref.name.clone(),
+            slug: front_matter.slug.unwrap_or_else(|| slugify(stem)),
+            keywords: front_matter.keywords,
+            body: body.to_string(),
+            content_hash: content_hash(content),
+        }
+    }
+}
+
+impl DataProcessor {
+    /// Imports a directory of Markdown files through a `LocalDirSource`:
+    /// every top-level folder becomes a collection and every file an article
+    /// whose Markdown is stored as is. Files whose content hash is unchanged
+    /// are skipped, changed ones lose their chunks so they are re-embedded,
+    /// and articles whose file is gone are archived. Imported articles are
+    /// IDed by the canonical path of the directory, so two trees that share
+    /// a folder name never archive each other's articles.
+    pub async fn import_markdown_dir(
+        &self,
+        root: &Path,
+        control: &JobControl,
+        progress: &JobProgress,
+    ) -> Result<SyncReport> {
+        let root = root
+            .canonicalize()
+            .with_context(|| format!("Import directory {:?} not found", root))?;
+        let source_prefix = format!("{}{}/", MARKDOWN_IMPORT_PREFIX, root.display());
+        let source = LocalDirSource::with_id_prefix(root.clone(), &source_prefix)?;
+        info!("Importing Markdown from {:?} as {}", root, source_prefix);
+
+        let mut collections = Vec::new();
+        for collection in source.list_collections().await? {
+            control.checkpoint().await?;
+            let mut documents = Vec::new();
+            for article_ref in source.list_article_refs(&collection).await? {
+                let (format, content) = source.read_document(&article_ref.id)?;
+                if format == FileFormat::Markdown {
+                    documents.push(MarkdownDocument::parse(
+                        &source_prefix,
+                        &article_ref,
+                        &content,
+                    ));
+                }
+            }
+            if !documents.is_empty() {
+                collections.push((collection, documents));
+            }
+        }
+        let total: usize = collections
+            .iter()
+            .map(|(_, documents)| documents.len())
+            .sum();
+        // An empty listing more likely means a wrong or unmounted path than
+        // a tree whose files were all deleted.
+        anyhow::ensure!(
+            total > 0,
+            "No Markdown files found in {:?}, refusing to archive its articles",
+            root
+        );
+        progress.set_total(total);
+
+        let mut report = SyncReport::default();
+        let mut imported_ids = HashSet::new();
+        for (collection, documents) in &collections {
+            control.checkpoint().await?;
+            let collection = self.sync_collection(collection).await?;
+
+            for document in documents {
+                control.checkpoint().await?;
+                progress.item_started(&document.relative_path);
+                match self.import_markdown_document(&collection, document).await {
+                    Ok(outcome) => {
+                        progress.item_succeeded(&document.relative_path);
+                        report.record(outcome);
+                    }
+                    Err(e) => {
+                        error!("Failed to import {}: {}", document.relative_path, e);
+                        progress.item_failed(&document.relative_path, &e);
+                        report.failed += 1;
+                    }
+                }
+                imported_ids.insert(document.source_id.as_str());
+            }
+        }
+
+        let mut conn = self.db_pool.get().context("Failed to get DB connection")?;
+        for article in Article::load_active_with_source_prefix(&mut conn, &source_prefix)? {
+            let still_present = article
+                .helpscout_article_id
+                .as_deref()
+                .is_some_and(|source_id| imported_ids.contains(source_id));
+            if !still_present {
+                warn!(
+                    "Imported article {:?} no longer has a file, archiving",
+                    article.title
+                );
+                article
+                    .archive(&mut conn, RemovalReason::Deleted)
+                    .with_context(|| format!("Failed to archive article ID:{}", article.id))?;
+                report.archived += 1;
+            }
+        }
+
+        info!("Finished Markdown import of {:?} ({})", root, report);
+        Ok(report)
+    }
+
+    async fn import_markdown_document(
+        &self,
+        collection: &Collection,
+        document: &MarkdownDocument,
+    ) -> Result<SyncOutcome> {
+        let source_id = document.source_id.as_str();
+        let mut conn = self.db_pool.get().context("Failed to get DB connection")?;
+        let existing = Article::find_by_helpscout_id(&mut conn, source_id)
+            .with_context(|| format!("Failed to look up article {}", source_id))?;
+
+        if let Some(existing) = existing.as_ref().filter(|existing| !existing.is_archived()) {
+            if existing.content_hash.as_deref() == Some(document.content_hash.as_str()) {
+                return Ok(SyncOutcome::Unchanged);
+            }
+        }
+
+        let mut article = Article::new(
+            collection.id,
+            collection.helpscout_collection_id.clone(),
+            Some(source_id.to_string()),
+            document.title.clone(),
+            document.slug.clone(),
+            None,
+        );
+        article.markdown_content = Some(document.body.clone());
+        article.content_hash = Some(document.content_hash.clone());
+        let article = article
+            .upsert(&mut conn)
+            .with_context(|| format!("Failed to store article {}", source_id))?;
+
+        // Keywords the front-matter doesn't set may come from metadata
+        // generation, so only listed ones replace them
+        if let Some(keywords) = &document.keywords {
+            article.update_keywords(&mut conn, keywords.clone())?;
+        }
+        if existing.is_some() {
+            // Stale chunks would keep the old text searchable; the next
+            // embedding run regenerates them.
+            article.delete_chunks(&mut conn)?;
+        }
+
+        Ok(if existing.is_some() {
+            SyncOutcome::Updated
+        } else {
+            SyncOutcome::Created
+        })
+    }
+}
@@ -1,44 +1,86 @@
 // File: src/data_processing/mod.rs
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use log::info;
 use pgvector::Vector;
 use std::sync::Arc;
 use uuid::Uuid;
 
+use crate::config;
 use crate::db::DbPool;
 use crate::services::{AIService, EmbeddingService};
-use api_client::ApiClient;
+use source::DocumentSource;
 
 pub mod api_client;
 pub mod convert_html;
 pub mod data_sync;
+pub mod markdown_import;
 pub mod process_article;
 pub mod process_failed_articles;
+pub mod rate_limiter;
+pub mod source;
+pub mod webhook;
 
 pub use convert_html::html_to_markdown;
+pub use data_sync::{SyncMode, SyncOutcome, SyncReport};
 
 pub struct DataProcessor {
-    pub api_client: ApiClient,
+    pub source: Arc<dyn DocumentSource>,
+    /// Handles Help Scout webhook events, see `source::helpscout_from_config`.
+    helpscout: Option<Arc<dyn DocumentSource>>,
     db_pool: Arc<DbPool>,
     ai_service: Arc<AIService>,
     embedding_service: Arc<EmbeddingService>,
 }
 
 impl DataProcessor {
+    /// Syncs from the configured source, see `source::from_config`.
     pub async fn new(db_pool: Arc<DbPool>) -> Result<Self> {
-        let api_client = ApiClient::new(None, None).map_err(|e| anyhow::anyhow!("{}", e))?;
+        let config = config::get();
+        let processor = Self::with_source(db_pool, source::from_config(config)?)?;
+        if processor.helpscout.is_some() {
+            return Ok(processor);
+        }
+        Ok(match source::helpscout_from_config(config)? {
+            Some(helpscout) => processor.with_helpscout_source(helpscout),
+            None => processor,
+        })
+    }
+
+    /// Syncs from `source`. Help Scout webhook events are only handled when
+    /// `source` is Help Scout, or after `with_helpscout_source`.
+    pub fn with_source(db_pool: Arc<DbPool>, source: Arc<dyn DocumentSource>) -> Result<Self> {
         let ai_service = Arc::new(AIService::new());
-        let embedding_service = Arc::new(EmbeddingService::new());
+        let embedding_service = Arc::new(EmbeddingService::new()?);
+        let helpscout = (source.name() == "helpscout").then(|| source.clone());
 
         info!("DataProcessor initialization complete");
         Ok(Self {
-            api_client,
+            source,
+            helpscout,
             db_pool,
             ai_service,
             embedding_service,
         })
     }
+
+    pub fn with_helpscout_source(mut self, helpscout: Arc<dyn DocumentSource>) -> Self {
+        self.helpscout = Some(helpscout);
+        self
+    }
+
+    /// The source Help Scout webhook events are resolved and fetched with.
+    pub fn helpscout_source(&self) -> Result<&Arc<dyn DocumentSource>> {
+        self.helpscout
+            .as_ref()
+            .context("Help Scout webhooks need helpscout.api_key to be set")
+    }
+
+    /// Shared with the jobs and services that embed, so the backend is only
+    /// loaded once per process.
+    pub fn embedding_service(&self) -> &Arc<EmbeddingService> {
+        &self.embedding_service
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -50,6 +92,8 @@ pub struct ProcessResult {
     pub paragraph_description_embedding: Option<Vector>,
     pub bullet_points_embedding: Option<Vector>,
     pub keywords_embedding: Option<Vector>,
+    /// Model the embeddings above come from.
+    pub embedding_model: Option<String>,
 }
 
 impl ProcessResult {
@@ -62,6 +106,7 @@ impl ProcessResult {
             paragraph_description_embedding: None,
             bullet_points_embedding: None,
             keywords_embedding: None,
+            embedding_model: None,
         }
     }
 
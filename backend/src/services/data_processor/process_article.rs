@@ -23,12 +23,14 @@ impl DataProcessor {
             let response = self.ai_service.generate_article_metadata(article).await?;
             match self.parse_llm_response(&response) {
                 Ok((paragraph, bullets, keywords)) => {
+                    let keywords = article.imported_keywords().unwrap_or(keywords);
                     info!("Response for article: {}: {}", article.id, response);
                     info!("Paragraph: {}", paragraph);
                     info!("Bullets: {:?}", bullets);
                     info!("Keywords: {:?}", keywords);
 
                     let mut result = ProcessResult::new(article.id);
+                    result.embedding_model = Some(self.embedding_service.model().to_string());
 
                     if paragraph != "No summary available" {
                         result.paragraph = Some(paragraph.clone());
@@ -9,6 +9,16 @@ impl DataProcessor {
         &self,
         article: &Article,
     ) -> Result<ProcessResult, Box<dyn std::error::Error + Send + Sync>> {
+        // Vectors of another model can't be kept next to new ones
+        let model = self.embedding_service.model();
+        if article
+            .metadata_embedding_model
+            .as_deref()
+            .is_some_and(|m| m != model)
+        {
+            return self.process_article_metadata(article).await;
+        }
+
         let mut conn = self.db_pool.get()?;
         let mut attempts = 0;
         const MAX_ATTEMPTS: u8 = 3;
@@ -19,6 +29,7 @@ impl DataProcessor {
                 Ok((paragraph, bullets, keywords)) => {
                     info!("Response for article: {}: {}", article.id, response);
                     let mut result = ProcessResult::new(article.id);
+                    result.embedding_model = Some(model.to_string());
 
                     // Paragraph description
                     result.paragraph = article.paragraph_description.clone();
@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use anyhow::Result;
+
+use tokio::sync::Mutex;
+use tokio::time::{sleep_until, Instant};
+
+/// Client-side token bucket shared by every request an `ApiClient` makes.
+/// Holds up to `capacity` tokens and refills continuously at
+/// `refill_per_second`; `acquire` waits for a token.
+pub struct TokenBucket {
+    capacity: f64,
+    refill_per_second: f64,
+    state: Mutex<BucketState>,
+}
+
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+    /// Set when the server asks us to back off; nobody acquires before it.
+    blocked_until: Option<Instant>,
+}
+
+impl TokenBucket {
+    /// Fails unless both `capacity` and `refill_per_second` are positive; an
+    /// empty or never refilling bucket would block its callers forever.
+    pub fn new(capacity: u32, refill_per_second: f64) -> Result<Self> {
+        anyhow::ensure!(capacity > 0, "Token bucket capacity must be positive");
+        anyhow::ensure!(
+            refill_per_second.is_finite() && refill_per_second > 0.0,
+            "Token bucket refill rate must be positive, got {}",
+            refill_per_second
+        );
+        let capacity = f64::from(capacity);
+        Ok(Self {
+            capacity,
+            refill_per_second,
+            state: Mutex::new(BucketState {
+                tokens: capacity,
+                last_refill: Instant::now(),
+                blocked_until: None,
+            }),
+        })
+    }
+
+    /// A bucket allowing `requests_per_minute` on average, in bursts of up
+    /// to `burst`.
+    pub fn per_minute(requests_per_minute: u32, burst: u32) -> Result<Self> {
+        Self::new(burst, f64::from(requests_per_minute) / 60.0)
+    }
+
+    pub async fn acquire(&self) {
+        loop {
+            let wait_until = {
+                let mut state = self.state.lock().await;
+                let now = Instant::now();
+                match state.blocked_until {
+                    Some(until) if until > now => until,
+                    _ => {
+                        state.blocked_until = None;
+                        self.refill(&mut state, now);
+                        if state.tokens >= 1.0 {
+                            state.tokens -= 1.0;
+                            return;
+                        }
+                        let missing = 1.0 - state.tokens;
+                        now + Duration::from_secs_f64(missing / self.refill_per_second)
+                    }
+                }
+            };
+            sleep_until(wait_until).await;
+        }
+    }
+
+    /// Holds back every caller for `delay`, e.g. after a 429 with
+    /// `Retry-After`, and empties the bucket so requests resume gradually.
+    pub async fn block_for(&self, delay: Duration) {
+        let mut state = self.state.lock().await;
+        let until = Instant::now() + delay;
+        if state.blocked_until.is_none_or(|current| current < until) {
+            state.blocked_until = Some(until);
+        }
+        state.tokens = 0.0;
+        state.last_refill = until;
+    }
+
+    /// Tokens available right now, for diagnostics and tests.
+    pub async fn available(&self) -> f64 {
+        let mut state = self.state.lock().await;
+        self.refill(&mut state, Instant::now());
+        state.tokens
+    }
+
+    fn refill(&self, state: &mut BucketState, now: Instant) {
+        if now > state.last_refill {
+            let elapsed = (now - state.last_refill).as_secs_f64();
+            state.tokens = (state.tokens + elapsed * self.refill_per_second).min(self.capacity);
+            state.last_refill = now;
+        }
+    }
+}
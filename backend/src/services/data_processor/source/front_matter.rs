@@ -0,0 +1,84 @@
+/// The fields read from a document's front-matter. Only flat `key: value`
+/// pairs are understood; `keywords` may be an inline `[a, b]` list, a
+/// comma-separated string or a block list of `- item` lines.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrontMatter {
+    pub title: Option<String>,
+    pub slug: Option<String>,
+    /// `None` when neither `keywords` nor `tags` is set.
+    pub keywords: Option<Vec<String>>,
+}
+
+/// Splits a leading `---` delimited front-matter block off `content`.
+/// Content without one is returned unchanged with empty front-matter.
+pub fn parse_front_matter(content: &str) -> (FrontMatter, &str) {
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return (FrontMatter::default(), content);
+    };
+    let Some((block, body)) = split_at_closing_fence(rest) else {
+        return (FrontMatter::default(), content);
+    };
+
+    let mut front_matter = FrontMatter::default();
+    let mut in_keywords = false;
+    for line in block.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if in_keywords {
+            if let Some(item) = trimmed.strip_prefix("- ") {
+                front_matter
+                    .keywords
+                    .get_or_insert_with(Vec::new)
+                    .push(unquote(item));
+                continue;
+            }
+            in_keywords = false;
+        }
+        let Some((key, value)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "title" if !value.is_empty() => front_matter.title = Some(unquote(value)),
+            "slug" if !value.is_empty() => front_matter.slug = Some(unquote(value)),
+            "keywords" | "tags" => {
+                let list = value
+                    .strip_prefix('[')
+                    .and_then(|list| list.strip_suffix(']'))
+                    .unwrap_or(value);
+                front_matter.keywords.get_or_insert_with(Vec::new).extend(
+                    list.split(',')
+                        .map(unquote)
+                        .filter(|keyword| !keyword.is_empty()),
+                );
+                in_keywords = value.is_empty();
+            }
+            _ => {}
+        }
+    }
+    (front_matter, body)
+}
+
+fn split_at_closing_fence(rest: &str) -> Option<(&str, &str)> {
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+fn unquote(value: &str) -> String {
+    value
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'')
+        .trim()
+        .to_string()
+}
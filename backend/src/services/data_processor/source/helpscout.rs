@@ -0,0 +1,63 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use log::warn;
+use tokio::time::sleep;
+
+use crate::models::{Article, ArticleRef, Collection};
+use crate::services::data_processor::api_client::{ApiClient, ArticleListing};
+
+use super::DocumentSource;
+
+/// How many times a failed article listing is resumed before giving up.
+const LISTING_RESUMES: usize = 2;
+
+#[async_trait]
+impl DocumentSource for ApiClient {
+    fn name(&self) -> &'static str {
+        "helpscout"
+    }
+
+    /// Help Scout IDs are plain hex object IDs, while every other source
+    /// prefixes its IDs (`local:`, `markdown:`) or uses page URLs.
+    fn owns_id(&self, source_id: &str) -> bool {
+        !source_id.contains(':')
+    }
+
+    async fn list_collections(&self) -> Result<Vec<Collection>> {
+        self.get_list_collections().await
+    }
+
+    /// Resumes from the page that failed when a listing gives up mid-way,
+    /// rather than starting over from page one.
+    async fn list_article_refs(&self, collection: &Collection) -> Result<Vec<ArticleRef>> {
+        let mut listing = ArticleListing::new(collection);
+        let mut resumes = 0;
+        loop {
+            match self.resume_list_articles(&mut listing).await {
+                Ok(()) => return Ok(listing.article_refs),
+                Err(e) if resumes < LISTING_RESUMES => {
+                    resumes += 1;
+                    let delay = self.config().retry_max_delay;
+                    warn!(
+                        "Listing articles of collection {} failed at page {}, resuming in {:?}: {}",
+                        collection.slug, listing.next_page, delay, e
+                    );
+                    sleep(delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn fetch_article(
+        &self,
+        article_ref: &ArticleRef,
+        collection: &Collection,
+    ) -> Result<Article> {
+        self.get_article(&article_ref.id, collection).await
+    }
+
+    async fn fetch_collection(&self, source_collection_id: &str) -> Result<Collection> {
+        self.get_collection(source_collection_id).await
+    }
+}
@@ -0,0 +1,286 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+use crate::models::{Article, ArticleRef, Collection};
+
+use super::front_matter::parse_front_matter;
+use super::{slugify, DocumentSource};
+
+/// Prefix of every collection and article ID handed out by `LocalDirSource::new`.
+const ID_PREFIX: &str = "local:";
+/// Collection ID for files directly under the root directory.
+const ROOT_COLLECTION: &str = ".";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    Markdown,
+    Html,
+}
+
+impl FileFormat {
+    fn of(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "md" | "markdown" => Some(FileFormat::Markdown),
+            "html" | "htm" => Some(FileFormat::Html),
+            _ => None,
+        }
+    }
+}
+
+/// Markdown and HTML files in a directory. Each top-level folder is a
+/// collection containing every document below it; documents directly under
+/// the root form one more collection named after the root.
+pub struct LocalDirSource {
+    root: PathBuf,
+    id_prefix: String,
+}
+
+impl LocalDirSource {
+    pub fn new(root: PathBuf) -> Result<Self> {
+        Self::with_id_prefix(root, ID_PREFIX)
+    }
+
+    /// Hands out collection and article IDs starting with `id_prefix`
+    /// instead of `local:`, e.g. to keep imports of different directories
+    /// apart.
+    pub fn with_id_prefix(root: PathBuf, id_prefix: &str) -> Result<Self> {
+        let root = root
+            .canonicalize()
+            .with_context(|| format!("Docs directory {:?} not found", root))?;
+        anyhow::ensure!(root.is_dir(), "{:?} is not a directory", root);
+        Ok(Self {
+            root,
+            id_prefix: id_prefix.to_string(),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Reads the document behind an article ID handed out by this source.
+    pub fn read_document(&self, article_id: &str) -> Result<(FileFormat, String)> {
+        let path = self.article_path(article_id)?;
+        let format = FileFormat::of(&path)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported document {:?}", path))?;
+        let content =
+            fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        Ok((format, content))
+    }
+
+    fn collection(&self, folder: &str) -> Collection {
+        let name = if folder == ROOT_COLLECTION {
+            self.root
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("docs")
+                .to_string()
+        } else {
+            folder.to_string()
+        };
+        Collection::new(
+            name.clone(),
+            None,
+            slugify(&name),
+            format!("{}{}", self.id_prefix, folder),
+        )
+    }
+
+    /// Resolves an article ID back to its file, refusing anything that
+    /// would leave the root directory.
+    fn article_path(&self, article_id: &str) -> Result<PathBuf> {
+        let relative = Path::new(
+            article_id
+                .strip_prefix(self.id_prefix.as_str())
+                .ok_or_else(|| anyhow::anyhow!("{} is not a local article ID", article_id))?,
+        );
+        anyhow::ensure!(
+            relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_))),
+            "Invalid local article path {:?}",
+            relative
+        );
+        Ok(self.root.join(relative))
+    }
+
+    fn article_id(&self, path: &Path) -> Result<String> {
+        let relative = path.strip_prefix(&self.root)?;
+        let relative = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        Ok(format!("{}{}", self.id_prefix, relative))
+    }
+
+    fn documents(&self, folder: &str) -> Vec<PathBuf> {
+        let (dir, depth) = if folder == ROOT_COLLECTION {
+            (self.root.clone(), 1)
+        } else {
+            (self.root.join(folder), usize::MAX)
+        };
+        let mut documents: Vec<PathBuf> = WalkDir::new(dir)
+            .max_depth(depth)
+            .into_iter()
+            .filter_entry(|entry| !is_hidden(entry.path()))
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file() && FileFormat::of(entry.path()).is_some())
+            .map(|entry| entry.into_path())
+            .collect();
+        documents.sort();
+        documents
+    }
+}
+
+#[async_trait]
+impl DocumentSource for LocalDirSource {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn owns_id(&self, source_id: &str) -> bool {
+        source_id.starts_with(self.id_prefix.as_str())
+    }
+
+    async fn list_collections(&self) -> Result<Vec<Collection>> {
+        let mut folders = Vec::new();
+        for entry in fs::read_dir(&self.root)? {
+            let path = entry?.path();
+            if path.is_dir() && !is_hidden(&path) {
+                if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+                    folders.push(name.to_string());
+                }
+            }
+        }
+        folders.sort();
+        if !self.documents(ROOT_COLLECTION).is_empty() {
+            folders.insert(0, ROOT_COLLECTION.to_string());
+        }
+        Ok(folders
+            .iter()
+            .map(|folder| self.collection(folder))
+            .collect())
+    }
+
+    async fn list_article_refs(&self, collection: &Collection) -> Result<Vec<ArticleRef>> {
+        let folder = collection
+            .helpscout_collection_id
+            .strip_prefix(self.id_prefix.as_str())
+            .ok_or_else(|| anyhow::anyhow!("{} is not a local collection", collection.slug))?;
+
+        self.documents(folder)
+            .into_iter()
+            .map(|path| {
+                let content = fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {:?}", path))?;
+                Ok(ArticleRef::new(
+                    self.article_id(&path)?,
+                    collection.helpscout_collection_id.clone(),
+                    document_title(&path, &content),
+                    format!("file://{}", path.display()),
+                    modified_at(&path),
+                ))
+            })
+            .collect()
+    }
+
+    async fn fetch_article(
+        &self,
+        article_ref: &ArticleRef,
+        collection: &Collection,
+    ) -> Result<Article> {
+        let path = self.article_path(&article_ref.id)?;
+        let (format, content) = self.read_document(&article_ref.id)?;
+        let slug = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .map(slugify)
+            .unwrap_or_default();
+
+        let title = document_title(&path, &content);
+        let mut article = match format {
+            FileFormat::Html => Article::new(
+                collection.id,
+                collection.helpscout_collection_id.clone(),
+                Some(article_ref.id.clone()),
+                title,
+                slug,
+                Some(content),
+            ),
+            FileFormat::Markdown => {
+                let mut article = Article::new(
+                    collection.id,
+                    collection.helpscout_collection_id.clone(),
+                    Some(article_ref.id.clone()),
+                    title,
+                    slug,
+                    None,
+                );
+                // Stored like the Markdown importer stores it, so both paths
+                // produce the same content and chunks
+                let (_, body) = parse_front_matter(&content);
+                article.markdown_content = Some(body.to_string());
+                article
+            }
+        };
+        if let Some(modified_at) = modified_at(&path) {
+            article.updated_at = modified_at;
+        }
+        Ok(article)
+    }
+}
+
+/// The front-matter title or first `# ` heading of a Markdown file or the
+/// `<title>`/`<h1>` of an HTML file, falling back to the file name.
+fn document_title(path: &Path, content: &str) -> String {
+    let title = match FileFormat::of(path) {
+        Some(FileFormat::Markdown) => {
+            let (front_matter, body) = parse_front_matter(content);
+            front_matter.title.or_else(|| {
+                body.lines()
+                    .find_map(|line| line.trim().strip_prefix("# "))
+                    .map(|title| title.trim().to_string())
+            })
+        }
+        Some(FileFormat::Html) => {
+            let document = scraper::Html::parse_document(content);
+            ["title", "h1"].iter().find_map(|tag| {
+                let selector = scraper::Selector::parse(tag).ok()?;
+                let text = document
+                    .select(&selector)
+                    .next()?
+                    .text()
+                    .collect::<String>();
+                let text = text.trim();
+                (!text.is_empty()).then(|| text.to_string())
+            })
+        }
+        None => None,
+    };
+    title.unwrap_or_else(|| {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default()
+    })
+}
+
+fn modified_at(path: &Path) -> Option<DateTime<Utc>> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .map(DateTime::<Utc>::from)
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.'))
+}
@@ -0,0 +1,111 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use log::info;
+use std::{path::PathBuf, sync::Arc};
+
+use crate::config::Config;
+use crate::models::{Article, ArticleRef, Collection};
+
+use super::api_client::ApiClient;
+
+pub mod front_matter;
+pub mod helpscout;
+pub mod local_dir;
+pub mod sitemap;
+
+pub use local_dir::LocalDirSource;
+pub use sitemap::SitemapSource;
+
+/// Where collections and articles are synced from. `Collection` and `Article`
+/// keep the source's own identifiers in their `helpscout_*_id` columns, so
+/// every source must hand out IDs that stay stable between syncs and don't
+/// collide with other sources.
+#[async_trait]
+pub trait DocumentSource: Send + Sync {
+    /// Short name used in logs, e.g. `"helpscout"`.
+    fn name(&self) -> &'static str;
+
+    /// Whether a stored article or collection ID was handed out by this
+    /// source. Syncs only archive the articles their source owns, so
+    /// switching `source.kind` leaves what other sources synced alone.
+    fn owns_id(&self, source_id: &str) -> bool;
+
+    async fn list_collections(&self) -> Result<Vec<Collection>>;
+
+    /// Every article currently in `collection`, including unpublished ones so
+    /// the sync can archive them.
+    async fn list_article_refs(&self, collection: &Collection) -> Result<Vec<ArticleRef>>;
+
+    /// Fetches the full article. The result carries `html_content`, which is
+    /// converted to Markdown after storing, or `markdown_content` directly.
+    async fn fetch_article(
+        &self,
+        article_ref: &ArticleRef,
+        collection: &Collection,
+    ) -> Result<Article>;
+
+    /// Fetches one collection by its source ID.
+    async fn fetch_collection(&self, source_collection_id: &str) -> Result<Collection> {
+        self.list_collections()
+            .await?
+            .into_iter()
+            .find(|collection| collection.helpscout_collection_id == source_collection_id)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Collection {} not found in {} source",
+                    source_collection_id,
+                    self.name()
+                )
+            })
+    }
+}
+
+/// Builds the source selected by `source.kind`:
+///
+/// - `helpscout` (default): the Help Scout Docs API, see `ApiClient`.
+/// - `local`: Markdown/HTML files under `source.docs_dir`.
+/// - `sitemap`: pages listed in `source.sitemap_urls`.
+pub fn from_config(config: &Config) -> Result<Arc<dyn DocumentSource>> {
+    let kind = config.source.kind.trim();
+    info!("Using {} document source", kind);
+
+    let source: Arc<dyn DocumentSource> = match kind {
+        "helpscout" => Arc::new(ApiClient::from_config(None, None, &config.helpscout)?),
+        "local" => {
+            let root = config
+                .source
+                .docs_dir
+                .as_ref()
+                .context("source.docs_dir must be set for the local source")?;
+            Arc::new(LocalDirSource::new(PathBuf::from(root))?)
+        }
+        "sitemap" => Arc::new(SitemapSource::new(
+            config.source.sitemap_urls.clone(),
+            config.source.sitemap_requests_per_minute,
+        )?),
+        other => anyhow::bail!("Unknown source.kind {:?}", other),
+    };
+    Ok(source)
+}
+
+/// Builds the Help Scout source webhook events are handled with, since they
+/// come from Help Scout whatever `source.kind` is. Returns `None` when no
+/// Help Scout API key is configured.
+pub fn helpscout_from_config(config: &Config) -> Result<Option<Arc<dyn DocumentSource>>> {
+    if config.helpscout.api_key.as_deref().unwrap_or_default().is_empty() {
+        return Ok(None);
+    }
+    let source: Arc<dyn DocumentSource> =
+        Arc::new(ApiClient::from_config(None, None, &config.helpscout)?);
+    Ok(Some(source))
+}
+
+/// Lowercase ASCII slug: runs of anything but letters and digits become `-`.
+pub fn slugify(value: &str) -> String {
+    value
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
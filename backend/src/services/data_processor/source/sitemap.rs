@@ -0,0 +1,242 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use log::{info, warn};
+use regex::Regex;
+use scraper::{Html, Selector};
+use std::{sync::OnceLock, time::Duration};
+use url::Url;
+
+use crate::models::{Article, ArticleRef, Collection};
+use crate::services::data_processor::rate_limiter::TokenBucket;
+
+use super::{slugify, DocumentSource};
+
+/// How deep nested `<sitemapindex>` files are followed.
+const MAX_SITEMAP_DEPTH: usize = 3;
+
+/// Crawls the pages listed in one or more XML sitemaps. Each sitemap is a
+/// collection, each page an article identified by its URL; the page's
+/// `<main>` or `<article>` element (else its body) becomes the article HTML.
+pub struct SitemapSource {
+    client: reqwest::Client,
+    sitemap_urls: Vec<Url>,
+    rate_limiter: TokenBucket,
+}
+
+/// One `<url>` entry of a sitemap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SitemapEntry {
+    pub loc: String,
+    pub lastmod: Option<DateTime<Utc>>,
+}
+
+impl SitemapSource {
+    /// Requests are limited to `requests_per_minute` so crawls stay polite.
+    pub fn new(sitemap_urls: Vec<String>, requests_per_minute: u32) -> Result<Self> {
+        anyhow::ensure!(!sitemap_urls.is_empty(), "No sitemap URLs configured");
+        anyhow::ensure!(
+            requests_per_minute > 0,
+            "Sitemap requests per minute must be positive"
+        );
+        let sitemap_urls = sitemap_urls
+            .iter()
+            .map(|url| Url::parse(url).with_context(|| format!("Invalid sitemap URL {:?}", url)))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()?,
+            sitemap_urls,
+            rate_limiter: TokenBucket::per_minute(requests_per_minute, 5)?,
+        })
+    }
+
+    async fn get_text(&self, url: &str) -> Result<String> {
+        self.rate_limiter.acquire().await;
+        let response = self.client.get(url).send().await?;
+        let status = response.status();
+        anyhow::ensure!(status.is_success(), "Request to {} failed: {}", url, status);
+        Ok(response.text().await?)
+    }
+
+    /// Every page listed by the sitemap, following sitemap indexes.
+    async fn entries(&self, sitemap_url: &str) -> Result<Vec<SitemapEntry>> {
+        let mut pending = vec![(sitemap_url.to_string(), 0)];
+        let mut entries = Vec::new();
+        while let Some((url, depth)) = pending.pop() {
+            info!("Fetching sitemap {}", url);
+            let xml = self.get_text(&url).await?;
+            if xml.contains("<sitemapindex") {
+                if depth >= MAX_SITEMAP_DEPTH {
+                    warn!("Sitemap index {} nested too deep, skipping", url);
+                    continue;
+                }
+                pending.extend(
+                    parse_sitemap(&xml)
+                        .into_iter()
+                        .map(|nested| (nested.loc, depth + 1)),
+                );
+            } else {
+                entries.extend(parse_sitemap(&xml));
+            }
+        }
+        Ok(entries)
+    }
+}
+
+#[async_trait]
+impl DocumentSource for SitemapSource {
+    fn name(&self) -> &'static str {
+        "sitemap"
+    }
+
+    /// Pages and sitemaps are identified by their URL.
+    fn owns_id(&self, source_id: &str) -> bool {
+        source_id.starts_with("https://") || source_id.starts_with("http://")
+    }
+
+    async fn list_collections(&self) -> Result<Vec<Collection>> {
+        Ok(self
+            .sitemap_urls
+            .iter()
+            .map(|url| {
+                let name = format!("{}{}", url.host_str().unwrap_or_default(), url.path());
+                Collection::new(name.clone(), None, slugify(&name), url.to_string())
+            })
+            .collect())
+    }
+
+    async fn list_article_refs(&self, collection: &Collection) -> Result<Vec<ArticleRef>> {
+        let entries = self.entries(&collection.helpscout_collection_id).await?;
+        info!(
+            "Sitemap {} lists {} pages",
+            collection.helpscout_collection_id,
+            entries.len()
+        );
+        Ok(entries
+            .into_iter()
+            .map(|entry| {
+                ArticleRef::new(
+                    entry.loc.clone(),
+                    collection.helpscout_collection_id.clone(),
+                    page_slug(&entry.loc),
+                    entry.loc,
+                    entry.lastmod,
+                )
+            })
+            .collect())
+    }
+
+    async fn fetch_article(
+        &self,
+        article_ref: &ArticleRef,
+        collection: &Collection,
+    ) -> Result<Article> {
+        let html = self.get_text(&article_ref.id).await?;
+        let (title, content) = extract_page(&html);
+
+        let mut article = Article::new(
+            collection.id,
+            collection.helpscout_collection_id.clone(),
+            Some(article_ref.id.clone()),
+            title.unwrap_or_else(|| article_ref.name.clone()),
+            page_slug(&article_ref.id),
+            Some(content),
+        );
+        if let Some(modified_at) = article_ref.last_modified_at() {
+            article.updated_at = modified_at;
+        }
+        Ok(article)
+    }
+}
+
+/// Reads the `<loc>`/`<lastmod>` pairs of a `<urlset>` or `<sitemapindex>`.
+pub fn parse_sitemap(xml: &str) -> Vec<SitemapEntry> {
+    static ENTRY: OnceLock<Regex> = OnceLock::new();
+    static LOC: OnceLock<Regex> = OnceLock::new();
+    static LASTMOD: OnceLock<Regex> = OnceLock::new();
+    let entry =
+        ENTRY.get_or_init(|| Regex::new(r"(?s)<(?:url|sitemap)>(.*?)</(?:url|sitemap)>").unwrap());
+    let loc = LOC.get_or_init(|| Regex::new(r"(?s)<loc>\s*(.*?)\s*</loc>").unwrap());
+    let lastmod =
+        LASTMOD.get_or_init(|| Regex::new(r"(?s)<lastmod>\s*(.*?)\s*</lastmod>").unwrap());
+
+    entry
+        .captures_iter(xml)
+        .filter_map(|block| {
+            let block = block.get(1)?.as_str();
+            let url = unescape_xml(loc.captures(block)?.get(1)?.as_str());
+            let modified = lastmod
+                .captures(block)
+                .and_then(|m| parse_lastmod(m.get(1)?.as_str()));
+            Some(SitemapEntry {
+                loc: url,
+                lastmod: modified,
+            })
+        })
+        .collect()
+}
+
+/// `<lastmod>` is W3C datetime: a full timestamp or just a date.
+fn parse_lastmod(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()?
+                .and_hms_opt(0, 0, 0)
+                .map(|timestamp| timestamp.and_utc())
+        })
+}
+
+fn unescape_xml(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// The page title and the HTML of its main content.
+pub fn extract_page(html: &str) -> (Option<String>, String) {
+    let document = Html::parse_document(html);
+    let first = |selector: &str| {
+        Selector::parse(selector)
+            .ok()
+            .and_then(|selector| document.select(&selector).next())
+    };
+
+    let title = ["h1", "title"].iter().find_map(|selector| {
+        let text = first(selector)?.text().collect::<String>();
+        let text = text.trim();
+        (!text.is_empty()).then(|| text.to_string())
+    });
+    let content = ["main", "article", "[role=main]", "body"]
+        .iter()
+        .find_map(|selector| first(selector))
+        .map(|element| element.inner_html())
+        .unwrap_or_else(|| html.to_string());
+    (title, content)
+}
+
+fn page_slug(url: &str) -> String {
+    let path = Url::parse(url)
+        .map(|url| url.path().to_string())
+        .unwrap_or_else(|_| url.to_string());
+    let last_segment = path
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or_default();
+    let last_segment = last_segment
+        .rsplit_once('.')
+        .map_or(last_segment, |(stem, _)| stem);
+    match slugify(last_segment) {
+        slug if slug.is_empty() => "index".to_string(),
+        slug => slug,
+    }
+}
@@ -0,0 +1,55 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha1::Sha1;
+
+use crate::errors::WebhookError;
+use crate::models::ArticleRef;
+
+pub const SIGNATURE_HEADER: &str = "X-HelpScout-Signature";
+pub const EVENT_HEADER: &str = "X-HelpScout-Event";
+
+type HmacSha1 = Hmac<Sha1>;
+
+#[derive(Debug, Clone)]
+pub enum WebhookEvent {
+    ArticleCreated(ArticleRef),
+    ArticleUpdated(ArticleRef),
+    /// Carries the Help Scout ID of the deleted article.
+    ArticleDeleted(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct DeletedArticle {
+    id: String,
+}
+
+/// Base64-encoded HMAC-SHA1 of the raw request body, as sent by Help Scout.
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = HmacSha1::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    STANDARD.encode(mac.finalize().into_bytes())
+}
+
+pub fn verify_signature(secret: &[u8], body: &[u8], signature: &str) -> Result<(), WebhookError> {
+    let signature = STANDARD
+        .decode(signature.trim())
+        .map_err(|_| WebhookError::InvalidSignature)?;
+    let mut mac = HmacSha1::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    // Constant-time comparison
+    mac.verify_slice(&signature)
+        .map_err(|_| WebhookError::InvalidSignature)
+}
+
+pub fn parse_event(event_type: &str, body: &[u8]) -> Result<WebhookEvent, WebhookError> {
+    match event_type {
+        "docs.article.created" => Ok(WebhookEvent::ArticleCreated(serde_json::from_slice(body)?)),
+        "docs.article.updated" => Ok(WebhookEvent::ArticleUpdated(serde_json::from_slice(body)?)),
+        "docs.article.deleted" => {
+            let deleted: DeletedArticle = serde_json::from_slice(body)?;
+            Ok(WebhookEvent::ArticleDeleted(deleted.id))
+        }
+        other => Err(WebhookError::UnsupportedEvent(other.to_string())),
+    }
+}
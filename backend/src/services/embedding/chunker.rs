@@ -0,0 +1,396 @@
+//! Splits Markdown into chunks for embedding along its structure: chunks end
+//! at headings, paragraphs and list items rather than mid-sentence, code
+//! blocks and tables are never split, and every chunk knows the headings it
+//! sits under.
+
+/// A contiguous piece of the Markdown passed to `Chunker::chunk`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub content: String,
+    /// Texts of the enclosing headings, outermost first.
+    pub heading_path: Vec<String>,
+    /// Character (not byte) offsets of `content` in the Markdown.
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Chunk {
+    /// The text that is embedded for the chunk, see `embedding_text`.
+    pub fn embedding_text(&self) -> String {
+        embedding_text(&self.heading_path, &self.content)
+    }
+}
+
+/// Joins the headings in front of a chunk's `embedding_text`.
+const HEADING_SEPARATOR: &str = " > ";
+
+/// The text that is embedded for a chunk: its content, preceded by its
+/// headings so that a chunk deep in a section still matches queries about
+/// the section.
+pub fn embedding_text(heading_path: &[String], content: &str) -> String {
+    if heading_path.is_empty() {
+        content.to_string()
+    } else {
+        format!("{}\n\n{}", heading_path.join(HEADING_SEPARATOR), content)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chunker {
+    max_tokens: usize,
+    overlap_tokens: usize,
+}
+
+/// Rough token count for budgeting: a token per four characters of each
+/// word, at least one per word, which is close to what WordPiece and BPE
+/// tokenizers produce for English prose.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.split_whitespace()
+        .map(|word| word.chars().count().div_ceil(4))
+        .sum()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Heading(usize),
+    Paragraph,
+    ListItem,
+    Code,
+    Table,
+}
+
+/// A block of the Markdown, by byte range with trailing whitespace trimmed.
+#[derive(Debug, Clone, Copy)]
+struct Block {
+    kind: BlockKind,
+    start: usize,
+    end: usize,
+}
+
+/// Part of a chunk: a whole block, or a piece of a block too long for one.
+#[derive(Debug, Clone, Copy)]
+struct Unit {
+    start: usize,
+    end: usize,
+    tokens: usize,
+    /// Code blocks and tables, which overlap must not cut into.
+    atomic: bool,
+}
+
+impl Chunker {
+    /// Chunks hold at most `max_tokens` estimated tokens, counting the
+    /// headings `embedding_text` puts in front of them, except for code
+    /// blocks and tables larger than that, which become a chunk of their own.
+    /// Consecutive chunks of a section share up to `overlap_tokens` of text.
+    pub fn new(max_tokens: usize, overlap_tokens: usize) -> Self {
+        let max_tokens = max_tokens.max(1);
+        Self {
+            max_tokens,
+            overlap_tokens: overlap_tokens.min(max_tokens - 1),
+        }
+    }
+
+    pub fn chunk(&self, markdown: &str) -> Vec<Chunk> {
+        let mut chunks = Vec::new();
+        let mut heading_path: Vec<(usize, String)> = Vec::new();
+        let mut current: Vec<Unit> = Vec::new();
+        let mut budget = self.max_tokens;
+
+        for block in parse_blocks(markdown) {
+            let text = &markdown[block.start..block.end];
+            match block.kind {
+                BlockKind::Heading(level) => {
+                    self.flush(markdown, &heading_path, &mut current, &mut chunks);
+                    heading_path.retain(|(outer, _)| *outer < level);
+                    heading_path.push((level, heading_text(text)));
+                    budget = self.budget(&heading_path);
+                }
+                kind => {
+                    let atomic = matches!(kind, BlockKind::Code | BlockKind::Table);
+                    for unit in self.units(markdown, block, atomic, budget) {
+                        let tokens: usize = current.iter().map(|unit| unit.tokens).sum();
+                        if !current.is_empty() && tokens + unit.tokens > budget {
+                            let room = budget.saturating_sub(unit.tokens);
+                            let overlap = self.overlap(markdown, &current, room);
+                            self.flush(markdown, &heading_path, &mut current, &mut chunks);
+                            current.extend(overlap);
+                        }
+                        current.push(unit);
+                    }
+                }
+            }
+        }
+        self.flush(markdown, &heading_path, &mut current, &mut chunks);
+        chunks
+    }
+
+    /// Tokens left for the content of chunks under `heading_path`, at least
+    /// enough for the overlap and one more.
+    fn budget(&self, heading_path: &[(usize, String)]) -> usize {
+        let headings: Vec<&str> = heading_path.iter().map(|(_, text)| text.as_str()).collect();
+        let heading_tokens = estimate_tokens(&headings.join(HEADING_SEPARATOR));
+        self.max_tokens
+            .saturating_sub(heading_tokens)
+            .max(self.overlap_tokens + 1)
+    }
+
+    /// The block as one unit, or split into word runs when it is longer than
+    /// `budget` and not atomic. Runs leave room for the overlap with the
+    /// previous one.
+    fn units(&self, markdown: &str, block: Block, atomic: bool, budget: usize) -> Vec<Unit> {
+        let text = &markdown[block.start..block.end];
+        let tokens = estimate_tokens(text);
+        if atomic || tokens <= budget {
+            return vec![Unit {
+                start: block.start,
+                end: block.end,
+                tokens,
+                atomic,
+            }];
+        }
+
+        let run_tokens = budget - self.overlap_tokens;
+        let mut units: Vec<Unit> = Vec::new();
+        for (start, end) in words(text) {
+            let word_tokens = estimate_tokens(&text[start..end]);
+            match units.last_mut() {
+                Some(unit) if unit.tokens + word_tokens <= run_tokens => {
+                    unit.end = block.start + end;
+                    unit.tokens += word_tokens;
+                }
+                _ => units.push(Unit {
+                    start: block.start + start,
+                    end: block.start + end,
+                    tokens: word_tokens,
+                    atomic: false,
+                }),
+            }
+        }
+        units
+    }
+
+    /// The trailing words of `units` worth at most `overlap_tokens` and
+    /// `budget` tokens, stopping at code blocks and tables.
+    fn overlap(&self, markdown: &str, units: &[Unit], budget: usize) -> Option<Unit> {
+        let limit = self.overlap_tokens.min(budget);
+        let end = units.last()?.end;
+        let mut start = end;
+        let mut tokens = 0;
+        'units: for unit in units.iter().rev() {
+            if unit.atomic {
+                break;
+            }
+            let text = &markdown[unit.start..unit.end];
+            for (word_start, word_end) in words(text).into_iter().rev() {
+                let word_tokens = estimate_tokens(&text[word_start..word_end]);
+                if tokens + word_tokens > limit {
+                    break 'units;
+                }
+                tokens += word_tokens;
+                start = unit.start + word_start;
+            }
+        }
+        (tokens > 0).then_some(Unit {
+            start,
+            end,
+            tokens,
+            atomic: false,
+        })
+    }
+
+    fn flush(
+        &self,
+        markdown: &str,
+        heading_path: &[(usize, String)],
+        current: &mut Vec<Unit>,
+        chunks: &mut Vec<Chunk>,
+    ) {
+        let (Some(first), Some(last)) = (current.first(), current.last()) else {
+            return;
+        };
+        let (start, end) = (first.start, last.end);
+        chunks.push(Chunk {
+            content: markdown[start..end].to_string(),
+            heading_path: heading_path.iter().map(|(_, text)| text.clone()).collect(),
+            start: markdown[..start].chars().count(),
+            end: markdown[..end].chars().count(),
+        });
+        current.clear();
+    }
+}
+
+/// Byte ranges of the whitespace-separated words of `text`.
+fn words(text: &str) -> Vec<(usize, usize)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (index, c) in text.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(word_start)) => {
+                words.push((word_start, index));
+                start = None;
+            }
+            (false, None) => start = Some(index),
+            _ => {}
+        }
+    }
+    if let Some(word_start) = start {
+        words.push((word_start, text.len()));
+    }
+    words
+}
+
+fn heading_text(text: &str) -> String {
+    text.trim()
+        .trim_start_matches('#')
+        .trim_end_matches('#')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The heading level of an ATX heading line.
+fn heading_level(line: &str) -> Option<usize> {
+    let trimmed = unindent(line)?;
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    let rest = &trimmed[level..];
+    ((1..=6).contains(&level) && (rest.is_empty() || rest.starts_with([' ', '\t'])))
+        .then_some(level)
+}
+
+/// The opening fence of a fenced code block, e.g. "```" or "~~~~".
+fn code_fence(line: &str) -> Option<&str> {
+    let trimmed = unindent(line)?;
+    let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let length = trimmed.chars().take_while(|c| *c == marker).count();
+    (length >= 3).then(|| &trimmed[..length])
+}
+
+fn is_table_row(line: &str) -> bool {
+    unindent(line).is_some_and(|trimmed| trimmed.starts_with('|'))
+}
+
+fn is_list_item(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    let marker_end = if trimmed.starts_with(['-', '*', '+']) {
+        1
+    } else {
+        let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+        if digits == 0 || !trimmed[digits..].starts_with(['.', ')']) {
+            return false;
+        }
+        digits + 1
+    };
+    let rest = &trimmed[marker_end..];
+    rest.is_empty() || rest.starts_with([' ', '\t'])
+}
+
+/// The line without up to three spaces of indentation, or `None` when it is
+/// indented further.
+fn unindent(line: &str) -> Option<&str> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    (indent <= 3).then(|| &line[indent..])
+}
+
+/// The level of a setext heading whose underline is `line`: `===` for 1 and
+/// `---` for 2, as html2md writes `<h1>` and `<h2>`.
+fn setext_level(line: &str) -> Option<usize> {
+    let trimmed = unindent(line)?.trim_end();
+    if !trimmed.is_empty() && trimmed.chars().all(|c| c == '=') {
+        Some(1)
+    } else if !trimmed.is_empty() && trimmed.chars().all(|c| c == '-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// A `---`, `***` or `___` line between blocks.
+fn is_thematic_break(line: &str) -> bool {
+    let Some(trimmed) = unindent(line) else {
+        return false;
+    };
+    let marks: Vec<char> = trimmed.chars().filter(|c| !c.is_whitespace()).collect();
+    marks.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|mark| marks.iter().all(|c| c == mark))
+}
+
+fn starts_block(line: &str) -> bool {
+    heading_level(line).is_some()
+        || code_fence(line).is_some()
+        || is_table_row(line)
+        || is_list_item(line)
+}
+
+fn parse_blocks(markdown: &str) -> Vec<Block> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for line in markdown.split_inclusive('\n') {
+        lines.push((offset, line.trim_end_matches(['\n', '\r'])));
+        offset += line.len();
+    }
+
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let (start, line) = lines[i];
+        if line.trim().is_empty() {
+            i += 1;
+            continue;
+        }
+
+        if is_thematic_break(line) {
+            i += 1;
+            continue;
+        }
+
+        // Setext headings end before their underline
+        let mut underline = false;
+        let kind = if let Some(level) = heading_level(line) {
+            i += 1;
+            BlockKind::Heading(level)
+        } else if let Some(fence) = code_fence(line) {
+            i += 1;
+            while i < lines.len() && !unindent(lines[i].1).is_some_and(|l| l.starts_with(fence)) {
+                i += 1;
+            }
+            // Include the closing fence; an unclosed block runs to the end
+            i = (i + 1).min(lines.len());
+            BlockKind::Code
+        } else if is_table_row(line) {
+            while i < lines.len() && is_table_row(lines[i].1) {
+                i += 1;
+            }
+            BlockKind::Table
+        } else {
+            let mut kind = if is_list_item(line) {
+                BlockKind::ListItem
+            } else {
+                BlockKind::Paragraph
+            };
+            i += 1;
+            while i < lines.len() && !lines[i].1.trim().is_empty() {
+                if kind == BlockKind::Paragraph {
+                    if let Some(level) = setext_level(lines[i].1) {
+                        kind = BlockKind::Heading(level);
+                        underline = true;
+                        i += 1;
+                        break;
+                    }
+                }
+                if starts_block(lines[i].1) {
+                    break;
+                }
+                i += 1;
+            }
+            kind
+        };
+
+        let last = if underline { i - 2 } else { i - 1 };
+        let (last_start, last_line) = lines[last];
+        let end = last_start + last_line.trim_end().len();
+        blocks.push(Block { kind, start, end });
+    }
+    blocks
+}
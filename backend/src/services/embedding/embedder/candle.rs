@@ -0,0 +1,166 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use candle_core::{DType, Device, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{BertModel, Config, DTYPE};
+use log::info;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokenizers::{Tokenizer, TruncationParams};
+
+use super::Embedder;
+
+/// sentence-transformers' limit for all-MiniLM-L6-v2, used when the model
+/// directory has no `sentence_bert_config.json`.
+const DEFAULT_MAX_SEQ_LENGTH: usize = 256;
+
+/// Models already loaded, by directory, so that every `EmbeddingService`
+/// shares one copy of the weights.
+static MODELS: OnceLock<Mutex<HashMap<PathBuf, Arc<SentenceModel>>>> = OnceLock::new();
+
+/// Runs a sentence-transformers BERT model such as all-MiniLM-L6-v2 on the
+/// CPU, with the same mean pooling and normalization as the Python service,
+/// so it can replace it without re-embedding.
+///
+/// The model directory is a checkout of the Hugging Face repository and needs
+/// `config.json`, `tokenizer.json` and `model.safetensors`.
+pub struct CandleEmbedder {
+    model: Arc<SentenceModel>,
+    model_name: String,
+}
+
+struct SentenceModel {
+    bert: BertModel,
+    tokenizer: Tokenizer,
+    device: Device,
+    dimensions: usize,
+}
+
+#[derive(Deserialize)]
+struct SentenceBertConfig {
+    max_seq_length: usize,
+}
+
+impl CandleEmbedder {
+    /// Loads the model in `model_path`; `model_name` identifies it in stored
+    /// embeddings.
+    pub fn load(model_path: impl AsRef<Path>, model_name: &str) -> Result<Self> {
+        let model_path = model_path.as_ref().to_path_buf();
+        let mut models = MODELS
+            .get_or_init(Default::default)
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Embedding model cache poisoned"))?;
+        let model = match models.get(&model_path) {
+            Some(model) => model.clone(),
+            None => {
+                let model = Arc::new(SentenceModel::load(&model_path)?);
+                models.insert(model_path, model.clone());
+                model
+            }
+        };
+        Ok(Self {
+            model,
+            model_name: model_name.to_string(),
+        })
+    }
+}
+
+impl SentenceModel {
+    fn load(dir: &Path) -> Result<Self> {
+        info!("Loading embedding model from {:?}", dir);
+        let device = Device::Cpu;
+
+        let config: Config = serde_json::from_str(
+            &fs::read_to_string(dir.join("config.json"))
+                .with_context(|| format!("Failed to read {:?}", dir.join("config.json")))?,
+        )
+        .context("Invalid model config.json")?;
+
+        let max_seq_length = match fs::read_to_string(dir.join("sentence_bert_config.json")) {
+            Ok(contents) => {
+                serde_json::from_str::<SentenceBertConfig>(&contents)
+                    .context("Invalid sentence_bert_config.json")?
+                    .max_seq_length
+            }
+            Err(_) => DEFAULT_MAX_SEQ_LENGTH,
+        };
+        let mut tokenizer = Tokenizer::from_file(dir.join("tokenizer.json"))
+            .map_err(anyhow::Error::msg)
+            .with_context(|| format!("Failed to load {:?}", dir.join("tokenizer.json")))?;
+        tokenizer
+            .with_padding(None)
+            .with_truncation(Some(TruncationParams {
+                max_length: max_seq_length,
+                ..Default::default()
+            }))
+            .map_err(anyhow::Error::msg)?;
+
+        let weights = dir.join("model.safetensors");
+        // Safety: the weights file must not be modified while mapped
+        let vb = unsafe { VarBuilder::from_mmaped_safetensors(&[&weights], DTYPE, &device)? };
+        let bert = BertModel::load(vb, &config)
+            .with_context(|| format!("Failed to load weights from {:?}", weights))?;
+
+        Ok(Self {
+            bert,
+            tokenizer,
+            device,
+            dimensions: config.hidden_size,
+        })
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let encoding = self
+            .tokenizer
+            .encode(text, true)
+            .map_err(anyhow::Error::msg)?;
+        let input_ids = Tensor::new(encoding.get_ids(), &self.device)?.unsqueeze(0)?;
+        let token_type_ids = Tensor::new(encoding.get_type_ids(), &self.device)?.unsqueeze(0)?;
+        let attention_mask =
+            Tensor::new(encoding.get_attention_mask(), &self.device)?.unsqueeze(0)?;
+
+        let hidden = self
+            .bert
+            .forward(&input_ids, &token_type_ids, Some(&attention_mask))?;
+
+        // Mean over the non-padding tokens, then L2-normalize
+        let mask = attention_mask.to_dtype(DType::F32)?.unsqueeze(2)?;
+        let summed = hidden.broadcast_mul(&mask)?.sum(1)?;
+        let pooled = summed.broadcast_div(&mask.sum(1)?)?;
+        let norm = pooled.sqr()?.sum_keepdim(1)?.sqrt()?;
+        let embedding = pooled.broadcast_div(&norm)?.squeeze(0)?;
+
+        Ok(embedding.to_vec1::<f32>()?)
+    }
+}
+
+#[async_trait]
+impl Embedder for CandleEmbedder {
+    fn name(&self) -> &'static str {
+        "candle"
+    }
+
+    fn model(&self) -> &str {
+        &self.model_name
+    }
+
+    fn dimensions(&self) -> usize {
+        self.model.dimensions
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let model = self.model.clone();
+        let text = text.to_string();
+        tokio::task::spawn_blocking(move || model.embed(&text)).await?
+    }
+
+    async fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let model = self.model.clone();
+        let texts: Vec<String> = texts.iter().map(|text| text.to_string()).collect();
+        tokio::task::spawn_blocking(move || texts.iter().map(|text| model.embed(text)).collect())
+            .await?
+    }
+}
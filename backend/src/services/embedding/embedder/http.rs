@@ -0,0 +1,115 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use log::{error, warn};
+use reqwest::{Client, StatusCode};
+use serde_json::json;
+
+use super::Embedder;
+use crate::config::EmbeddingConfig;
+
+/// Client for `python_services/embedding_service.py`.
+pub struct HttpEmbedder {
+    client: Client,
+    embed_url: String,
+    batch_url: String,
+    model: String,
+    dimensions: usize,
+}
+
+impl HttpEmbedder {
+    pub fn new(config: &EmbeddingConfig) -> Self {
+        Self {
+            client: Client::new(),
+            embed_url: config.endpoint("embed"),
+            batch_url: config.endpoint("embed_batch"),
+            model: config.model.clone(),
+            dimensions: config.dimensions,
+        }
+    }
+
+    /// Posts `body` to `url` and returns the response's `field`.
+    async fn post<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+        body: serde_json::Value,
+        field: &str,
+    ) -> Result<T> {
+        let resp = self
+            .client
+            .post(url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| {
+                error!("Failed to send request to embedding service: {}", e);
+                e
+            })?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let error_message = resp.text().await?;
+            error!("Embedding service returned an error: {}", error_message);
+            return Err(EmbeddingServiceError {
+                status,
+                message: error_message,
+            }
+            .into());
+        }
+
+        let mut embedding_data: serde_json::Value = resp.json().await.map_err(|e| {
+            error!("Failed to parse embedding service response: {}", e);
+            e
+        })?;
+
+        serde_json::from_value(embedding_data[field].take())
+            .with_context(|| format!("Failed to extract {} from response", field))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Embedding service error: {status} - {message}")]
+struct EmbeddingServiceError {
+    status: StatusCode,
+    message: String,
+}
+
+#[async_trait]
+impl Embedder for HttpEmbedder {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.post(&self.embed_url, json!({ "text": text }), "embedding")
+            .await
+    }
+
+    async fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let result = self
+            .post(&self.batch_url, json!({ "texts": texts }), "embeddings")
+            .await;
+        match result {
+            // Services started before `/embed_batch` existed
+            Err(e)
+                if e.downcast_ref::<EmbeddingServiceError>()
+                    .is_some_and(|e| e.status == StatusCode::NOT_FOUND) =>
+            {
+                warn!("Embedding service has no /embed_batch, embedding texts one at a time");
+                let mut embeddings = Vec::with_capacity(texts.len());
+                for text in texts {
+                    embeddings.push(self.embed(text).await?);
+                }
+                Ok(embeddings)
+            }
+            result => result,
+        }
+    }
+}
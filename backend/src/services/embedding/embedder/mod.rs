@@ -0,0 +1,95 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use log::info;
+use std::sync::Arc;
+
+use crate::config::EmbeddingConfig;
+use crate::utils::OllamaLoadBalancer;
+
+#[cfg(feature = "candle")]
+pub mod candle;
+pub mod http;
+pub mod ollama;
+
+#[cfg(feature = "candle")]
+pub use self::candle::CandleEmbedder;
+pub use http::HttpEmbedder;
+pub use ollama::OllamaEmbedder;
+
+/// Turns text into a vector. Embeddings are stored with the backend's
+/// `model()` and search only compares a query with chunks embedded by the same
+/// model, so after switching models articles must be re-embedded before search
+/// finds them again.
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    /// Short name used in logs, e.g. `"http"`.
+    fn name(&self) -> &'static str;
+
+    /// Identifier of the model, stored with every embedding, e.g.
+    /// `"all-MiniLM-L6-v2"`.
+    fn model(&self) -> &str;
+
+    /// Length of every vector `embed` returns.
+    fn dimensions(&self) -> usize;
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>>;
+
+    /// Embeds `texts` in one go, returning the vectors in the same order.
+    /// Backends without a batch protocol embed one text at a time.
+    async fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for text in texts {
+            embeddings.push(self.embed(text).await?);
+        }
+        Ok(embeddings)
+    }
+}
+
+/// Builds the backend selected by `embedding.backend`:
+///
+/// - `http` (default): the Python service at `embedding.url`, running
+///   `embedding.model`.
+/// - `ollama`: `embedding.ollama_model` on the Ollama servers at
+///   `embedding.ollama_host` and `embedding.ollama_ports`.
+/// - `candle`: `embedding.model` run in-process from `embedding.model_path`,
+///   only available when built with the `candle` feature.
+pub fn from_config(config: &EmbeddingConfig) -> Result<Arc<dyn Embedder>> {
+    let backend = config.backend.trim();
+    info!("Using {} embedding backend", backend);
+
+    let embedder: Arc<dyn Embedder> = match backend {
+        "http" => Arc::new(HttpEmbedder::new(config)),
+        "ollama" => {
+            anyhow::ensure!(
+                !config.ollama_ports.is_empty(),
+                "embedding.ollama_ports must not be empty"
+            );
+            let balancer =
+                OllamaLoadBalancer::with_host(&config.ollama_host, &config.ollama_ports, 1);
+            Arc::new(OllamaEmbedder::new(
+                Arc::new(balancer),
+                config.ollama_model.clone(),
+                config.dimensions,
+            ))
+        }
+        "candle" => {
+            let model_path = config
+                .model_path
+                .as_ref()
+                .context("embedding.model_path must be set for the candle backend")?;
+            load_candle(model_path, &config.model)?
+        }
+        other => anyhow::bail!("Unknown embedding.backend {:?}", other),
+    };
+    Ok(embedder)
+}
+
+#[cfg(feature = "candle")]
+fn load_candle(model_path: &str, model: &str) -> Result<Arc<dyn Embedder>> {
+    Ok(Arc::new(CandleEmbedder::load(model_path, model)?))
+}
+
+#[cfg(not(feature = "candle"))]
+fn load_candle(_model_path: &str, _model: &str) -> Result<Arc<dyn Embedder>> {
+    anyhow::bail!("The candle embedding backend requires building with `--features candle`")
+}
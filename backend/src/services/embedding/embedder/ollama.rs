@@ -0,0 +1,74 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use log::error;
+use std::sync::Arc;
+
+use super::Embedder;
+use crate::utils::OllamaLoadBalancer;
+
+/// Embeds through Ollama's embeddings endpoint, spreading requests over the
+/// servers of an `OllamaLoadBalancer`. Pull the model first, e.g.
+/// `ollama pull all-minilm` for vectors compatible with the Python service.
+pub struct OllamaEmbedder {
+    balancer: Arc<OllamaLoadBalancer>,
+    model: String,
+    dimensions: usize,
+}
+
+impl OllamaEmbedder {
+    /// `dimensions` is the vector size `model` must produce; anything else is
+    /// rejected rather than stored next to incompatible vectors.
+    pub fn new(balancer: Arc<OllamaLoadBalancer>, model: String, dimensions: usize) -> Self {
+        Self {
+            balancer,
+            model,
+            dimensions,
+        }
+    }
+}
+
+#[async_trait]
+impl Embedder for OllamaEmbedder {
+    fn name(&self) -> &'static str {
+        "ollama"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let server = self.balancer.get_server().await;
+        let response = {
+            let ollama = server.lock().await;
+            ollama
+                .generate_embeddings(self.model.clone(), text.to_string(), None)
+                .await
+                .map_err(|e| {
+                    error!("Ollama failed to embed with {}: {}", self.model, e);
+                    anyhow::anyhow!("Ollama embedding error: {}", e)
+                })?
+        };
+
+        let embedding: Vec<f32> = response.embeddings.into_iter().map(|v| v as f32).collect();
+        anyhow::ensure!(
+            embedding.len() == self.dimensions,
+            "Ollama model {} returned {} dimensions, expected {}",
+            self.model,
+            embedding.len(),
+            self.dimensions
+        );
+        Ok(embedding)
+    }
+
+    /// Embeds the texts concurrently, one request per text, so a batch is
+    /// spread over all servers.
+    async fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        try_join_all(texts.iter().map(|text| self.embed(text))).await
+    }
+}
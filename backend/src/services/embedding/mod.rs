@@ -1,71 +1,284 @@
-use diesel::PgConnection;
-use reqwest::Client;
-use serde_json::json;
+use diesel::{OptionalExtension, PgConnection};
+use futures::stream::{self, StreamExt};
+use lru::LruCache;
+use std::collections::{HashMap, HashSet};
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
 
 use anyhow::{Context, Result};
-use diesel::RunQueryDsl;
-use log::{error, info};
+use log::{error, info, warn};
 
-use crate::models::{embedding::Embedding, Article};
+use crate::config::{self, EmbeddingConfig};
+use crate::db::DbPool;
+use crate::errors::SyncError;
+use crate::job::{JobControl, JobProgress};
+use crate::models::{
+    embedding::{ensure_vector_indexes, Embedding},
+    Article, ArticleChunk, CachedEmbedding, EmbeddingGeneration,
+};
+use crate::utils::content_hash;
+
+pub mod chunker;
+pub mod embedder;
+
+pub use chunker::Chunker;
+pub use embedder::Embedder;
 
 pub struct EmbeddingService {
-    client: Client,
+    embedder: Arc<dyn Embedder>,
+    /// Backend of the model search used before a re-embed into `embedder`'s,
+    /// see `query_model`.
+    previous: Option<Arc<dyn Embedder>>,
+    config: EmbeddingConfig,
+    /// Vectors of recent search queries by content hash, see `embed_query`.
+    query_cache: Option<Mutex<LruCache<String, Vec<f32>>>>,
 }
 
 impl EmbeddingService {
-    pub fn new() -> Self {
+    pub fn new() -> Result<Self> {
+        Self::with_config(config::get().embedding.clone())
+    }
+
+    /// Builds the backend selected by `embedding.backend`, see
+    /// `embedder::from_config`, and the one of `embedding.previous`.
+    pub fn with_config(config: EmbeddingConfig) -> Result<Self> {
+        let previous = match &config.previous {
+            Some(previous) => Some(embedder::from_config(previous)?),
+            None => None,
+        };
+        let service = Self::with_embedder(embedder::from_config(&config)?, config);
+        Ok(match previous {
+            Some(previous) => service.with_previous(previous),
+            None => service,
+        })
+    }
+
+    pub fn with_embedder(embedder: Arc<dyn Embedder>, config: EmbeddingConfig) -> Self {
+        let query_cache =
+            NonZeroUsize::new(config.query_cache_size).map(|size| Mutex::new(LruCache::new(size)));
         Self {
-            client: Client::new(),
+            embedder,
+            previous: None,
+            config,
+            query_cache,
         }
     }
+
+    /// Keeps embedding search queries with `previous` while the active
+    /// generation was built with its model, see `query_model`.
+    pub fn with_previous(mut self, previous: Arc<dyn Embedder>) -> Self {
+        self.previous = Some(previous);
+        self
+    }
+
+    /// The model this service's vectors come from, see `Embedder::model`.
+    pub fn model(&self) -> &str {
+        self.embedder.model()
+    }
+
+    /// Length of this service's vectors.
+    pub fn dimensions(&self) -> usize {
+        self.embedder.dimensions()
+    }
+
     pub async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
-        let resp = self
-            .client
-            .post("http://localhost:8080/embed")
-            .json(&json!({ "text": text }))
-            .send()
-            .await
-            .map_err(|e| {
-                error!("Failed to send request to embedding service: {}", e);
-                e
-            })?;
-
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let error_message = resp.text().await?;
-            error!("Embedding service returned an error: {}", error_message);
-            return Err(anyhow::anyhow!(
-                "Embedding service error: {} - {}",
-                status,
-                error_message
-            ));
+        embed_with(&self.embedder, text).await
+    }
+
+    /// The model search queries must be embedded with: that of the active
+    /// generation, so search keeps working while a re-embed into this
+    /// service's model is building. That is this service's model, or
+    /// `embedding.previous`'s until the new generation is activated.
+    pub fn query_model(&self, conn: &mut PgConnection) -> Result<&str> {
+        let Some(active) = EmbeddingGeneration::active(conn).optional()? else {
+            return Ok(self.model());
+        };
+        match self.query_embedder(&active.model) {
+            Some(embedder) => Ok(embedder.model()),
+            None => {
+                warn!(
+                    "The active embedding generation {} was built with {}, which is neither {} nor \
+                     embedding.previous; search finds nothing until it is re-embedded",
+                    active.id,
+                    active.model,
+                    self.model()
+                );
+                Ok(self.model())
+            }
         }
+    }
 
-        let embedding_data: serde_json::Value = resp.json().await.map_err(|e| {
-            error!("Failed to parse embedding service response: {}", e);
-            e
-        })?;
+    fn query_embedder(&self, model: &str) -> Option<&Arc<dyn Embedder>> {
+        std::iter::once(&self.embedder)
+            .chain(&self.previous)
+            .find(|embedder| embedder.model() == model)
+    }
+
+    /// Embeds a search query with `model`, see `query_model`, reusing the
+    /// vector of a recent identical query when `embedding.query_cache_size`
+    /// allows.
+    pub async fn embed_query(&self, model: &str, query: &str) -> Result<Vec<f32>> {
+        let embedder = self
+            .query_embedder(model)
+            .with_context(|| format!("No embedding backend is configured for {}", model))?;
+        let Some(cache) = &self.query_cache else {
+            return embed_with(embedder, query).await;
+        };
+
+        let key = format!("{}:{}", model, content_hash(query));
+        if let Some(embedding) = lock_cache(cache)?.get(&key) {
+            return Ok(embedding.clone());
+        }
+        let embedding = embed_with(embedder, query).await?;
+        lock_cache(cache)?.put(key, embedding.clone());
+        Ok(embedding)
+    }
+
+    /// Embeds `texts` in requests of at most `embedding.batch_size` texts,
+    /// returning one vector per text in order.
+    pub async fn generate_embeddings(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.config.batch_size.max(1)) {
+            let batch_embeddings = self.embedder.embed_batch(batch).await?;
+            anyhow::ensure!(
+                batch_embeddings.len() == batch.len(),
+                "{} embedding backend returned {} vectors for {} texts",
+                self.embedder.name(),
+                batch_embeddings.len(),
+                batch.len()
+            );
+            for embedding in &batch_embeddings {
+                check_dimensions(&self.embedder, embedding)?;
+            }
+            embeddings.extend(batch_embeddings);
+        }
+        Ok(embeddings)
+    }
+
+    /// Embeds `texts` like `generate_embeddings`, but takes the vectors of texts
+    /// embedded by this model before from the embedding cache, and caches the
+    /// new ones. Unchanged text is therefore never sent to the backend twice.
+    pub async fn embed_chunks(
+        &self,
+        conn: &mut PgConnection,
+        texts: &[&str],
+    ) -> Result<Vec<Vec<f32>>> {
+        let hashes: Vec<String> = texts.iter().map(|text| content_hash(text)).collect();
+        let mut vectors = CachedEmbedding::load(conn, self.model(), &hashes).unwrap_or_else(|e| {
+            warn!("Failed to read the embedding cache: {}", e);
+            HashMap::new()
+        });
+        vectors.retain(|_, vector| vector.len() == self.embedder.dimensions());
+        let cached = vectors.len();
+
+        // Each missing text is embedded once, however often it occurs
+        let mut seen = HashSet::new();
+        let missing: Vec<(&String, &str)> = hashes
+            .iter()
+            .zip(texts)
+            .filter(|(hash, _)| !vectors.contains_key(*hash) && seen.insert(*hash))
+            .map(|(hash, text)| (hash, *text))
+            .collect();
+        if !missing.is_empty() {
+            let missing_texts: Vec<&str> = missing.iter().map(|(_, text)| *text).collect();
+            let embeddings = self.generate_embeddings(&missing_texts).await?;
+            let entries: Vec<CachedEmbedding> = missing
+                .iter()
+                .zip(&embeddings)
+                .map(|((hash, _), vector)| {
+                    CachedEmbedding::new(hash.to_string(), self.model(), vector.clone())
+                })
+                .collect();
+            if let Err(e) = CachedEmbedding::store_all(conn, &entries) {
+                warn!("Failed to write the embedding cache: {}", e);
+            }
+            vectors.extend(
+                missing
+                    .into_iter()
+                    .map(|(hash, _)| hash.clone())
+                    .zip(embeddings),
+            );
+        }
+        info!(
+            "Embedded {} texts, {} distinct from the cache",
+            texts.len(),
+            cached
+        );
+
+        Ok(hashes.iter().map(|hash| vectors[hash].clone()).collect())
+    }
 
-        serde_json::from_value(embedding_data["embedding"].clone())
-            .context("Failed to extract embedding vector from response")
+    /// The article's title as a chunk of its own, followed by its Markdown
+    /// content split by a `Chunker` configured by `embedding.chunk_size` and
+    /// `embedding.chunk_overlap`.
+    pub fn chunk_article(&self, article: &Article, generation_id: i32) -> Vec<ArticleChunk> {
+        let title_chunk = ArticleChunk {
+            id: Uuid::new_v4(),
+            article_id: article.id,
+            content: article.title.clone(),
+            is_title: true,
+            embedding_id: None,
+            generation_id,
+            heading_path: Vec::new(),
+            start_offset: None,
+            end_offset: None,
+        };
+
+        let chunker = Chunker::new(self.config.chunk_size, self.config.chunk_overlap);
+        let content = article.markdown_content.as_deref().unwrap_or_default();
+        std::iter::once(title_chunk)
+            .chain(
+                chunker
+                    .chunk(content)
+                    .into_iter()
+                    .map(|chunk| ArticleChunk {
+                        id: Uuid::new_v4(),
+                        article_id: article.id,
+                        content: chunk.content,
+                        is_title: false,
+                        embedding_id: None,
+                        generation_id,
+                        heading_path: chunk.heading_path,
+                        start_offset: Some(chunk.start as i32),
+                        end_offset: Some(chunk.end as i32),
+                    }),
+            )
+            .collect()
     }
 
+    /// Chunks the article, embeds all chunks in batches and stores chunks and
+    /// embeddings together in generation `generation_id`, so an article is
+    /// never left half-embedded.
     pub async fn generate_and_store_embedding(
         &self,
         conn: &mut PgConnection,
         article: &Article,
+        generation_id: i32,
     ) -> Result<(), Box<dyn std::error::Error>> {
         info!("Generating embedding for article {}", article.id);
 
-        let chunks = article.create_chunks(500); // 500 characters per chunk
-        for mut chunk in chunks {
-            let embedding_vector = self.generate_embedding(&chunk.content).await?;
-            let embedding = Embedding::new(article.id, embedding_vector.clone());
-            let stored_embedding = self.store_embedding(conn, embedding)?;
+        let mut chunks = self.chunk_article(article, generation_id);
+        let texts: Vec<String> = chunks
+            .iter()
+            .map(|chunk| chunker::embedding_text(&chunk.heading_path, &chunk.content))
+            .collect();
+        let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+        let vectors = self.embed_chunks(conn, &texts).await?;
 
-            chunk.embedding_id = Some(stored_embedding.id);
-            chunk.store(conn)?;
+        let mut chunk_embeddings = Vec::with_capacity(vectors.len());
+        for (chunk, vector) in chunks.iter_mut().zip(vectors) {
+            let embedding = Embedding::new(article.id, self.model(), vector);
+            chunk.embedding_id = Some(embedding.id);
+            chunk_embeddings.push(embedding);
         }
+        ArticleChunk::store_with_embeddings(conn, &chunks, &chunk_embeddings).map_err(|e| {
+            error!(
+                "Failed to store embeddings for article {}: {}",
+                article.id, e
+            );
+            e
+        })?;
 
         info!(
             "Successfully generated and stored embedding for article {}",
@@ -74,42 +287,262 @@ impl EmbeddingService {
         Ok(())
     }
 
-    pub async fn reembed_all_articles(
+    /// Embeds every active article into the active generation. Articles not
+    /// started before a cancellation are skipped.
+    pub async fn generate_all_embeddings(
         &self,
-        conn: &mut PgConnection,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        use crate::schema::article_chunks::dsl::*;
-        use crate::schema::articles::dsl::*;
-        use crate::schema::embeddings::dsl::*;
-        use diesel::delete;
+        pool: &DbPool,
+        control: &JobControl,
+        progress: &JobProgress,
+    ) -> Result<(), SyncError> {
+        let mut conn = pool.get().context("Failed to get DB connection")?;
+        let generation = active_generation(&mut conn)?;
+        let article_list = Article::load_active(&mut conn).map_err(|e| {
+            error!("Error fetching articles: {:?}", e);
+            SyncError::EmbeddingError(anyhow::anyhow!("Failed to fetch articles: {}", e))
+        })?;
+        self.embed_articles(pool, article_list, generation.id, control, progress)
+            .await
+    }
+
+    /// Embeds only the active articles that have no chunks in the active
+    /// generation, e.g. ones synced or changed since the last embedding run.
+    pub async fn backfill_embeddings(
+        &self,
+        pool: &DbPool,
+        control: &JobControl,
+        progress: &JobProgress,
+    ) -> Result<(), SyncError> {
+        let mut conn = pool.get().context("Failed to get DB connection")?;
+        let generation = active_generation(&mut conn)?;
+        let article_list =
+            Article::load_active_without_embeddings(&mut conn, generation.id, self.model())
+                .map_err(|e| {
+                    error!("Error fetching articles without embeddings: {:?}", e);
+                    SyncError::EmbeddingError(anyhow::anyhow!("Failed to fetch articles: {}", e))
+                })?;
+        self.embed_articles(pool, article_list, generation.id, control, progress)
+            .await
+    }
 
-        // Step 1: Remove all existing embeddings and article chunks
-        delete(embeddings).execute(conn)?;
-        delete(article_chunks).execute(conn)?;
+    /// Embeds the articles into generation `generation_id` in batches of 50,
+    /// four batches at a time.
+    async fn embed_articles(
+        &self,
+        pool: &DbPool,
+        article_list: Vec<Article>,
+        generation_id: i32,
+        control: &JobControl,
+        progress: &JobProgress,
+    ) -> Result<(), SyncError> {
+        info!("Successfully retrieved {} articles", article_list.len());
+        let mut success_count = 0;
+        let mut error_count = 0;
+        let article_count = article_list.len();
+        progress.set_total(article_count);
+
+        // Process articles in batches of 50
+        let results = stream::iter(article_list)
+            .chunks(50)
+            .map(|chunk| async {
+                let mut batch_success = 0;
+                let mut batch_error = 0;
+                for article in chunk {
+                    if control.checkpoint().await.is_err() {
+                        break;
+                    }
+                    progress.item_started(article.id);
+                    let mut conn = match pool.get() {
+                        Ok(conn) => conn,
+                        Err(e) => {
+                            batch_error += 1;
+                            error!("Failed to get DB connection: {}", e);
+                            progress.item_failed(article.id, &e);
+                            continue;
+                        }
+                    };
+                    match self
+                        .generate_and_store_embedding(&mut conn, &article, generation_id)
+                        .await
+                    {
+                        Ok(_) => {
+                            batch_success += 1;
+                            progress.item_succeeded(article.id);
+                            info!(
+                                "Successfully generated and stored embedding for article {}",
+                                article.id
+                            );
+                        }
+                        Err(e) => {
+                            batch_error += 1;
+                            progress.item_failed(article.id, &e);
+                            error!(
+                                "Failed to generate/store embedding for article {}: {}",
+                                article.id, e
+                            );
+                        }
+                    }
+                }
+                (batch_success, batch_error)
+            })
+            .buffer_unordered(4)
+            .collect::<Vec<_>>()
+            .await;
 
-        // Step 2: Fetch all articles
-        let all_articles = articles.load::<Article>(conn)?;
+        for (batch_success, batch_error) in results {
+            success_count += batch_success;
+            error_count += batch_error;
+        }
 
-        // Step 3: Generate new embeddings for each article
-        for article in all_articles {
-            self.generate_and_store_embedding(conn, &article).await?;
+        info!("Embedding generation process completed: total_articles: {}, successful: {}, failed: {}",
+            article_count,
+            success_count,
+            error_count
+        );
+        match pool.get() {
+            Ok(mut conn) => self.evict_cache(&mut conn),
+            Err(e) => warn!(
+                "Failed to get DB connection to evict from the embedding cache: {}",
+                e
+            ),
         }
 
+        if control.is_cancelled() {
+            return Err(SyncError::EmbeddingError(anyhow::anyhow!(
+                "Embedding generation cancelled after {} articles",
+                success_count + error_count
+            )));
+        }
         Ok(())
     }
 
-    fn store_embedding(
+    /// Re-embeds every active article into a new generation while search
+    /// keeps reading the active one, then switches search over in one
+    /// transaction. The previous generation is kept for
+    /// `EmbeddingGeneration::rollback`. On failure or cancellation the new
+    /// generation is dropped and search is left untouched. Fails right away
+    /// while another re-embedding is building a generation.
+    pub async fn reembed_all_articles(
         &self,
         conn: &mut PgConnection,
-        embedding: Embedding,
-    ) -> Result<Embedding, Box<dyn std::error::Error>> {
-        let stored_embedding = embedding.store(conn).map_err(|e| {
+        control: &JobControl,
+        progress: &JobProgress,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        ensure_vector_indexes(conn, self.dimensions())?;
+        let Some(generation) = EmbeddingGeneration::start(conn, self.model())? else {
+            return Err(SyncError::EmbeddingError(anyhow::anyhow!(
+                "An embedding generation is already building"
+            ))
+            .into());
+        };
+        if let Err(e) = self
+            .build_generation(conn, &generation, control, progress)
+            .await
+        {
             error!(
-                "Failed to store embedding for article {}: {}",
-                embedding.article_id, e
+                "Re-embedding into generation {} failed: {}",
+                generation.id, e
             );
-            e
-        })?;
-        Ok(stored_embedding)
+            generation.fail(conn)?;
+            return Err(e);
+        }
+        generation.activate(conn)?;
+        self.evict_cache(conn);
+        Ok(())
+    }
+
+    /// Trims the embedding cache to `embedding.cache_size` entries. Failures
+    /// are only logged; the cache is just an optimization.
+    fn evict_cache(&self, conn: &mut PgConnection) {
+        match CachedEmbedding::evict(conn, self.config.cache_size) {
+            Ok(0) => {}
+            Ok(evicted) => info!("Evicted {} entries from the embedding cache", evicted),
+            Err(e) => warn!("Failed to evict from the embedding cache: {}", e),
+        }
     }
+
+    async fn build_generation(
+        &self,
+        conn: &mut PgConnection,
+        generation: &EmbeddingGeneration,
+        control: &JobControl,
+        progress: &JobProgress,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let all_articles = Article::load_active(conn)?;
+        progress.set_total(all_articles.len());
+        self.embed_into(conn, all_articles, generation.id, control, progress)
+            .await?;
+
+        // Articles whose content changed during the run lost their new chunks
+        let changed = Article::load_active_without_embeddings(conn, generation.id, self.model())?;
+        if !changed.is_empty() {
+            info!(
+                "Re-embedding {} articles changed during the run",
+                changed.len()
+            );
+            progress.set_total(progress.snapshot().total + changed.len());
+            self.embed_into(conn, changed, generation.id, control, progress)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Embeds the articles one at a time, stopping at the first failure.
+    async fn embed_into(
+        &self,
+        conn: &mut PgConnection,
+        articles: Vec<Article>,
+        generation_id: i32,
+        control: &JobControl,
+        progress: &JobProgress,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for article in articles {
+            control.checkpoint().await?;
+            progress.item_started(article.id);
+            if let Err(e) = self
+                .generate_and_store_embedding(conn, &article, generation_id)
+                .await
+            {
+                progress.item_failed(article.id, &e);
+                return Err(e);
+            }
+            progress.item_succeeded(article.id);
+        }
+        Ok(())
+    }
+}
+
+async fn embed_with(embedder: &Arc<dyn Embedder>, text: &str) -> Result<Vec<f32>> {
+    let embedding = embedder.embed(text).await?;
+    check_dimensions(embedder, &embedding)?;
+    Ok(embedding)
+}
+
+fn check_dimensions(embedder: &Arc<dyn Embedder>, embedding: &[f32]) -> Result<()> {
+    anyhow::ensure!(
+        embedding.len() == embedder.dimensions(),
+        "{} returned a {}-dimensional vector, expected {}",
+        embedder.model(),
+        embedding.len(),
+        embedder.dimensions()
+    );
+    Ok(())
+}
+
+fn lock_cache(
+    cache: &Mutex<LruCache<String, Vec<f32>>>,
+) -> Result<std::sync::MutexGuard<'_, LruCache<String, Vec<f32>>>> {
+    cache
+        .lock()
+        .map_err(|_| anyhow::anyhow!("Query embedding cache poisoned"))
+}
+
+fn active_generation(conn: &mut PgConnection) -> Result<EmbeddingGeneration, SyncError> {
+    EmbeddingGeneration::active(conn).map_err(|e| {
+        error!("Error fetching the active embedding generation: {:?}", e);
+        SyncError::EmbeddingError(anyhow::anyhow!(
+            "Failed to fetch the active embedding generation: {}",
+            e
+        ))
+    })
 }
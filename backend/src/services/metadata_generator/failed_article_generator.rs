@@ -11,11 +11,14 @@ use tokio::sync::Semaphore;
 use uuid::Uuid;
 
 use super::MetadataGenerator;
+use crate::job::{JobCancelled, JobControl, JobProgress};
 use crate::models::Article;
 
 impl MetadataGenerator {
     pub async fn generate_failed_article_metadata(
         &self,
+        control: &JobControl,
+        progress: &JobProgress,
     ) -> Result<(Vec<Uuid>, Vec<Uuid>), Box<dyn Error + Send + Sync>> {
         info!("Starting failed article metadata generation");
         let mut conn = self
@@ -31,6 +34,7 @@ impl MetadataGenerator {
         let total_articles = articles_to_process.len();
 
         info!("Loaded {} articles for processing", total_articles);
+        progress.set_total(total_articles);
 
         let semaphore = Arc::new(Semaphore::new(self.concurrency_limit));
         let processed_count = Arc::new(Mutex::new(0));
@@ -45,6 +49,8 @@ impl MetadataGenerator {
                 let data_processor = Arc::clone(&self.data_processor);
                 let sem = Arc::clone(&semaphore);
                 let processed_count = Arc::clone(&processed_count);
+                let control = control.clone();
+                let progress = progress.clone();
 
                 async move {
                     info!(
@@ -55,6 +61,12 @@ impl MetadataGenerator {
                     // Acquire semaphore permit
                     let _permit = sem.acquire().await.expect("Semaphore should not be closed");
 
+                    // Articles not started before a cancellation are left untouched
+                    if control.checkpoint().await.is_err() {
+                        return None;
+                    }
+                    progress.item_started(article.id);
+
                     info!(
                         "Processing metadata for article: {} (ID: {})",
                         article.title, article.id
@@ -62,9 +74,16 @@ impl MetadataGenerator {
                     let result = data_processor
                         .process_failed_article_metadata(&article)
                         .await;
+                    match &result {
+                        Ok(process_result) if process_result.is_complete() => {
+                            progress.item_succeeded(article.id)
+                        }
+                        Ok(_) => progress.item_failed(article.id, "Metadata partially generated"),
+                        Err(e) => progress.item_failed(article.id, e),
+                    }
                     let mut count = processed_count.lock().await;
                     *count += 1;
-                    (article.id, article.title.clone(), result, *count)
+                    Some((article.id, article.title.clone(), result, *count))
                 }
             })
             .buffer_unordered(self.concurrency_limit)
@@ -74,7 +93,7 @@ impl MetadataGenerator {
         let mut successful_ids = Vec::new();
         let mut failed_ids = Vec::new();
 
-        for (article_id, title, result, count) in results {
+        for (article_id, title, result, count) in results.into_iter().flatten() {
             match result {
                 Ok(process_result) => {
                     if process_result.is_complete() {
@@ -112,6 +131,10 @@ impl MetadataGenerator {
             error!("Failed to save failed article IDs: {}", e);
         }
 
+        if control.is_cancelled() {
+            return Err(Box::new(JobCancelled));
+        }
+
         Ok((successful_ids, failed_ids))
     }
 
@@ -23,4 +23,17 @@ impl MetadataGenerator {
             concurrency_limit,
         })
     }
+
+    /// Generates metadata through an existing processor and pool.
+    pub fn with_processor(
+        data_processor: Arc<DataProcessor>,
+        db_pool: Arc<DbPool>,
+        concurrency_limit: usize,
+    ) -> Self {
+        Self {
+            data_processor,
+            db_pool,
+            concurrency_limit,
+        }
+    }
 }
@@ -1,4 +1,5 @@
 pub mod ai;
+pub mod archive;
 pub mod chat;
 pub mod data_processor;
 pub mod embedding;
@@ -1,7 +1,7 @@
 use log::info;
-use std::collections::HashMap;
 use uuid::Uuid;
 
+use super::fusion::{fuse, Fusion, Ranking};
 use super::SearchService;
 use crate::models::{Article, Collection};
 
@@ -12,11 +12,16 @@ impl SearchService {
     ) -> Result<Vec<Article>, Box<dyn std::error::Error + Send + Sync>> {
         info!("Starting collection-based search for query: {}", query);
 
-        let query_embedding = self.embedding_service.generate_embedding(query).await?;
         let mut conn = self.db_pool.get()?;
+        let model = self.embedding_service.query_model(&mut conn)?;
+        let query_embedding = self.embedding_service.embed_query(model, query).await?;
 
-        let semantic_collection_results =
-            Collection::find_relevant_collection_ids(&query_embedding.clone().into(), &mut conn)?;
+        let semantic_collection_results = Collection::find_relevant_collection_ids(
+            &query_embedding.clone().into(),
+            model,
+            &mut conn,
+            &self.config,
+        )?;
 
         let relevant_collection_ids: Vec<Uuid> = semantic_collection_results
             .iter()
@@ -26,8 +31,10 @@ impl SearchService {
         // Get articles from relevant collections
         let semantic_article_results = Article::find_relevant_articles_by_collection_ids(
             &query_embedding.clone().into(),
+            model,
             &mut conn,
             &relevant_collection_ids,
+            &self.config,
         )
         .await?;
 
@@ -40,32 +47,23 @@ impl SearchService {
             .iter()
             .map(|(article, _)| article.id)
             .collect();
-        let keyword_results = Article::keyword_search(&mut conn, query, Some(&semantic_ids))?;
+        let keyword_results =
+            Article::keyword_search(&mut conn, query, Some(&semantic_ids), &self.config)?;
         info!("Keyword search found {} results", keyword_results.len());
 
-        // Combine and rank results
-        let mut combined_results = HashMap::new();
-        for (article, score) in semantic_article_results {
-            combined_results
-                .entry(article.id)
-                .or_insert((article, 0.0))
-                .1 += score;
-        }
-        for article in keyword_results {
-            combined_results
-                .entry(article.id)
-                .or_insert((article, 0.0))
-                .1 += 1.0;
-        }
-
-        // Sort and select top results
-        let mut final_results: Vec<_> = combined_results.into_values().collect();
-        final_results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        let top_results: Vec<Article> = final_results
-            .into_iter()
-            .take(5)
-            .map(|(article, _)| article)
-            .collect();
+        // Fuse the two rankings and select top results
+        let top_results: Vec<Article> = fuse(
+            vec![
+                Ranking::new(semantic_article_results, self.config.semantic_weight),
+                Ranking::new(keyword_results, self.config.keyword_weight),
+            ],
+            |article| article.id,
+            Fusion::from_config(&self.config),
+        )
+        .into_iter()
+        .take(self.config.article_results)
+        .map(|(article, _)| article)
+        .collect();
 
         info!(
             "Collection-based search completed, returning {} top results",
@@ -1,6 +1,7 @@
 use log::info;
 use tokio::task;
 
+use super::fusion::{fuse, Fusion, Ranking};
 use super::SearchService;
 use crate::models::Article;
 
@@ -12,19 +13,23 @@ impl SearchService {
         let keyword_search = task::spawn({
             let pool = self.db_pool.clone();
             let query = query.clone();
+            let limits = self.config.clone();
             async move {
                 let mut conn = pool.get().expect("couldn't get db connection from pool");
-                Article::keyword_search(&mut conn, &query, None)
+                Article::keyword_search(&mut conn, &query, None, &limits)
             }
         });
 
         let semantic_search = task::spawn({
             let pool = self.db_pool.clone();
             let embedding_service = self.embedding_service.clone();
+            let limits = self.config.clone();
             async move {
-                let query_embedding = embedding_service.generate_embedding(&query).await?;
                 let mut conn = pool.get().expect("couldn't get db connection from pool");
-                Article::find_relevant_articles(&query_embedding.into(), &mut conn).await
+                let model = embedding_service.query_model(&mut conn)?;
+                let query_embedding = embedding_service.embed_query(model, &query).await?;
+                Article::find_relevant_articles(&query_embedding.into(), model, &mut conn, &limits)
+                    .await
             }
         });
 
@@ -33,8 +38,11 @@ impl SearchService {
         let keyword_results = keyword_results??;
         let semantic_results = semantic_results??;
 
-        for article in &keyword_results {
-            info!("Keyword result: id={}, title={}", article.id, article.title);
+        for (article, rank) in &keyword_results {
+            info!(
+                "Keyword result: id={}, title={}, rank={}",
+                article.id, article.title, rank
+            );
         }
         for (article, _) in &semantic_results {
             info!(
@@ -43,29 +51,18 @@ impl SearchService {
             );
         }
 
-        // Combine and deduplicate results
-        let mut combined_results: Vec<(Article, f64)> = Vec::new();
-        let mut seen_ids = std::collections::HashSet::new();
-
-        for article in keyword_results {
-            if seen_ids.insert(article.id) {
-                combined_results.push((article, 1.0)); // Give keyword results a high score
-            }
-        }
-
-        for (article, score) in semantic_results {
-            if seen_ids.insert(article.id) {
-                combined_results.push((article, score));
-            }
-        }
-
-        // Sort combined results
-        combined_results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        combined_results.truncate(10); // Limit to top 10 results
-
-        Ok(combined_results
-            .into_iter()
-            .map(|(article, _)| article)
-            .collect())
+        // Fuse the two rankings
+        Ok(fuse(
+            vec![
+                Ranking::new(semantic_results, self.config.semantic_weight),
+                Ranking::new(keyword_results, self.config.keyword_weight),
+            ],
+            |article| article.id,
+            Fusion::from_config(&self.config),
+        )
+        .into_iter()
+        .take(self.config.combined_results)
+        .map(|(article, _)| article)
+        .collect())
     }
 }
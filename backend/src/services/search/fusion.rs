@@ -0,0 +1,101 @@
+//! Merges the result lists of different retrievers, e.g. semantic and
+//! keyword search, into one ranking.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::config::{FusionMethod, SearchConfig};
+
+/// How `fuse` turns the lists' scores into one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fusion {
+    /// Reciprocal Rank Fusion: a result scores `weight / (k + rank)` in each
+    /// list it appears in, ranks counting from 1. Only positions matter, so
+    /// lists scored on different scales need no calibration.
+    Rrf { k: f64 },
+    /// Each list's scores are min-max normalized to [0, 1] and summed, times
+    /// the list's weight. A list whose scores are all equal scores 1.
+    Weighted,
+}
+
+impl Fusion {
+    /// The method selected by `search.fusion`.
+    pub fn from_config(config: &SearchConfig) -> Self {
+        match config.fusion {
+            FusionMethod::Rrf => Fusion::Rrf { k: config.rrf_k },
+            FusionMethod::Weighted => Fusion::Weighted,
+        }
+    }
+}
+
+/// The results of one retriever with their scores, where higher is better.
+#[derive(Debug, Clone)]
+pub struct Ranking<T> {
+    pub results: Vec<(T, f64)>,
+    /// How much the list counts relative to the others.
+    pub weight: f64,
+}
+
+impl<T> Ranking<T> {
+    pub fn new(results: Vec<(T, f64)>, weight: f64) -> Self {
+        Self { results, weight }
+    }
+}
+
+/// Combines `rankings` into one list, best first, of every result that
+/// appears in any of them, identified by `key`. A result found by several
+/// retrievers is taken from the first ranking it appears in; ties keep that
+/// order too.
+pub fn fuse<T, K, F>(rankings: Vec<Ranking<T>>, key: F, fusion: Fusion) -> Vec<(T, f64)>
+where
+    K: Eq + Hash,
+    F: Fn(&T) -> K,
+{
+    let mut fused: Vec<(T, f64)> = Vec::new();
+    let mut positions: HashMap<K, usize> = HashMap::new();
+
+    for ranking in rankings {
+        let mut results = ranking.results;
+        results.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let scores = fused_scores(&results, ranking.weight, fusion);
+
+        for ((item, _), score) in results.into_iter().zip(scores) {
+            match positions.get(&key(&item)) {
+                Some(&position) => fused[position].1 += score,
+                None => {
+                    positions.insert(key(&item), fused.len());
+                    fused.push((item, score));
+                }
+            }
+        }
+    }
+
+    fused.sort_by(|a, b| b.1.total_cmp(&a.1));
+    fused
+}
+
+/// What each of `results`, sorted best first, contributes to its fused score.
+fn fused_scores<T>(results: &[(T, f64)], weight: f64, fusion: Fusion) -> Vec<f64> {
+    match fusion {
+        Fusion::Rrf { k } => (1..=results.len())
+            .map(|rank| weight / (k + rank as f64))
+            .collect(),
+        Fusion::Weighted => {
+            let (Some(max), Some(min)) = (results.first(), results.last()) else {
+                return Vec::new();
+            };
+            let (max, min) = (max.1, min.1);
+            results
+                .iter()
+                .map(|(_, score)| {
+                    let normalized = if max > min {
+                        (score - min) / (max - min)
+                    } else {
+                        1.0
+                    };
+                    weight * normalized
+                })
+                .collect()
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! Finds the words of a passage that match a search query, so the frontend
+//! can mark them.
+
+/// Words too common to be worth marking.
+const STOPWORDS: &[&str] = &[
+    "and", "are", "can", "does", "for", "from", "how", "not", "the", "this", "what", "when",
+    "where", "which", "who", "why", "with", "you", "your",
+];
+
+/// Query terms shorter than this are ignored.
+const MIN_TERM_CHARS: usize = 3;
+
+/// Terms at least this long also match longer words they start, so "refund"
+/// marks "refunds" and "refunded".
+const MIN_PREFIX_CHARS: usize = 4;
+
+/// Character (not byte) ranges of the words of `text` that match a term of
+/// `query`, ignoring case, in order and without overlaps.
+pub fn highlight(text: &str, query: &str) -> Vec<(usize, usize)> {
+    let terms: Vec<String> = words(query)
+        .into_iter()
+        .map(|(word, _)| word)
+        .filter(|term| {
+            term.chars().count() >= MIN_TERM_CHARS && !STOPWORDS.contains(&term.as_str())
+        })
+        .collect();
+    if terms.is_empty() {
+        return Vec::new();
+    }
+
+    words(text)
+        .into_iter()
+        .filter(|(word, _)| {
+            terms.iter().any(|term| {
+                word == term
+                    || (term.chars().count() >= MIN_PREFIX_CHARS && word.starts_with(term.as_str()))
+            })
+        })
+        .map(|(_, range)| range)
+        .collect()
+}
+
+/// The lowercased alphanumeric runs of `text` with their character ranges.
+fn words(text: &str) -> Vec<(String, (usize, usize))> {
+    let mut words = Vec::new();
+    let mut current: Option<(String, usize)> = None;
+    let mut end = 0;
+    for (index, c) in text.chars().enumerate() {
+        end = index + 1;
+        if c.is_alphanumeric() {
+            current
+                .get_or_insert_with(|| (String::new(), index))
+                .0
+                .extend(c.to_lowercase());
+        } else if let Some((word, start)) = current.take() {
+            words.push((word, (start, index)));
+        }
+    }
+    if let Some((word, start)) = current {
+        words.push((word, (start, end)));
+    }
+    words
+}
@@ -1,26 +1,55 @@
+use anyhow::Result;
 use log::info;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use super::{AIService, EmbeddingService};
+use crate::config::{self, SearchConfig};
 use crate::db::DbPool;
+use crate::models::articles::query::ChunkMatch;
 
 pub mod collection_search;
 pub mod combined_search;
+pub mod fusion;
+pub mod highlight;
 pub mod two_stage_retrieval;
 
 pub struct SearchService {
     embedding_service: Arc<EmbeddingService>,
     db_pool: Arc<DbPool>,
     ai_service: Arc<AIService>,
+    config: SearchConfig,
 }
 
 impl SearchService {
-    pub fn new(db_pool: Arc<DbPool>, ai_service: Arc<AIService>) -> Self {
+    pub fn new(db_pool: Arc<DbPool>, ai_service: Arc<AIService>) -> Result<Self> {
+        Self::with_config(db_pool, ai_service, config::get().search.clone())
+    }
+
+    pub fn with_config(
+        db_pool: Arc<DbPool>,
+        ai_service: Arc<AIService>,
+        config: SearchConfig,
+    ) -> Result<Self> {
+        Ok(Self::with_services(
+            db_pool,
+            ai_service,
+            Arc::new(EmbeddingService::new()?),
+            config,
+        ))
+    }
+
+    pub fn with_services(
+        db_pool: Arc<DbPool>,
+        ai_service: Arc<AIService>,
+        embedding_service: Arc<EmbeddingService>,
+        config: SearchConfig,
+    ) -> Self {
         SearchService {
-            embedding_service: Arc::new(EmbeddingService::new()),
+            embedding_service,
             db_pool,
             ai_service,
+            config,
         }
     }
 
@@ -62,6 +91,43 @@ pub struct SearchResult {
 pub struct ArticleResult {
     pub id: uuid::Uuid,
     pub title: String,
+    /// The best passage, or the whole article when it has none.
     pub content: String,
     pub slug: String,
+    /// Best-matching passages, best first.
+    pub passages: Vec<PassageResult>,
+}
+
+/// A chunk of an article that matched the query, located in the article's
+/// Markdown so the frontend can link to it.
+#[derive(Debug, Serialize)]
+pub struct PassageResult {
+    pub content: String,
+    /// The innermost heading the passage sits under.
+    pub heading: Option<String>,
+    pub heading_path: Vec<String>,
+    /// Character range of the passage in the article's Markdown.
+    pub start_offset: Option<i32>,
+    pub end_offset: Option<i32>,
+    pub score: f64,
+    /// Character ranges in `content` of words matching the query.
+    pub highlights: Vec<[usize; 2]>,
+}
+
+impl PassageResult {
+    pub fn new(chunk: ChunkMatch, query: &str) -> Self {
+        let highlights = highlight::highlight(&chunk.content, query)
+            .into_iter()
+            .map(|(start, end)| [start, end])
+            .collect();
+        PassageResult {
+            heading: chunk.heading_path.last().cloned(),
+            content: chunk.content,
+            heading_path: chunk.heading_path,
+            start_offset: chunk.start_offset,
+            end_offset: chunk.end_offset,
+            score: chunk.similarity,
+            highlights,
+        }
+    }
 }
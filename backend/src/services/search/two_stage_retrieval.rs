@@ -1,61 +1,60 @@
 use log::info;
-use std::collections::HashMap;
 use uuid::Uuid;
 
+use super::fusion::{fuse, Fusion, Ranking};
 use super::SearchService;
+use crate::models::articles::query::ChunkMatch;
 use crate::models::Article;
 
 impl SearchService {
+    /// Returns the best articles for `query` with their best-matching chunks.
     pub async fn two_stage_retrieval(
         &self,
         query: &str,
-    ) -> Result<Vec<Article>, Box<dyn std::error::Error + Send + Sync>> {
+    ) -> Result<Vec<(Article, Vec<ChunkMatch>)>, Box<dyn std::error::Error + Send + Sync>> {
         info!("Starting two-stage retrieval for query: {}", query);
 
         // Stage 1: Semantic search
         info!("Stage 1: Performing semantic search");
-        let query_embedding = self.embedding_service.generate_embedding(query).await?;
         let mut conn = self.db_pool.get()?;
+        let model = self.embedding_service.query_model(&mut conn)?;
+        let query_embedding = self.embedding_service.embed_query(model, query).await?;
         let semantic_results =
-            Article::find_relevant_articles(&query_embedding.into(), &mut conn).await?;
+            Article::find_relevant_chunks(&query_embedding.into(), model, &mut conn, &self.config)
+                .await?;
         info!("Semantic search found {} results", semantic_results.len());
 
         // Stage 2: Keyword search on semantic results
         info!("Stage 2: Performing keyword search on semantic results");
         let semantic_ids: Vec<Uuid> = semantic_results
             .iter()
-            .map(|(article, _)| article.id)
+            .map(|(article, _, _)| article.id)
             .collect();
-        let keyword_results = Article::keyword_search(&mut conn, query, Some(&semantic_ids))?;
+        let keyword_results =
+            Article::keyword_search(&mut conn, query, Some(&semantic_ids), &self.config)?;
         info!("Keyword search found {} results", keyword_results.len());
 
-        // Combine and rank results
-        let mut combined_results = HashMap::new();
-        for (article, score) in semantic_results {
-            combined_results
-                .entry(article.id)
-                .or_insert((article, 0.0))
-                .1 += score;
-        }
-        for article in keyword_results {
-            combined_results
-                .entry(article.id)
-                .or_insert((article, 0.0))
-                .1 += 1.0;
-        }
-
-        // Sort and select top results
-        let mut final_results: Vec<_> = combined_results.into_values().collect();
-        final_results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        // let top_results: Vec<String> = final_results.into_iter()
-        //     .take(5)
-        //     .map(|(article, _)| article.markdown_content.unwrap_or(article.title))
-        //     .collect();
-        let top_results: Vec<Article> = final_results
+        // Fuse the two rankings and select top results
+        let semantic = semantic_results
+            .into_iter()
+            .map(|(article, score, passages)| ((article, passages), score))
+            .collect();
+        let keyword = keyword_results
             .into_iter()
-            .take(5)
-            .map(|(article, _)| article)
+            .map(|(article, rank)| ((article, Vec::new()), rank))
             .collect();
+        let top_results: Vec<(Article, Vec<ChunkMatch>)> = fuse(
+            vec![
+                Ranking::new(semantic, self.config.semantic_weight),
+                Ranking::new(keyword, self.config.keyword_weight),
+            ],
+            |(article, _)| article.id,
+            Fusion::from_config(&self.config),
+        )
+        .into_iter()
+        .take(self.config.article_results)
+        .map(|(result, _)| result)
+        .collect();
 
         info!(
             "Two-stage retrieval completed, returning {} top results",
@@ -0,0 +1,7 @@
+use std::time::Duration;
+
+/// Exponential backoff: `base * 2^(attempts - 1)`, capped at `max`.
+pub fn backoff_delay(base: Duration, max: Duration, attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 31) as u32;
+    base.saturating_mul(2u32.saturating_pow(exponent)).min(max)
+}
@@ -0,0 +1,6 @@
+use sha2::{Digest, Sha256};
+
+/// SHA-256 of `content`, hex-encoded: 64 characters.
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
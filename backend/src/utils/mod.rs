@@ -0,0 +1,7 @@
+pub mod backoff;
+pub mod content_hash;
+pub mod ollama_load_balancer;
+
+pub use backoff::backoff_delay;
+pub use content_hash::content_hash;
+pub use ollama_load_balancer::OllamaLoadBalancer;
@@ -13,14 +13,14 @@ pub struct OllamaLoadBalancer {
 
 impl OllamaLoadBalancer {
     pub fn new(server_ports: &[u16], threads_per_server: usize) -> Self {
+        Self::with_host("http://localhost", server_ports, threads_per_server)
+    }
+
+    /// One server per port of `host`, e.g. `"http://localhost"`, used in turn.
+    pub fn with_host(host: &str, server_ports: &[u16], threads_per_server: usize) -> Self {
         let servers: Vec<Arc<Mutex<Ollama>>> = server_ports
             .iter()
-            .map(|&port| {
-                Arc::new(Mutex::new(Ollama::new(
-                    format!("http://localhost:{}", port),
-                    port,
-                )))
-            })
+            .map(|&port| Arc::new(Mutex::new(Ollama::new(host.to_string(), port))))
             .collect();
 
         let total_threads = servers.len() * threads_per_server;
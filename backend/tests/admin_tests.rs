@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test as actix_test, web, App};
+    use backend::config::{Config, JobsConfig};
+    use backend::db::DbPool;
+    use backend::job::JobQueue;
+    use backend::routes::admin::reject_unless_admin;
+    use backend::routes::archive::export;
+    use backend::routes::embed::{reembed_all_articles, rollback_embedding_generation};
+    use diesel::r2d2::{ConnectionManager, Pool};
+    use std::sync::Arc;
+
+    fn config(admin_token: Option<&str>) -> Config {
+        let mut config = Config::default();
+        config.server.admin_token = admin_token.map(str::to_string);
+        config
+    }
+
+    fn status(config: &Config, authorization: Option<&str>) -> Option<StatusCode> {
+        let mut request = actix_test::TestRequest::post().uri("/import");
+        if let Some(authorization) = authorization {
+            request = request.insert_header(("Authorization", authorization));
+        }
+        reject_unless_admin(&request.to_http_request(), config).map(|response| response.status())
+    }
+
+    #[test]
+    fn test_admin_routes_are_refused_without_a_configured_token() {
+        assert_eq!(
+            status(&config(None), Some("Bearer anything")),
+            Some(StatusCode::SERVICE_UNAVAILABLE)
+        );
+        assert_eq!(
+            status(&config(Some("")), Some("Bearer ")),
+            Some(StatusCode::SERVICE_UNAVAILABLE)
+        );
+    }
+
+    #[test]
+    fn test_admin_routes_require_the_bearer_token() {
+        let config = config(Some("s3cret"));
+
+        assert_eq!(status(&config, None), Some(StatusCode::UNAUTHORIZED));
+        assert_eq!(
+            status(&config, Some("Bearer wrong")),
+            Some(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(
+            status(&config, Some("s3cret")),
+            Some(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(status(&config, Some("Bearer s3cret")), None);
+    }
+
+    /// A pool that never connects; rejected requests don't reach the database.
+    fn unused_pool() -> Arc<DbPool> {
+        Arc::new(Pool::builder().build_unchecked(ConnectionManager::new("postgres://unused")))
+    }
+
+    #[actix_web::test]
+    async fn test_export_requires_the_admin_token() {
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(unused_pool()))
+                .app_data(web::Data::new(Arc::new(config(Some("s3cret")))))
+                .service(export),
+        )
+        .await;
+
+        let request = actix_test::TestRequest::get().uri("/export").to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn test_embedding_generation_changes_require_the_admin_token() {
+        let job_queue = JobQueue::without_workers(unused_pool(), &JobsConfig::default());
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(unused_pool()))
+                .app_data(web::Data::new(Arc::new(job_queue)))
+                .app_data(web::Data::new(Arc::new(config(Some("s3cret")))))
+                .service(reembed_all_articles)
+                .service(rollback_embedding_generation),
+        )
+        .await;
+
+        for uri in ["/reembed-all", "/embedding-generations/rollback"] {
+            let request = actix_test::TestRequest::post()
+                .uri(uri)
+                .insert_header(("Authorization", "Bearer wrong"))
+                .to_request();
+            let response = actix_test::call_service(&app, request).await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", uri);
+        }
+    }
+}
@@ -1,13 +1,65 @@
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
+    use chrono::{TimeZone, Utc};
     use dotenv::dotenv;
-    use mockito::Server;
-    use serde_json::json;
+    use mockito::{Matcher, Server};
+    use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+    use serde_json::{json, Value};
+    use std::time::Duration;
     use tokio;
 
     use backend::models::Collection;
-    use backend::services::data_processor::api_client::ApiClient;
+    use backend::services::data_processor::api_client::{
+        parse_retry_after, ApiClient, ApiClientConfig, ArticleListing,
+    };
+    use backend::services::data_processor::rate_limiter::TokenBucket;
+
+    fn test_config(max_retries: u32) -> ApiClientConfig {
+        ApiClientConfig {
+            max_retries,
+            retry_base_delay: Duration::from_millis(10),
+            retry_max_delay: Duration::from_millis(50),
+            ..ApiClientConfig::default()
+        }
+    }
+
+    fn test_collection() -> Collection {
+        Collection::new(
+            "Test Collection".to_string(),
+            None,
+            "test-collection".to_string(),
+            "5214c77c45667acd25394b51".to_string(),
+        )
+    }
+
+    fn articles_page(page: i32, pages: i32, article_id: &str) -> Value {
+        json!({
+            "articles": {
+                "page": page,
+                "pages": pages,
+                "count": 1,
+                "items": [
+                    {
+                        "id": article_id,
+                        "number": 121,
+                        "collectionId": "5214c77c45667acd25394b51",
+                        "status": "published",
+                        "hasDraft": false,
+                        "name": "My Article",
+                        "publicUrl": "https://docs.helpscout.net/article/100-my-article",
+                        "popularity": 4.3,
+                        "viewCount": 237,
+                        "createdBy": 73423,
+                        "updatedBy": null,
+                        "createdAt": "2013-08-21T19:34:13Z",
+                        "updatedAt": null,
+                        "lastPublishedAt": "2013-08-21T19:34:13Z"
+                    }
+                ]
+            }
+        })
+    }
 
     #[tokio::test]
     async fn test_parse_collection() -> Result<()> {
@@ -122,6 +174,7 @@ mod tests {
             article.html_content,
             Some("This is the text of the article.".to_string())
         );
+        assert_eq!(article.updated_at.to_rfc3339(), "2013-08-22T21:40:56+00:00");
 
         Ok(())
     }
@@ -242,4 +295,197 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_retries_rate_limited_request_after_retry_after() -> Result<()> {
+        let mut server = Server::new_async().await;
+        let path = "/v1/collections/5214c77c45667acd25394b51/articles";
+
+        let rate_limited = server
+            .mock("GET", path)
+            .match_query(Matcher::Missing)
+            .with_status(429)
+            .with_header("Retry-After", "0")
+            .expect(1)
+            .create_async()
+            .await;
+        let ok = server
+            .mock("GET", path)
+            .match_query(Matcher::Missing)
+            .with_status(200)
+            .with_body(articles_page(1, 1, "5215163545667acd25394b5c").to_string())
+            .expect(1)
+            .create_async()
+            .await;
+
+        let api_client = ApiClient::with_config(
+            Some(server.url()),
+            Some("test_api_key".to_string()),
+            test_config(2),
+        )?;
+        let article_refs = api_client.get_list_articles(&test_collection()).await?;
+
+        assert_eq!(article_refs.len(), 1);
+        rate_limited.assert_async().await;
+        ok.assert_async().await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fails_on_retry_after_above_the_maximum() -> Result<()> {
+        let mut server = Server::new_async().await;
+
+        let rate_limited = server
+            .mock("GET", "/v1/articles/521632244566c845e582652d")
+            .with_status(429)
+            .with_header("Retry-After", "86400")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let api_client = ApiClient::with_config(
+            Some(server.url()),
+            Some("test_api_key".to_string()),
+            test_config(3),
+        )?;
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            api_client.get_article("521632244566c845e582652d", &test_collection()),
+        )
+        .await
+        .expect("waited for the Retry-After");
+
+        assert!(result.is_err());
+        rate_limited.assert_async().await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_client_errors() -> Result<()> {
+        let mut server = Server::new_async().await;
+
+        let not_found = server
+            .mock("GET", "/v1/articles/missing")
+            .with_status(404)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let api_client = ApiClient::with_config(
+            Some(server.url()),
+            Some("test_api_key".to_string()),
+            test_config(3),
+        )?;
+        let result = api_client.get_article("missing", &test_collection()).await;
+
+        assert!(result.is_err());
+        not_found.assert_async().await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resume_list_articles_after_failed_page() -> Result<()> {
+        let mut server = Server::new_async().await;
+        let path = "/v1/collections/5214c77c45667acd25394b51/articles";
+
+        let _page_one = server
+            .mock("GET", path)
+            .match_query(Matcher::Missing)
+            .with_status(200)
+            .with_body(articles_page(1, 2, "first-article").to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        let failing_page_two = server
+            .mock("GET", path)
+            .match_query(Matcher::UrlEncoded("page".into(), "2".into()))
+            .with_status(503)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let api_client = ApiClient::with_config(
+            Some(server.url()),
+            Some("test_api_key".to_string()),
+            test_config(1),
+        )?;
+        let mut listing = ArticleListing::new(&test_collection());
+
+        assert!(api_client.resume_list_articles(&mut listing).await.is_err());
+        failing_page_two.assert_async().await;
+        assert_eq!(listing.next_page, 2);
+        assert!(!listing.complete);
+        assert_eq!(listing.article_refs.len(), 1);
+
+        failing_page_two.remove_async().await;
+        let _page_two = server
+            .mock("GET", path)
+            .match_query(Matcher::UrlEncoded("page".into(), "2".into()))
+            .with_status(200)
+            .with_body(articles_page(2, 2, "second-article").to_string())
+            .expect(1)
+            .create_async()
+            .await;
+
+        api_client.resume_list_articles(&mut listing).await?;
+
+        assert!(listing.complete);
+        let ids: Vec<&str> = listing.article_refs.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["first-article", "second-article"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = Utc.with_ymd_and_hms(2015, 10, 21, 7, 27, 0).unwrap();
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers, now), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(
+            parse_retry_after(&headers, now),
+            Some(Duration::from_secs(120))
+        );
+
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(
+            parse_retry_after(&headers, now),
+            Some(Duration::from_secs(60))
+        );
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("soon"));
+        assert_eq!(parse_retry_after(&headers, now), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_token_bucket_waits_for_refill() {
+        let bucket = TokenBucket::per_minute(60, 2).unwrap();
+        let start = tokio::time::Instant::now();
+
+        bucket.acquire().await;
+        bucket.acquire().await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        bucket.acquire().await;
+        assert!(start.elapsed() >= Duration::from_secs(1));
+
+        bucket.block_for(Duration::from_secs(30)).await;
+        bucket.acquire().await;
+        assert!(start.elapsed() >= Duration::from_secs(32));
+    }
+
+    #[test]
+    fn test_token_bucket_rejects_non_positive_rates() {
+        assert!(TokenBucket::per_minute(0, 2).is_err());
+        assert!(TokenBucket::per_minute(60, 0).is_err());
+        assert!(TokenBucket::new(1, -1.0).is_err());
+        assert!(TokenBucket::new(1, f64::NAN).is_err());
+    }
 }
@@ -0,0 +1,147 @@
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use chrono::Utc;
+    use flate2::{write::GzEncoder, Compression};
+    use pgvector::Vector;
+    use std::io::{BufRead, Write};
+    use uuid::Uuid;
+
+    use backend::errors::ArchiveError;
+    use backend::models::{Article, Collection};
+    use backend::services::archive::{
+        archive_reader, parse_record, ArchiveHeader, ArchiveRecord, ChunkRecord, ARCHIVE_FORMAT,
+        ARCHIVE_VERSION,
+    };
+
+    fn header(version: u32) -> ArchiveHeader {
+        ArchiveHeader {
+            format: ARCHIVE_FORMAT.to_string(),
+            version,
+            exported_at: Utc::now(),
+            includes_embeddings: true,
+        }
+    }
+
+    #[test]
+    fn test_records_round_trip() -> Result<()> {
+        let mut collection = Collection::new(
+            "Billing".to_string(),
+            Some("Invoices and payments".to_string()),
+            "billing".to_string(),
+            "col-1".to_string(),
+        );
+        collection.keywords_embedding = Some(Vector::from(vec![0.5, 0.25]));
+        let mut article = Article::new(
+            collection.id,
+            "col-1".to_string(),
+            Some("art-1".to_string()),
+            "Refunds".to_string(),
+            "refunds".to_string(),
+            None,
+        );
+        article.markdown_content = Some("# Refunds\n\nWithin 30 days.".to_string());
+        article.keywords = Some(vec![Some("refund".to_string())]);
+        let chunk = ChunkRecord {
+            article_id: article.id,
+            content: "Within 30 days.".to_string(),
+            is_title: false,
+            embedding: Some(Vector::from(vec![1.0, 0.0, -1.0])),
+            embedding_model: Some("all-MiniLM-L6-v2".to_string()),
+            heading_path: vec!["Billing".to_string(), "Refunds".to_string()],
+            start_offset: Some(0),
+            end_offset: Some(15),
+        };
+
+        let line = serde_json::to_string(&ArchiveRecord::Collection(collection.clone()))?;
+        assert!(line.starts_with(r#"{"kind":"collection""#));
+        match parse_record(2, &line)? {
+            ArchiveRecord::Collection(parsed) => {
+                assert_eq!(parsed.id, collection.id);
+                assert_eq!(parsed.description, collection.description);
+                assert_eq!(parsed.keywords_embedding, collection.keywords_embedding);
+            }
+            other => panic!("Expected a collection, got {:?}", other),
+        }
+
+        let line = serde_json::to_string(&ArchiveRecord::Article(article.clone()))?;
+        match parse_record(3, &line)? {
+            ArchiveRecord::Article(parsed) => {
+                assert_eq!(parsed.id, article.id);
+                assert_eq!(parsed.markdown_content, article.markdown_content);
+                assert_eq!(parsed.keywords, article.keywords);
+            }
+            other => panic!("Expected an article, got {:?}", other),
+        }
+
+        let line = serde_json::to_string(&ArchiveRecord::Chunk(chunk.clone()))?;
+        match parse_record(4, &line)? {
+            ArchiveRecord::Chunk(parsed) => {
+                assert_eq!(parsed.article_id, chunk.article_id);
+                assert_eq!(parsed.embedding, chunk.embedding);
+                assert_eq!(parsed.embedding_model, chunk.embedding_model);
+                assert_eq!(parsed.heading_path, chunk.heading_path);
+                assert_eq!(parsed.start_offset, chunk.start_offset);
+            }
+            other => panic!("Expected a chunk, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_record_reports_line() {
+        let error = parse_record(7, r#"{"kind":"article","id":"not-a-uuid"}"#).unwrap_err();
+
+        assert!(matches!(error, ArchiveError::InvalidRecord { line: 7, .. }));
+    }
+
+    #[test]
+    fn test_header_validation() {
+        assert!(header(ARCHIVE_VERSION).validate().is_ok());
+        assert!(header(0).validate().is_ok());
+        assert!(matches!(
+            header(ARCHIVE_VERSION + 1).validate(),
+            Err(ArchiveError::UnsupportedVersion { .. })
+        ));
+
+        let mut foreign = header(ARCHIVE_VERSION);
+        foreign.format = "other-archive".to_string();
+        assert!(matches!(
+            foreign.validate(),
+            Err(ArchiveError::UnsupportedVersion { .. })
+        ));
+    }
+
+    #[test]
+    fn test_archive_reader_accepts_gzip_and_plain() -> Result<()> {
+        let content = format!(
+            "{}\n{}\n",
+            serde_json::to_string(&ArchiveRecord::Header(header(ARCHIVE_VERSION)))?,
+            serde_json::to_string(&ArchiveRecord::Chunk(ChunkRecord {
+                article_id: Uuid::new_v4(),
+                content: "Title".to_string(),
+                is_title: true,
+                embedding: None,
+                embedding_model: None,
+                heading_path: Vec::new(),
+                start_offset: None,
+                end_offset: None,
+            }))?
+        );
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content.as_bytes())?;
+        let compressed = encoder.finish()?;
+
+        for input in [compressed.as_slice(), content.as_bytes()] {
+            let lines = archive_reader(input)?
+                .lines()
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(lines.len(), 2);
+            assert!(matches!(
+                parse_record(1, &lines[0])?,
+                ArchiveRecord::Header(ref parsed) if parsed.version == ARCHIVE_VERSION
+            ));
+        }
+        Ok(())
+    }
+}
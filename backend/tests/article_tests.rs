@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
-    use backend::models::Article;
+    use backend::config::SearchConfig;
+    use backend::models::{Article, Collection};
     use backend::services::EmbeddingService;
     use diesel::connection::Connection;
     use diesel::pg::PgConnection;
@@ -18,14 +19,14 @@ mod tests {
     #[tokio::test]
     async fn test_find_relevant_articles() {
         let mut conn = establish_connection();
-        let embedding_service = EmbeddingService::new();
+        let embedding_service = EmbeddingService::new().unwrap();
 
         // Create a test embedding
         let test_embedding = embedding_service.generate_embedding("How can I create an Organization?").await.unwrap();
         let test_embedding = Vector::from(test_embedding);
 
         // Call the function
-        let results = Article::find_relevant_articles(&test_embedding, &mut conn).await.unwrap();
+        let results = Article::find_relevant_articles(&test_embedding, embedding_service.model(), &mut conn, &SearchConfig::default()).await.unwrap();
 
         // Assert that we got results
         assert!(!results.is_empty(), "No results returned from find_relevant_articles");
@@ -42,4 +43,63 @@ mod tests {
             println!("Article: {}, Similarity: {}", article.title, similarity);
         }
     }
+
+    #[test]
+    fn test_keyword_search_ranks_matches() {
+        let mut conn = establish_connection();
+        conn.test_transaction::<_, diesel::result::Error, _>(|conn| {
+            let collection = Collection::new(
+                "Ranking".to_string(),
+                None,
+                "ranking".to_string(),
+                "ranking-collection".to_string(),
+            );
+            collection.store(conn)?;
+            let mut fixture = |title: &str, slug: &str, body: &str| {
+                let mut article = Article::new(
+                    collection.id,
+                    collection.helpscout_collection_id.clone(),
+                    None,
+                    title.to_string(),
+                    slug.to_string(),
+                    None,
+                );
+                article.markdown_content = Some(body.to_string());
+                article.store(conn)
+            };
+            let in_title = fixture(
+                "Create an organization",
+                "create-an-organization",
+                "Open the dashboard and follow the steps.",
+            )?;
+            let in_body = fixture(
+                "Team settings",
+                "team-settings",
+                "Admins can create an organization from the dashboard.",
+            )?;
+            let unrelated = fixture("Refunds", "refunds", "How refunds are issued.")?;
+            let ids = [in_title.id, in_body.id, unrelated.id];
+            let limits = SearchConfig::default();
+
+            let results = Article::keyword_search(conn, "create organization", Some(&ids), &limits)?;
+
+            // Title matches outrank body matches; articles missing a word are left out
+            let found: Vec<_> = results.iter().map(|(article, _)| article.id).collect();
+            assert_eq!(found, vec![in_title.id, in_body.id]);
+            for (_, rank) in &results {
+                assert!((0.0..1.0).contains(rank), "Rank {} is outside [0, 1)", rank);
+            }
+            assert!(results[0].1 > results[1].1);
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_keyword_search_ignores_empty_query() {
+        let mut conn = establish_connection();
+
+        let results = Article::keyword_search(&mut conn, "   ", None, &SearchConfig::default()).unwrap();
+
+        assert!(results.is_empty());
+    }
 }
\ No newline at end of file
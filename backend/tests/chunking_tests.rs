@@ -0,0 +1,150 @@
+#[cfg(test)]
+mod tests {
+    use backend::services::embedding::chunker::{estimate_tokens, Chunk, Chunker};
+
+    /// `content` must be the text between the chunk's character offsets.
+    fn assert_offsets(markdown: &str, chunks: &[Chunk]) {
+        for chunk in chunks {
+            let text: String = markdown
+                .chars()
+                .skip(chunk.start)
+                .take(chunk.end - chunk.start)
+                .collect();
+            assert_eq!(text, chunk.content);
+        }
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("a an the"), 3);
+        assert_eq!(estimate_tokens("configuration"), 4);
+    }
+
+    #[test]
+    fn test_chunks_carry_heading_path() {
+        let markdown = "Intro text.\n\n# Billing\n\nHow billing works.\n\n## Refunds\n\nWithin 30 days.\n\n# Teams\n\nInvite people.";
+        let chunks = Chunker::new(256, 0).chunk(markdown);
+
+        let summary: Vec<(&str, Vec<String>)> = chunks
+            .iter()
+            .map(|chunk| (chunk.content.as_str(), chunk.heading_path.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Intro text.", vec![]),
+                ("How billing works.", vec!["Billing".to_string()]),
+                (
+                    "Within 30 days.",
+                    vec!["Billing".to_string(), "Refunds".to_string()]
+                ),
+                ("Invite people.", vec!["Teams".to_string()]),
+            ]
+        );
+        assert_offsets(markdown, &chunks);
+    }
+
+    #[test]
+    fn test_setext_headings_from_html2md() {
+        let markdown =
+            "Billing\n==========\n\nHow billing works.\n\nRefunds\n----------\n\nWithin 30 days.";
+        let chunks = Chunker::new(256, 0).chunk(markdown);
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].heading_path, vec!["Billing"]);
+        assert_eq!(chunks[1].heading_path, vec!["Billing", "Refunds"]);
+        assert_eq!(chunks[1].content, "Within 30 days.");
+    }
+
+    #[test]
+    fn test_blocks_are_packed_up_to_the_budget() {
+        let markdown = "One two three.\n\nFour five six.\n\nSeven eight nine.";
+        let chunks = Chunker::new(8, 0).chunk(markdown);
+
+        let contents: Vec<&str> = chunks.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            vec!["One two three.\n\nFour five six.", "Seven eight nine."]
+        );
+        assert_offsets(markdown, &chunks);
+    }
+
+    #[test]
+    fn test_budget_counts_the_heading_prefix() {
+        let markdown =
+            "One two three.\n\nFour five six.\n\n# Refunds\n\nOne two three.\n\nFour five six.";
+        let chunks = Chunker::new(8, 0).chunk(markdown);
+
+        let contents: Vec<&str> = chunks.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            vec![
+                "One two three.\n\nFour five six.",
+                "One two three.",
+                "Four five six."
+            ]
+        );
+        for chunk in &chunks {
+            assert!(estimate_tokens(&chunk.embedding_text()) <= 8);
+        }
+    }
+
+    #[test]
+    fn test_list_items_are_not_split() {
+        let markdown =
+            "1. Open Settings\n   and choose Billing\n2. Click Refund\n3. Confirm the refund";
+        let chunks = Chunker::new(9, 0).chunk(markdown);
+
+        let contents: Vec<&str> = chunks.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            vec![
+                "1. Open Settings\n   and choose Billing",
+                "2. Click Refund",
+                "3. Confirm the refund"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_code_blocks_and_tables_stay_intact() {
+        let code = "```sh\ncurl -X POST https://example.com/api/refunds\n\n  --data amount=10\n```";
+        let table = "| Plan | Price |\n|------|-------|\n| Team | $10 |\n| Business | $20 |";
+        let markdown = format!("Run this:\n\n{}\n\nPrices:\n\n{}", code, table);
+        let chunks = Chunker::new(4, 2).chunk(&markdown);
+
+        assert!(chunks.iter().any(|chunk| chunk.content == code));
+        assert!(chunks.iter().any(|chunk| chunk.content == table));
+        assert_offsets(&markdown, &chunks);
+    }
+
+    #[test]
+    fn test_long_paragraphs_split_with_overlap() {
+        let words: Vec<String> = (0..30).map(|i| format!("w{}", i)).collect();
+        let markdown = words.join(" ");
+        let chunks = Chunker::new(10, 3).chunk(&markdown);
+
+        assert!(chunks.len() > 3);
+        for chunk in &chunks {
+            assert!(estimate_tokens(&chunk.content) <= 10);
+        }
+        for pair in chunks.windows(2) {
+            let previous: Vec<&str> = pair[0].content.split(' ').collect();
+            let next: Vec<&str> = pair[1].content.split(' ').take(3).collect();
+            assert_eq!(&previous[previous.len() - 3..], next.as_slice());
+        }
+        assert!(chunks.last().unwrap().content.ends_with("w29"));
+        assert_offsets(&markdown, &chunks);
+    }
+
+    #[test]
+    fn test_offsets_count_characters() {
+        let markdown = "# Überblick\n\nRückerstattung in 30 Tagen.";
+        let chunks = Chunker::new(256, 0).chunk(markdown);
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].start, 13);
+        assert_offsets(markdown, &chunks);
+    }
+}
@@ -0,0 +1,141 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use backend::config::Config;
+    use backend::errors::ConfigError;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key| vars.get(key).cloned()
+    }
+
+    fn valid() -> Config {
+        Config::from_toml(
+            r#"
+            [database]
+            url = "postgres://localhost/helpdoc"
+
+            [helpscout]
+            base_url = "https://docsapi.helpscout.net"
+            api_key = "secret"
+            "#,
+            env(&[]),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_empty_file_uses_defaults() {
+        let config = Config::from_toml("", env(&[])).unwrap();
+
+        assert_eq!(config, Config::default());
+        assert_eq!(config.server.port, 3000);
+        assert_eq!(config.embedding.chunk_size, 200);
+        assert_eq!(config.ai.model, "llama3.1:latest");
+        assert_eq!(config.search.chunk_candidates, 20);
+    }
+
+    #[test]
+    fn test_file_overrides_section() {
+        let config = Config::from_toml(
+            r#"
+            [server]
+            port = 8000
+
+            [embedding]
+            url = "http://embedder:9000/"
+            "#,
+            env(&[]),
+        )
+        .unwrap();
+
+        assert_eq!(config.server.port, 8000);
+        assert_eq!(config.server.host, "127.0.0.1");
+        assert_eq!(
+            config.embedding.endpoint("embed"),
+            "http://embedder:9000/embed"
+        );
+    }
+
+    #[test]
+    fn test_env_overrides_file() {
+        let config = Config::from_toml(
+            "[jobs]\nworkers = 2\n",
+            env(&[
+                ("JOB_QUEUE_WORKERS", "8"),
+                ("API_KEY", "from-env"),
+                (
+                    "SITEMAP_URLS",
+                    "https://a.example/sitemap.xml, https://b.example/sitemap.xml",
+                ),
+                ("SCHEDULE_SYNC", ""),
+                ("EMBEDDING_OLLAMA_PORTS", "11434, 11435"),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(config.jobs.workers, 8);
+        assert_eq!(config.helpscout.api_key.as_deref(), Some("from-env"));
+        assert_eq!(
+            config.source.sitemap_urls,
+            vec![
+                "https://a.example/sitemap.xml",
+                "https://b.example/sitemap.xml"
+            ]
+        );
+        assert_eq!(config.schedule.sync, None);
+        assert_eq!(config.embedding.ollama_ports, vec![11434, 11435]);
+    }
+
+    #[test]
+    fn test_invalid_env_value_names_variable() {
+        let error = Config::from_toml("", env(&[("JOB_QUEUE_WORKERS", "many")])).unwrap_err();
+
+        assert!(
+            matches!(error, ConfigError::Override { ref variable, .. } if variable == "JOB_QUEUE_WORKERS")
+        );
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        assert!(matches!(
+            Config::from_toml("[server]\nprot = 8000\n", env(&[])),
+            Err(ConfigError::Parse(_))
+        ));
+        assert!(matches!(
+            Config::from_toml("[servre]\nport = 8000\n", env(&[])),
+            Err(ConfigError::Parse(_))
+        ));
+        assert!(matches!(
+            Config::from_toml("[search]\nfusion = \"sum\"\n", env(&[])),
+            Err(ConfigError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(valid().validate().is_ok());
+
+        let problems = match Config::default().validate() {
+            Err(ConfigError::Invalid(problems)) => problems,
+            other => panic!("Expected validation errors, got {:?}", other),
+        };
+        assert!(problems.iter().any(|p| p.contains("database.url")));
+        assert!(problems.iter().any(|p| p.contains("helpscout.api_key")));
+
+        let mut config = valid();
+        config.source.kind = "sitemap".to_string();
+        config.source.sitemap_requests_per_minute = 0;
+        config.jobs.retry_base_ms = config.jobs.retry_max_ms + 1;
+        config.embedding.url = "not a url".to_string();
+        config.schedule.sync = Some("every hour".to_string());
+        match config.validate() {
+            Err(ConfigError::Invalid(problems)) => assert_eq!(problems.len(), 5),
+            other => panic!("Expected validation errors, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,149 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+    use serde_json::json;
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+    use uuid::Uuid;
+
+    use backend::models::{Article, ArticleRef};
+    use backend::services::data_processor::api_client::{ApiClient, ApiClientConfig};
+    use backend::services::data_processor::data_sync::unlisted_articles;
+    use backend::services::data_processor::source::{DocumentSource, LocalDirSource, SitemapSource};
+    use backend::services::data_processor::{SyncOutcome, SyncReport};
+
+    fn build_article_ref(updated_at: Option<&str>, last_published_at: Option<&str>) -> ArticleRef {
+        serde_json::from_value(json!({
+            "id": "5215163545667acd25394b5c",
+            "number": 121,
+            "collectionId": "5214c77c45667acd25394b51",
+            "status": "published",
+            "hasDraft": false,
+            "name": "My Article",
+            "publicUrl": "https://docs.helpscout.net/article/100-my-article",
+            "popularity": 4.3,
+            "viewCount": 237,
+            "createdBy": 73423,
+            "updatedBy": null,
+            "createdAt": "2013-08-21T19:34:13Z",
+            "updatedAt": updated_at,
+            "lastPublishedAt": last_published_at
+        }))
+        .unwrap()
+    }
+
+    fn timestamp(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_last_modified_at_uses_latest_timestamp() {
+        let article_ref = build_article_ref(Some("2013-08-22T10:00:00Z"), Some("2013-08-23T10:00:00Z"));
+        assert_eq!(
+            article_ref.last_modified_at(),
+            Some(timestamp("2013-08-23T10:00:00Z"))
+        );
+
+        let article_ref = build_article_ref(None, None);
+        assert_eq!(
+            article_ref.last_modified_at(),
+            Some(timestamp("2013-08-21T19:34:13Z"))
+        );
+    }
+
+    #[test]
+    fn test_is_newer_than_stored_article() {
+        let article_ref = build_article_ref(Some("2013-08-22T10:00:00Z"), None);
+
+        assert!(article_ref.is_newer_than(timestamp("2013-08-22T09:59:59Z")));
+        assert!(!article_ref.is_newer_than(timestamp("2013-08-22T10:00:00Z")));
+        assert!(!article_ref.is_newer_than(timestamp("2024-01-01T00:00:00Z")));
+    }
+
+    #[test]
+    fn test_sync_report_counts() {
+        let mut report = SyncReport::default();
+        report.record(SyncOutcome::Created);
+        report.record(SyncOutcome::Updated);
+        report.record(SyncOutcome::Unchanged);
+        report.record(SyncOutcome::Unchanged);
+        report.record(SyncOutcome::Archived);
+        report.record(SyncOutcome::Skipped);
+
+        let mut total = SyncReport {
+            failed: 1,
+            ..Default::default()
+        };
+        total.merge(&report);
+
+        assert_eq!(total.created, 1);
+        assert_eq!(total.updated, 1);
+        assert_eq!(total.unchanged, 2);
+        assert_eq!(total.archived, 1);
+        assert_eq!(total.skipped, 1);
+        assert_eq!(total.failed, 1);
+        assert_eq!(
+            total.to_string(),
+            "created: 1, updated: 1, unchanged: 2, archived: 1, skipped: 1, failed: 1"
+        );
+    }
+
+    #[test]
+    fn test_sync_outcome_is_stored_as_job_result() {
+        let result = serde_json::to_value(SyncOutcome::Unchanged).unwrap();
+        assert_eq!(result, serde_json::json!("unchanged"));
+        assert_eq!(
+            serde_json::from_value::<SyncOutcome>(result).unwrap(),
+            SyncOutcome::Unchanged
+        );
+    }
+
+    fn stored_article(source_id: &str) -> Article {
+        Article::new(
+            Uuid::new_v4(),
+            "collection".to_string(),
+            Some(source_id.to_string()),
+            source_id.to_string(),
+            "slug".to_string(),
+            None,
+        )
+    }
+
+    fn unlisted(source: &dyn DocumentSource, listed: &[&str]) -> Vec<String> {
+        let stored = [
+            "5215163545667acd25394b5c",
+            "local:guides/kept.md",
+            "local:guides/removed.md",
+            "https://example.com/docs/page",
+        ]
+        .into_iter()
+        .map(stored_article)
+        .collect();
+        let listed: HashSet<String> = listed.iter().map(|id| id.to_string()).collect();
+        unlisted_articles(source, stored, &listed)
+            .into_iter()
+            .filter_map(|article| article.helpscout_article_id)
+            .collect()
+    }
+
+    #[test]
+    fn test_reconcile_only_archives_articles_of_the_synced_source() {
+        let local = LocalDirSource::new(PathBuf::from(env!("CARGO_MANIFEST_DIR"))).unwrap();
+        assert_eq!(
+            unlisted(&local, &["local:guides/kept.md"]),
+            ["local:guides/removed.md"]
+        );
+
+        let helpscout = ApiClient::with_config(
+            Some("http://localhost".to_string()),
+            Some("test_api_key".to_string()),
+            ApiClientConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(unlisted(&helpscout, &[]), ["5215163545667acd25394b5c"]);
+
+        let sitemap =
+            SitemapSource::new(vec!["https://example.com/sitemap.xml".to_string()], 60).unwrap();
+        assert_eq!(unlisted(&sitemap, &[]), ["https://example.com/docs/page"]);
+    }
+}
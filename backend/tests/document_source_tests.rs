@@ -0,0 +1,168 @@
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use chrono::{TimeZone, Utc};
+    use std::fs;
+    use std::path::PathBuf;
+    use uuid::Uuid;
+
+    use backend::services::data_processor::source::sitemap::{extract_page, parse_sitemap};
+    use backend::services::data_processor::source::{
+        slugify, DocumentSource, LocalDirSource, SitemapSource,
+    };
+
+    fn docs_dir() -> Result<PathBuf> {
+        let root = std::env::temp_dir().join(format!("helpdoc-docs-{}", Uuid::new_v4()));
+        fs::create_dir_all(root.join("guides/advanced"))?;
+        fs::create_dir_all(root.join(".git"))?;
+        fs::write(root.join("README.md"), "# Overview\n\nStart here.")?;
+        fs::write(
+            root.join("guides/setup.md"),
+            "---\nkeywords: [setup]\n---\nIntro\n\n# Setting Up\n\nSteps.",
+        )?;
+        fs::write(
+            root.join("guides/advanced/tuning.html"),
+            "<html><head><title>Tuning</title></head><body><p>Fast</p></body></html>",
+        )?;
+        fs::write(root.join("guides/notes.txt"), "ignored")?;
+        fs::write(root.join(".git/HEAD.md"), "# hidden")?;
+        Ok(root)
+    }
+
+    #[tokio::test]
+    async fn test_local_dir_lists_folders_and_documents() -> Result<()> {
+        let root = docs_dir()?;
+        let source = LocalDirSource::new(root.clone())?;
+
+        let collections = source.list_collections().await?;
+        let ids: Vec<&str> = collections
+            .iter()
+            .map(|c| c.helpscout_collection_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["local:.", "local:guides"]);
+
+        let article_refs = source.list_article_refs(&collections[1]).await?;
+        let refs: Vec<(&str, &str)> = article_refs
+            .iter()
+            .map(|r| (r.id.as_str(), r.name.as_str()))
+            .collect();
+        assert_eq!(
+            refs,
+            vec![
+                ("local:guides/advanced/tuning.html", "Tuning"),
+                ("local:guides/setup.md", "Setting Up"),
+            ]
+        );
+        assert!(article_refs.iter().all(|r| r.is_published()));
+        // Nothing Help Scout-specific is made up for local documents
+        assert!(article_refs
+            .iter()
+            .all(|r| r.status.is_none() && r.number.is_none() && r.created_at.is_none()));
+
+        fs::remove_dir_all(root)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_local_dir_fetches_markdown_and_html() -> Result<()> {
+        let root = docs_dir()?;
+        let source = LocalDirSource::new(root.clone())?;
+        let collections = source.list_collections().await?;
+        let article_refs = source.list_article_refs(&collections[1]).await?;
+
+        let html = source
+            .fetch_article(&article_refs[0], &collections[1])
+            .await?;
+        assert_eq!(html.slug, "tuning");
+        assert!(html.html_content.unwrap().contains("<p>Fast</p>"));
+        assert_eq!(html.markdown_content, None);
+
+        let markdown = source
+            .fetch_article(&article_refs[1], &collections[1])
+            .await?;
+        assert_eq!(markdown.title, "Setting Up");
+        assert_eq!(markdown.html_content, None);
+        assert_eq!(
+            markdown.markdown_content.as_deref(),
+            Some("Intro\n\n# Setting Up\n\nSteps.")
+        );
+
+        fs::remove_dir_all(root)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_local_dir_rejects_paths_outside_root() -> Result<()> {
+        let root = docs_dir()?;
+        let source = LocalDirSource::new(root.clone())?;
+        let collections = source.list_collections().await?;
+        let mut article_ref = source.list_article_refs(&collections[1]).await?.remove(0);
+
+        article_ref.id = "local:../../etc/passwd".to_string();
+        assert!(source
+            .fetch_article(&article_ref, &collections[1])
+            .await
+            .is_err());
+
+        fs::remove_dir_all(root)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_sitemap() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+              <url>
+                <loc>https://docs.example.com/guides/setup?lang=en&amp;v=2</loc>
+                <lastmod>2024-09-01T10:00:00+02:00</lastmod>
+              </url>
+              <url><loc>https://docs.example.com/faq</loc><lastmod>2024-08-15</lastmod></url>
+              <url><loc>https://docs.example.com/about</loc></url>
+            </urlset>"#;
+
+        let entries = parse_sitemap(xml);
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[0].loc,
+            "https://docs.example.com/guides/setup?lang=en&v=2"
+        );
+        assert_eq!(
+            entries[0].lastmod,
+            Some(Utc.with_ymd_and_hms(2024, 9, 1, 8, 0, 0).unwrap())
+        );
+        assert_eq!(
+            entries[1].lastmod,
+            Some(Utc.with_ymd_and_hms(2024, 8, 15, 0, 0, 0).unwrap())
+        );
+        assert_eq!(entries[2].lastmod, None);
+    }
+
+    #[test]
+    fn test_sitemap_source_rejects_zero_rate() {
+        let urls = vec!["https://docs.example.com/sitemap.xml".to_string()];
+        assert!(SitemapSource::new(urls.clone(), 0).is_err());
+        assert!(SitemapSource::new(urls, 60).is_ok());
+    }
+
+    #[test]
+    fn test_extract_page_prefers_main_content() {
+        let html = r#"<html><head><title>Setup | Docs</title></head>
+            <body><nav>Menu</nav><main><h1>Setup</h1><p>Install it.</p></main></body></html>"#;
+
+        let (title, content) = extract_page(html);
+
+        assert_eq!(title.as_deref(), Some("Setup"));
+        assert!(content.contains("<p>Install it.</p>"));
+        assert!(!content.contains("Menu"));
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Getting Started: Part 2"), "getting-started-part-2");
+        assert_eq!(
+            slugify("docs.example.com/sitemap.xml"),
+            "docs-example-com-sitemap-xml"
+        );
+    }
+}
@@ -0,0 +1,296 @@
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use mockito::{Matcher, Server};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    use backend::config::EmbeddingConfig;
+    use backend::services::embedding::embedder::{self, Embedder, HttpEmbedder};
+    use backend::services::EmbeddingService;
+
+    fn http_embedder(server: &Server) -> HttpEmbedder {
+        HttpEmbedder::new(&EmbeddingConfig {
+            url: server.url(),
+            ..EmbeddingConfig::default()
+        })
+    }
+
+    /// Returns `[len]` for each text and records the batch sizes it was sent,
+    /// 1 for single texts.
+    #[derive(Default)]
+    struct RecordingEmbedder {
+        batches: Mutex<Vec<usize>>,
+    }
+
+    #[async_trait]
+    impl Embedder for RecordingEmbedder {
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+
+        fn model(&self) -> &str {
+            "recording"
+        }
+
+        fn dimensions(&self) -> usize {
+            1
+        }
+
+        async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            self.batches.lock().unwrap().push(1);
+            Ok(vec![text.len() as f32])
+        }
+
+        async fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+            self.batches.lock().unwrap().push(texts.len());
+            Ok(texts.iter().map(|text| vec![text.len() as f32]).collect())
+        }
+    }
+
+    /// Embeds every text as `[value]`.
+    struct ConstantEmbedder {
+        model: &'static str,
+        value: f32,
+    }
+
+    #[async_trait]
+    impl Embedder for ConstantEmbedder {
+        fn name(&self) -> &'static str {
+            "constant"
+        }
+
+        fn model(&self) -> &str {
+            self.model
+        }
+
+        fn dimensions(&self) -> usize {
+            1
+        }
+
+        async fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+            Ok(vec![self.value])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_embedder_posts_text() -> Result<()> {
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("POST", "/embed")
+            .match_body(Matcher::Json(json!({ "text": "How do refunds work?" })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "embedding": [0.25, -0.5, 1.0] }).to_string())
+            .create_async()
+            .await;
+
+        let embedder = http_embedder(&server);
+        let embedding = embedder.embed("How do refunds work?").await?;
+
+        assert_eq!(embedding, vec![0.25, -0.5, 1.0]);
+        mock.assert_async().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_http_embedder_reports_service_errors() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("POST", "/embed")
+            .with_status(400)
+            .with_body(r#"{"error": "No text provided"}"#)
+            .create_async()
+            .await;
+
+        let embedder = http_embedder(&server);
+        let error = embedder.embed("").await.unwrap_err();
+
+        assert!(error.to_string().contains("No text provided"));
+    }
+
+    #[tokio::test]
+    async fn test_http_embedder_posts_batch() -> Result<()> {
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("POST", "/embed_batch")
+            .match_body(Matcher::Json(json!({ "texts": ["Refunds", "Invoices"] })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "embeddings": [[1.0, 0.0], [0.0, 1.0]] }).to_string())
+            .create_async()
+            .await;
+
+        let embeddings = http_embedder(&server)
+            .embed_batch(&["Refunds", "Invoices"])
+            .await?;
+
+        assert_eq!(embeddings, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+        mock.assert_async().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_http_embedder_falls_back_without_batch_endpoint() -> Result<()> {
+        let mut server = Server::new_async().await;
+        server
+            .mock("POST", "/embed_batch")
+            .with_status(404)
+            .create_async()
+            .await;
+        let single = server
+            .mock("POST", "/embed")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "embedding": [0.5] }).to_string())
+            .expect(2)
+            .create_async()
+            .await;
+
+        let embeddings = http_embedder(&server).embed_batch(&["a", "b"]).await?;
+
+        assert_eq!(embeddings, vec![vec![0.5], vec![0.5]]);
+        single.assert_async().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_generate_embeddings_splits_batches() -> Result<()> {
+        let embedder = Arc::new(RecordingEmbedder::default());
+        let service = EmbeddingService::with_embedder(
+            embedder.clone(),
+            EmbeddingConfig {
+                batch_size: 2,
+                ..EmbeddingConfig::default()
+            },
+        );
+
+        let embeddings = service
+            .generate_embeddings(&["a", "bb", "ccc", "dddd", "eeeee"])
+            .await?;
+
+        assert_eq!(
+            embeddings,
+            vec![vec![1.0], vec![2.0], vec![3.0], vec![4.0], vec![5.0]]
+        );
+        assert_eq!(*embedder.batches.lock().unwrap(), vec![2, 2, 1]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_embed_query_reuses_recent_queries() -> Result<()> {
+        let embedder = Arc::new(RecordingEmbedder::default());
+        let service = EmbeddingService::with_embedder(
+            embedder.clone(),
+            EmbeddingConfig {
+                query_cache_size: 1,
+                ..EmbeddingConfig::default()
+            },
+        );
+
+        assert_eq!(
+            service.embed_query("recording", "refunds").await?,
+            vec![7.0]
+        );
+        assert_eq!(
+            service.embed_query("recording", "refunds").await?,
+            vec![7.0]
+        );
+        assert_eq!(embedder.batches.lock().unwrap().len(), 1);
+
+        // The cache holds one query, so "invoices" evicts "refunds"
+        service.embed_query("recording", "invoices").await?;
+        service.embed_query("recording", "refunds").await?;
+        assert_eq!(embedder.batches.lock().unwrap().len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_embed_query_without_cache() -> Result<()> {
+        let embedder = Arc::new(RecordingEmbedder::default());
+        let service = EmbeddingService::with_embedder(
+            embedder.clone(),
+            EmbeddingConfig {
+                query_cache_size: 0,
+                ..EmbeddingConfig::default()
+            },
+        );
+
+        service.embed_query("recording", "refunds").await?;
+        service.embed_query("recording", "refunds").await?;
+        assert_eq!(embedder.batches.lock().unwrap().len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_embed_query_with_previous_model() -> Result<()> {
+        let service = EmbeddingService::with_embedder(
+            Arc::new(ConstantEmbedder {
+                model: "new",
+                value: 1.0,
+            }),
+            EmbeddingConfig::default(),
+        )
+        .with_previous(Arc::new(ConstantEmbedder {
+            model: "old",
+            value: 2.0,
+        }));
+
+        assert_eq!(service.embed_query("new", "refunds").await?, vec![1.0]);
+        // The query cache keeps each model's vector apart
+        assert_eq!(service.embed_query("old", "refunds").await?, vec![2.0]);
+        assert!(service.embed_query("other", "refunds").await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_generate_embedding_rejects_wrong_dimensions() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("POST", "/embed")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "embedding": [0.25, -0.5, 1.0] }).to_string())
+            .create_async()
+            .await;
+
+        let service = EmbeddingService::with_embedder(
+            Arc::new(http_embedder(&server)),
+            EmbeddingConfig::default(),
+        );
+        let error = service.generate_embedding("Refunds").await.unwrap_err();
+
+        assert!(error.to_string().contains("expected 384"));
+    }
+
+    #[tokio::test]
+    async fn test_from_config_selects_backend() {
+        let config = EmbeddingConfig::default();
+        assert_eq!(embedder::from_config(&config).unwrap().name(), "http");
+
+        let unknown = EmbeddingConfig {
+            backend: "onnx".to_string(),
+            ..EmbeddingConfig::default()
+        };
+        assert!(embedder::from_config(&unknown).is_err());
+
+        let ollama = EmbeddingConfig {
+            backend: "ollama".to_string(),
+            ..EmbeddingConfig::default()
+        };
+        assert_eq!(embedder::from_config(&ollama).unwrap().name(), "ollama");
+        let no_servers = EmbeddingConfig {
+            ollama_ports: Vec::new(),
+            ..ollama
+        };
+        assert!(embedder::from_config(&no_servers).is_err());
+
+        let missing_model = EmbeddingConfig {
+            backend: "candle".to_string(),
+            model_path: None,
+            ..EmbeddingConfig::default()
+        };
+        assert!(embedder::from_config(&missing_model).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod tests {
+    use backend::config::{FusionMethod, SearchConfig};
+    use backend::services::search::fusion::{fuse, Fusion, Ranking};
+
+    const RRF: Fusion = Fusion::Rrf { k: 60.0 };
+
+    fn ranking(results: &[(&'static str, f64)], weight: f64) -> Ranking<&'static str> {
+        Ranking::new(results.to_vec(), weight)
+    }
+
+    fn order(fused: &[(&'static str, f64)]) -> Vec<&'static str> {
+        fused.iter().map(|(item, _)| *item).collect()
+    }
+
+    #[test]
+    fn test_rrf_rewards_results_found_by_both() {
+        let semantic = ranking(&[("a", 0.9), ("b", 0.8), ("c", 0.7)], 1.0);
+        let keyword = ranking(&[("c", 0.5), ("d", 0.4)], 1.0);
+
+        let fused = fuse(vec![semantic, keyword], |item| *item, RRF);
+
+        assert_eq!(order(&fused), vec!["c", "a", "b", "d"]);
+        assert!((fused[0].1 - (1.0 / 63.0 + 1.0 / 61.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_rrf_ignores_score_scales() {
+        // Keyword ranks are tiny next to cosine similarities, which would
+        // drown them in a plain sum
+        let semantic = ranking(&[("a", 0.91), ("b", 0.9)], 1.0);
+        let keyword = ranking(&[("b", 0.02), ("a", 0.01)], 1.0);
+
+        let fused = fuse(vec![semantic, keyword], |item| *item, RRF);
+
+        assert_eq!(order(&fused), vec!["a", "b"]);
+        assert_eq!(fused[0].1, fused[1].1);
+    }
+
+    #[test]
+    fn test_rankings_are_sorted_before_fusion() {
+        let semantic = ranking(&[("low", 0.1), ("high", 0.9)], 1.0);
+
+        let fused = fuse(vec![semantic], |item| *item, RRF);
+
+        assert_eq!(order(&fused), vec!["high", "low"]);
+    }
+
+    #[test]
+    fn test_weights_shift_the_ranking() {
+        let rankings = || {
+            vec![
+                ranking(&[("a", 0.9), ("b", 0.1)], 1.0),
+                ranking(&[("b", 0.9), ("a", 0.1)], 3.0),
+            ]
+        };
+
+        assert_eq!(order(&fuse(rankings(), |item| *item, RRF)), vec!["b", "a"]);
+        assert_eq!(
+            order(&fuse(rankings(), |item| *item, Fusion::Weighted)),
+            vec!["b", "a"]
+        );
+    }
+
+    #[test]
+    fn test_weighted_normalizes_scores() {
+        let semantic = ranking(&[("a", 0.9), ("b", 0.7), ("c", 0.5)], 1.0);
+        let keyword = ranking(&[("c", 0.04), ("b", 0.02)], 1.0);
+
+        let fused = fuse(vec![semantic, keyword], |item| *item, Fusion::Weighted);
+
+        // a and c tie at 1, in the order they were found
+        assert_eq!(order(&fused), vec!["a", "c", "b"]);
+        let scores: Vec<f64> = fused.iter().map(|(_, score)| *score).collect();
+        for (score, expected) in scores.iter().zip([1.0, 1.0, 0.5]) {
+            assert!((score - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_weighted_list_of_equal_scores() {
+        let keyword = ranking(&[("a", 0.3), ("b", 0.3)], 2.0);
+
+        let fused = fuse(vec![keyword], |item| *item, Fusion::Weighted);
+
+        assert_eq!(fused, vec![("a", 2.0), ("b", 2.0)]);
+    }
+
+    #[test]
+    fn test_first_ranking_provides_the_result() {
+        let semantic = Ranking::new(vec![((1, "with passages"), 0.8)], 1.0);
+        let keyword = Ranking::new(vec![((1, "without"), 0.5), ((2, "keyword only"), 0.4)], 1.0);
+
+        let fused = fuse(vec![semantic, keyword], |(id, _)| *id, RRF);
+
+        assert_eq!(fused[0].0, (1, "with passages"));
+        assert_eq!(fused[1].0, (2, "keyword only"));
+    }
+
+    #[test]
+    fn test_empty_rankings() {
+        let fused = fuse(
+            vec![ranking(&[], 1.0), ranking(&[], 1.0)],
+            |item| *item,
+            Fusion::Weighted,
+        );
+        assert!(fused.is_empty());
+    }
+
+    #[test]
+    fn test_config_selects_fusion() {
+        let mut config = SearchConfig::default();
+        assert_eq!(Fusion::from_config(&config), Fusion::Rrf { k: 60.0 });
+
+        config.fusion = FusionMethod::Weighted;
+        assert_eq!(Fusion::from_config(&config), Fusion::Weighted);
+    }
+}
@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+    use backend::services::search::highlight::highlight;
+
+    fn marked(text: &str, query: &str) -> Vec<String> {
+        highlight(text, query)
+            .into_iter()
+            .map(|(start, end)| text.chars().skip(start).take(end - start).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_highlights_query_terms_ignoring_case() {
+        assert_eq!(
+            marked("Refunds are issued to the original card.", "refund card"),
+            vec!["Refunds", "card"]
+        );
+    }
+
+    #[test]
+    fn test_short_terms_and_stopwords_are_ignored() {
+        assert_eq!(
+            marked("How do I add a seat to the team?", "how do I add"),
+            vec!["add"]
+        );
+    }
+
+    #[test]
+    fn test_short_terms_only_match_whole_words() {
+        assert_eq!(
+            marked("Add an address, then adding is done.", "add"),
+            vec!["Add"]
+        );
+    }
+
+    #[test]
+    fn test_ranges_count_characters() {
+        let text = "Überblick: Rückerstattung beantragen";
+        assert_eq!(highlight(text, "rückerstattung"), vec![(11, 25)]);
+    }
+
+    #[test]
+    fn test_no_terms_no_highlights() {
+        assert!(highlight("Some passage", "").is_empty());
+    }
+}
@@ -0,0 +1,181 @@
+#[cfg(test)]
+mod tests {
+    use backend::job::control::ControlState;
+    use backend::utils::backoff_delay;
+    use backend::job::scheduler::ScheduledTask;
+    use backend::job::Job;
+    use backend::job::{JobControl, JobEvent, JobEventKind, ProgressSnapshot};
+    use backend::models::{JobInfo, JobStatus};
+    use chrono::{DateTime, Utc};
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_backoff_delay_doubles_per_attempt() {
+        let base = Duration::from_secs(1);
+        let max = Duration::from_secs(300);
+
+        assert_eq!(backoff_delay(base, max, 1), Duration::from_secs(1));
+        assert_eq!(backoff_delay(base, max, 2), Duration::from_secs(2));
+        assert_eq!(backoff_delay(base, max, 3), Duration::from_secs(4));
+        assert_eq!(backoff_delay(base, max, 5), Duration::from_secs(16));
+    }
+
+    #[test]
+    fn test_backoff_delay_is_capped() {
+        let base = Duration::from_secs(1);
+        let max = Duration::from_secs(60);
+
+        assert_eq!(backoff_delay(base, max, 7), Duration::from_secs(60));
+        assert_eq!(backoff_delay(base, max, 1000), Duration::from_secs(60));
+        assert_eq!(backoff_delay(base, max, 0), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_job_status_round_trip() {
+        for status in [
+            JobStatus::Queued,
+            JobStatus::Running,
+            JobStatus::Completed,
+            JobStatus::Retrying,
+            JobStatus::DeadLetter,
+            JobStatus::Paused,
+            JobStatus::Cancelled,
+        ] {
+            assert_eq!(status.as_str().parse::<JobStatus>().unwrap(), status);
+        }
+        assert!(JobStatus::DeadLetter.is_terminal());
+        assert!(JobStatus::Cancelled.is_terminal());
+        assert!(!JobStatus::Retrying.is_terminal());
+        assert!(!JobStatus::Paused.is_terminal());
+        assert!("unknown".parse::<JobStatus>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_waits_while_paused() {
+        let control = JobControl::new();
+        assert!(control.checkpoint().await.is_ok());
+
+        control.pause();
+        assert_eq!(control.state(), ControlState::Paused);
+        let waiting = tokio::time::timeout(Duration::from_millis(50), control.checkpoint()).await;
+        assert!(waiting.is_err(), "checkpoint returned while paused");
+
+        let resumer = control.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            resumer.resume();
+        });
+        let resumed = tokio::time::timeout(Duration::from_secs(1), control.checkpoint()).await;
+        assert!(matches!(resumed, Ok(Ok(()))));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_releases_paused_checkpoint() {
+        let control = JobControl::new();
+        control.pause();
+
+        let canceller = control.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            canceller.cancel();
+        });
+        let result = tokio::time::timeout(Duration::from_secs(1), control.checkpoint()).await;
+        assert!(matches!(result, Ok(Err(_))));
+
+        // Cancellation is final
+        control.resume();
+        assert!(control.is_cancelled());
+    }
+
+    #[test]
+    fn test_control_follows_stored_requests() {
+        let control = JobControl::new();
+
+        control.follow(JobStatus::Paused, false);
+        assert_eq!(control.state(), ControlState::Paused);
+        control.follow(JobStatus::Running, false);
+        assert_eq!(control.state(), ControlState::Running);
+
+        control.follow(JobStatus::Running, true);
+        assert!(control.is_cancelled());
+        control.follow(JobStatus::Running, false);
+        assert!(control.is_cancelled());
+
+        let control = JobControl::new();
+        control.follow(JobStatus::Cancelled, false);
+        assert!(control.is_cancelled());
+    }
+
+    #[test]
+    fn test_live_worker_needs_a_recent_heartbeat() {
+        let lease = Duration::from_secs(300);
+        let mut job = JobInfo::new("sync_all", serde_json::json!({}), 3);
+        assert!(!job.has_live_worker(lease));
+
+        job.heartbeat_at = Some(Utc::now() - chrono::Duration::seconds(10));
+        assert!(job.has_live_worker(lease));
+
+        job.heartbeat_at = Some(Utc::now() - chrono::Duration::seconds(301));
+        assert!(!job.has_live_worker(lease));
+    }
+
+    #[test]
+    fn test_progress_snapshot_percentage_and_eta() {
+        let progress = ProgressSnapshot::compute(10, 3, 1, Duration::from_secs(8));
+
+        assert_eq!(progress.percentage, 40.0);
+        // 2s per finished item, 6 items left
+        assert_eq!(progress.eta_seconds, Some(12));
+    }
+
+    #[test]
+    fn test_progress_snapshot_edge_cases() {
+        let not_started = ProgressSnapshot::compute(5, 0, 0, Duration::from_secs(3));
+        assert_eq!(not_started.percentage, 0.0);
+        assert_eq!(not_started.eta_seconds, None);
+
+        let empty = ProgressSnapshot::compute(0, 0, 0, Duration::ZERO);
+        assert_eq!(empty.percentage, 100.0);
+    }
+
+    #[test]
+    fn test_job_event_serialization() {
+        let job_id = Uuid::new_v4();
+        let event = JobEvent {
+            job_id,
+            parent_id: None,
+            kind: JobEventKind::ItemFailed {
+                item: "article-1".to_string(),
+                reason: "timeout".to_string(),
+                progress: ProgressSnapshot::compute(2, 0, 1, Duration::from_secs(1)),
+            },
+        };
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(event.kind.name(), "item_failed");
+        assert_eq!(json["event"], "item_failed");
+        assert_eq!(json["job_id"], job_id.to_string());
+        assert_eq!(json["reason"], "timeout");
+        assert_eq!(json["progress"]["percentage"], 50.0);
+    }
+
+    #[test]
+    fn test_scheduled_task_next_run() {
+        let task = ScheduledTask::new("sync", "0 0 * * * *", Job::BackfillEmbeddings).unwrap();
+        let after = DateTime::parse_from_rfc3339("2024-09-20T10:15:30Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let next = task.next_after(after).unwrap();
+        assert_eq!(next.to_rfc3339(), "2024-09-20T11:00:00+00:00");
+        let following = task.next_after(next).unwrap();
+        assert_eq!(following.to_rfc3339(), "2024-09-20T12:00:00+00:00");
+    }
+
+    #[test]
+    fn test_scheduled_task_rejects_invalid_expression() {
+        let result = ScheduledTask::new("sync", "every hour", Job::BackfillEmbeddings);
+        assert!(result.is_err());
+    }
+}
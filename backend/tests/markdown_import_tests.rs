@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use std::fs;
+    use uuid::Uuid;
+
+    use backend::models::ArticleRef;
+    use backend::services::data_processor::markdown_import::MarkdownDocument;
+    use backend::services::data_processor::source::front_matter::{
+        parse_front_matter, FrontMatter,
+    };
+    use backend::services::data_processor::source::local_dir::LocalDirSource;
+    use backend::services::data_processor::source::DocumentSource;
+    use backend::utils::content_hash;
+
+    #[test]
+    fn test_parse_front_matter() {
+        let content = "---\ntitle: \"Restarting the queue\"\nslug: restart-queue\nkeywords: [ops, queue, 'on-call']\n---\n# Heading\n\nBody";
+
+        let (front_matter, body) = parse_front_matter(content);
+
+        assert_eq!(
+            front_matter,
+            FrontMatter {
+                title: Some("Restarting the queue".to_string()),
+                slug: Some("restart-queue".to_string()),
+                keywords: Some(vec!["ops".into(), "queue".into(), "on-call".into()]),
+            }
+        );
+        assert_eq!(body, "# Heading\n\nBody");
+    }
+
+    #[test]
+    fn test_parse_front_matter_block_list_keywords() {
+        let content = "---\nkeywords:\n  - database\n  - \"failover\"\nowner: sre\n---\nBody";
+
+        let (front_matter, body) = parse_front_matter(content);
+
+        assert_eq!(
+            front_matter.keywords,
+            Some(vec!["database".to_string(), "failover".to_string()])
+        );
+        assert_eq!(front_matter.title, None);
+        assert_eq!(body, "Body");
+    }
+
+    #[test]
+    fn test_content_without_front_matter_is_untouched() {
+        let unterminated = "---\ntitle: Oops\n\nNo closing fence";
+        assert_eq!(
+            parse_front_matter(unterminated),
+            (FrontMatter::default(), unterminated)
+        );
+        assert_eq!(
+            parse_front_matter("Plain text"),
+            (FrontMatter::default(), "Plain text")
+        );
+    }
+
+    #[test]
+    fn test_document_takes_slug_and_keywords_from_front_matter() {
+        let prefix = "markdown:/srv/runbooks/";
+        let article_ref = ArticleRef::new(
+            format!("{}db/Failover Steps.md", prefix),
+            format!("{}db", prefix),
+            "Failover".to_string(),
+            "file:///srv/runbooks/db/Failover Steps.md".to_string(),
+            None,
+        );
+
+        let document = MarkdownDocument::parse(prefix, &article_ref, "# Failover\n\nSteps");
+        assert_eq!(document.relative_path, "db/Failover Steps.md");
+        assert_eq!(document.title, "Failover");
+        assert_eq!(document.slug, "failover-steps");
+        assert_eq!(document.keywords, None);
+        assert_eq!(document.body, "# Failover\n\nSteps");
+
+        let tagged = MarkdownDocument::parse(
+            prefix,
+            &article_ref,
+            "---\nslug: failover\nkeywords: [db]\n---\nSteps",
+        );
+        assert_eq!(tagged.slug, "failover");
+        assert_eq!(tagged.keywords, Some(vec!["db".to_string()]));
+        assert_eq!(tagged.body, "Steps");
+
+        let untagged =
+            MarkdownDocument::parse(prefix, &article_ref, "---\nkeywords: []\n---\nSteps");
+        assert_eq!(untagged.keywords, Some(vec![]));
+    }
+
+    #[test]
+    fn test_content_hash_covers_front_matter() {
+        let original = "---\nkeywords: [a]\n---\nBody";
+        let retagged = "---\nkeywords: [b]\n---\nBody";
+
+        assert_eq!(content_hash(original), content_hash(original));
+        assert_ne!(content_hash(original), content_hash(retagged));
+        assert_eq!(content_hash(original).len(), 64);
+    }
+
+    #[tokio::test]
+    async fn test_source_ids_carry_the_prefix_and_front_matter_titles() -> Result<()> {
+        let root = std::env::temp_dir().join(format!("helpdoc-runbooks-{}", Uuid::new_v4()));
+        fs::create_dir_all(root.join("db"))?;
+        fs::create_dir_all(root.join(".github"))?;
+        fs::write(
+            root.join("db/failover.md"),
+            "---\ntitle: Failover\n---\n# Steps",
+        )?;
+        fs::write(root.join(".github/template.md"), "# Hidden")?;
+        let prefix = format!("markdown:{}/", root.canonicalize()?.display());
+
+        let source = LocalDirSource::with_id_prefix(root.clone(), &prefix)?;
+        let collections = source.list_collections().await?;
+        assert_eq!(collections.len(), 1);
+        assert_eq!(
+            collections[0].helpscout_collection_id,
+            format!("{}db", prefix)
+        );
+
+        let refs = source.list_article_refs(&collections[0]).await?;
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0].id, format!("{}db/failover.md", prefix));
+        assert_eq!(refs[0].name, "Failover");
+
+        fs::remove_dir_all(root)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test as actix_test, web, App};
+    use backend::config::{Config, DatabaseConfig};
+    use backend::db::{init_pool_with, DbPool};
+    use backend::job::JobQueue;
+    use backend::models::JobInfo;
+    use backend::routes::webhook::helpscout_webhook;
+    use backend::schema::{job_status_history, jobs};
+    use backend::services::data_processor::source::local_dir::LocalDirSource;
+    use backend::services::data_processor::webhook::{sign, EVENT_HEADER, SIGNATURE_HEADER};
+    use backend::services::{DataProcessor, MetadataGenerator};
+    use diesel::prelude::*;
+    use dotenv::dotenv;
+    use serde_json::Value;
+    use std::env;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    const SECRET: &str = "test-webhook-secret";
+    const PAYLOAD: &[u8] = include_bytes!("fixtures/helpscout_article_updated.json");
+    const DELETED: &[u8] = br#"{"id": "5215163545667acd25394b5c"}"#;
+
+    fn pool() -> Arc<DbPool> {
+        dotenv().ok();
+        Arc::new(init_pool_with(&DatabaseConfig {
+            url: Some(env::var("DATABASE_URL").expect("DATABASE_URL must be set")),
+            max_connections: 2,
+        }))
+    }
+
+    fn services() -> (Arc<DataProcessor>, Arc<JobQueue>) {
+        let pool = pool();
+        // Syncs from a local directory and has no Help Scout source, so the
+        // webhook routes never fetch anything
+        let source = LocalDirSource::new(PathBuf::from(env!("CARGO_MANIFEST_DIR"))).unwrap();
+        let data_processor =
+            Arc::new(DataProcessor::with_source(pool.clone(), Arc::new(source)).unwrap());
+        let metadata_generator = Arc::new(MetadataGenerator::with_processor(
+            data_processor.clone(),
+            pool.clone(),
+            1,
+        ));
+        let job_queue = Arc::new(JobQueue::with_config(
+            pool.clone(),
+            data_processor.clone(),
+            metadata_generator,
+            &Config::default().jobs,
+        ));
+        (data_processor, job_queue)
+    }
+
+    fn config(secret: Option<&str>) -> Arc<Config> {
+        let mut config = Config::default();
+        config.helpscout.webhook_secret = secret.map(str::to_string);
+        Arc::new(config)
+    }
+
+    async fn post(
+        config: Arc<Config>,
+        event: &str,
+        body: &'static [u8],
+        signature: Option<String>,
+    ) -> (StatusCode, web::Bytes) {
+        let (data_processor, job_queue) = services();
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(data_processor))
+                .app_data(web::Data::new(job_queue))
+                .app_data(web::Data::new(config))
+                .service(helpscout_webhook),
+        )
+        .await;
+
+        let mut request = actix_test::TestRequest::post()
+            .uri("/webhooks/helpscout")
+            .insert_header((EVENT_HEADER, event))
+            .set_payload(body);
+        if let Some(signature) = signature {
+            request = request.insert_header((SIGNATURE_HEADER, signature));
+        }
+        let response = actix_test::call_service(&app, request.to_request()).await;
+        let status = response.status();
+        (status, actix_test::read_body(response).await)
+    }
+
+    #[actix_web::test]
+    async fn test_rejects_requests_without_a_configured_secret() {
+        let signature = Some(sign(SECRET.as_bytes(), PAYLOAD));
+        let (status, _) = post(config(None), "docs.article.updated", PAYLOAD, signature).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[actix_web::test]
+    async fn test_rejects_missing_or_wrong_signatures() {
+        let (status, _) = post(config(Some(SECRET)), "docs.article.updated", PAYLOAD, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let signature = Some(sign(b"other-secret", PAYLOAD));
+        let (status, _) = post(
+            config(Some(SECRET)),
+            "docs.article.updated",
+            PAYLOAD,
+            signature,
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn test_acknowledges_unsupported_events() {
+        let signature = Some(sign(SECRET.as_bytes(), PAYLOAD));
+        let (status, body) = post(config(Some(SECRET)), "convo.created", PAYLOAD, signature).await;
+        assert_eq!(status, StatusCode::OK);
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["status"], "ignored");
+    }
+
+    #[actix_web::test]
+    async fn test_article_events_need_a_helpscout_source() {
+        let signature = Some(sign(SECRET.as_bytes(), PAYLOAD));
+        let (status, _) = post(
+            config(Some(SECRET)),
+            "docs.article.updated",
+            PAYLOAD,
+            signature,
+        )
+        .await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[actix_web::test]
+    async fn test_rejects_malformed_payloads() {
+        let body: &'static [u8] = br#"{"id": 1}"#;
+        let signature = Some(sign(SECRET.as_bytes(), body));
+        let (status, _) = post(
+            config(Some(SECRET)),
+            "docs.article.deleted",
+            body,
+            signature,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_enqueues_a_job_for_signed_events() {
+        let signature = Some(sign(SECRET.as_bytes(), DELETED));
+        let (status, body) = post(
+            config(Some(SECRET)),
+            "docs.article.deleted",
+            DELETED,
+            signature,
+        )
+        .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        let job_id = body["job_id"].as_str().unwrap().parse().unwrap();
+        let mut conn = pool().get().unwrap();
+        let job = JobInfo::find(&mut conn, job_id).unwrap().unwrap();
+        assert_eq!(job.job_type, "archive_article");
+
+        // No workers run here, so the job would otherwise stay queued
+        diesel::delete(job_status_history::table.filter(job_status_history::job_id.eq(job_id)))
+            .execute(&mut conn)
+            .unwrap();
+        diesel::delete(jobs::table.find(job_id))
+            .execute(&mut conn)
+            .unwrap();
+    }
+}
@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod tests {
+    use backend::errors::WebhookError;
+    use backend::services::data_processor::webhook::{
+        parse_event, sign, verify_signature, WebhookEvent,
+    };
+
+    const SECRET: &[u8] = b"test-webhook-secret";
+    const PAYLOAD: &[u8] = include_bytes!("fixtures/helpscout_article_updated.json");
+    const SIGNATURE: &str = include_str!("fixtures/helpscout_article_updated.sig");
+
+    #[test]
+    fn test_fixture_signature_verifies() {
+        assert!(verify_signature(SECRET, PAYLOAD, SIGNATURE).is_ok());
+        assert_eq!(sign(SECRET, PAYLOAD), SIGNATURE.trim());
+    }
+
+    #[test]
+    fn test_rejects_tampered_payload_or_wrong_secret() {
+        let mut tampered = PAYLOAD.to_vec();
+        tampered.extend_from_slice(b" ");
+
+        assert!(matches!(
+            verify_signature(SECRET, &tampered, SIGNATURE),
+            Err(WebhookError::InvalidSignature)
+        ));
+        assert!(matches!(
+            verify_signature(b"other-secret", PAYLOAD, SIGNATURE),
+            Err(WebhookError::InvalidSignature)
+        ));
+        assert!(matches!(
+            verify_signature(SECRET, PAYLOAD, "not base64!"),
+            Err(WebhookError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_parse_article_updated_event() {
+        match parse_event("docs.article.updated", PAYLOAD).unwrap() {
+            WebhookEvent::ArticleUpdated(article_ref) => {
+                assert_eq!(article_ref.id, "5215163545667acd25394b5c");
+                assert_eq!(article_ref.collection_id, "5214c77c45667acd25394b51");
+                assert!(article_ref.is_published());
+            }
+            other => panic!("Unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_article_deleted_event() {
+        let body = br#"{"id": "5215163545667acd25394b5c"}"#;
+        match parse_event("docs.article.deleted", body).unwrap() {
+            WebhookEvent::ArticleDeleted(id) => assert_eq!(id, "5215163545667acd25394b5c"),
+            other => panic!("Unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_event_and_bad_payload() {
+        assert!(matches!(
+            parse_event("convo.created", PAYLOAD),
+            Err(WebhookError::UnsupportedEvent(_))
+        ));
+        assert!(matches!(
+            parse_event("docs.article.updated", br#"{"id": 1}"#),
+            Err(WebhookError::InvalidPayload(_))
+        ));
+    }
+}